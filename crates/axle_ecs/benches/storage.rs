@@ -0,0 +1,157 @@
+//! Compares iterating components with the old storage, where every component was a separate
+//! `Rc<RefCell<dyn Any>>` allocation, against the sparse set storage used by `Entities`.
+
+use std::{any::{Any, TypeId}, cell::RefCell, collections::HashMap, rc::Rc};
+
+use axle_ecs::{entities::query_entity::QueryEntity, resources::Resources, World};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use eyre::Result;
+
+const ENTITY_COUNT: usize = 100_000;
+
+struct Position(f32, f32);
+struct Velocity(f32, f32);
+struct Health;
+
+type OldComponents = HashMap<TypeId, Vec<Option<Rc<RefCell<dyn Any>>>>>;
+
+/// The storage `Entities` used before the sparse sets, kept here as a reference point.
+#[derive(Default)]
+struct OldStorage {
+    components: OldComponents,
+    bit_masks: HashMap<TypeId, u32>,
+    map: Vec<u32>,
+}
+
+impl OldStorage {
+    fn register_component<T: Any>(&mut self) {
+        let type_id = TypeId::of::<T>();
+
+        self.components.insert(type_id, vec![]);
+        self.bit_masks.insert(type_id, 1 << self.bit_masks.len());
+    }
+
+    fn create_entity(&mut self) -> usize {
+        self.components
+            .values_mut()
+            .for_each(|components| components.push(None));
+        self.map.push(0);
+
+        self.map.len() - 1
+    }
+
+    fn add_component(&mut self, index: usize, data: impl Any) {
+        let type_id = data.type_id();
+
+        self.components.get_mut(&type_id).unwrap()[index] = Some(Rc::new(RefCell::new(data)));
+        self.map[index] |= self.bit_masks[&type_id];
+    }
+
+    /// Moves the entities the same way `Query::run_entity` and `QueryEntity::get_component` did
+    /// with this storage: first the matching entities are collected and then every component is
+    /// looked up by its type.
+    fn move_entities(&self) {
+        let mask = self.bit_masks[&TypeId::of::<Position>()] | self.bit_masks[&TypeId::of::<Velocity>()];
+        let indexes: Vec<usize> = self.map
+            .iter()
+            .enumerate()
+            .filter(|(_, entity_map)| *entity_map & mask == mask)
+            .map(|(index, _)| index)
+            .collect();
+
+        for index in indexes {
+            let position = self.components[&TypeId::of::<Position>()][index].as_ref().unwrap();
+            let mut position = position.borrow_mut();
+            let position = position.downcast_mut::<Position>().unwrap();
+            let velocity = self.components[&TypeId::of::<Velocity>()][index].as_ref().unwrap();
+            let velocity = velocity.borrow();
+            let velocity = velocity.downcast_ref::<Velocity>().unwrap();
+
+            position.0 += velocity.0;
+            position.1 += velocity.1;
+        }
+    }
+}
+
+fn old_storage() -> OldStorage {
+    let mut storage = OldStorage::default();
+    storage.register_component::<Position>();
+    storage.register_component::<Velocity>();
+    storage.register_component::<Health>();
+
+    for i in 0..ENTITY_COUNT {
+        let index = storage.create_entity();
+        storage.add_component(index, Position(0.0, 0.0));
+        storage.add_component(index, Velocity(1.0, 1.0));
+
+        if i % 2 == 0 {
+            storage.add_component(index, Health);
+        }
+    }
+
+    storage
+}
+
+fn new_world() -> Result<World> {
+    let mut world = World::new();
+    world.register_component::<Position>();
+    world.register_component::<Velocity>();
+    world.register_component::<Health>();
+
+    for i in 0..ENTITY_COUNT {
+        let entities = world
+            .create_entity()
+            .with_component(Position(0.0, 0.0))?
+            .with_component(Velocity(1.0, 1.0))?;
+
+        if i % 2 == 0 {
+            entities.with_component(Health)?;
+        }
+    }
+
+    world
+        .create_system(move_entities)
+        .with_component::<Position>()?
+        .with_component::<Velocity>()?;
+
+    Ok(world)
+}
+
+fn move_entities(entities: &Vec<QueryEntity>, _: &mut Resources) -> Result<()> {
+    for entity in entities {
+        let mut position = entity.get_component_mut::<Position>()?;
+        let velocity = entity.get_component::<Velocity>()?;
+
+        position.0 += velocity.0;
+        position.1 += velocity.1;
+    }
+
+    Ok(())
+}
+
+fn iterate_components(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iterate_100k_entities");
+
+    let storage = old_storage();
+    group.bench_function("old_storage", |b| b.iter(|| black_box(&storage).move_entities()));
+
+    let mut world = new_world().unwrap();
+    group.bench_function("sparse_set_storage", |b| {
+        b.iter(|| black_box(&mut world).run_all_systems().unwrap())
+    });
+
+    group.finish();
+}
+
+fn create_entities(c: &mut Criterion) {
+    let mut group = c.benchmark_group("create_100k_entities");
+    group.sample_size(10);
+
+    group.bench_function("old_storage", |b| b.iter(|| black_box(old_storage())));
+    group.bench_function("sparse_set_storage", |b| b.iter(|| black_box(new_world().unwrap())));
+
+    group.finish();
+}
+
+criterion_group!(benches, iterate_components, create_entities);
+criterion_main!(benches);
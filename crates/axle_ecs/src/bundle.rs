@@ -0,0 +1,246 @@
+use std::any::Any;
+use eyre::Result;
+
+use crate::entities::Entities;
+use crate::prelude::*;
+use crate::sync::MaybeSendSync;
+
+pub use axle_ecs_derive::Bundle;
+
+/// A group of components that are added to an entity at once.
+///
+/// It is implemented for the tuples of up to twelve components, and can be derived for structs
+/// whose fields are components. The fields marked with `#[bundle]` are bundles themselves, and
+/// their components are added instead of the field.
+///
+/// # Example
+///
+/// ```
+/// use axle_ecs::{bundle::Bundle, World};
+///
+/// struct Health(u32);
+/// struct Speed(f32);
+/// struct Player;
+///
+/// #[derive(Bundle)]
+/// struct Character {
+///     health: Health,
+///     speed: Speed,
+/// }
+///
+/// #[derive(Bundle)]
+/// struct PlayerBundle {
+///     #[bundle]
+///     character: Character,
+///     player: Player,
+/// }
+///
+/// let mut world = World::new();
+/// let player = world.spawn(PlayerBundle {
+///     character: Character { health: Health(100), speed: Speed(2.0) },
+///     player: Player,
+/// }).unwrap();
+///
+/// world.add_bundle_to_entity_by_id((Health(50), Speed(1.0)), player).unwrap();
+///
+/// assert_eq!(world.query::<(&Health, &Speed, &Player)>().unwrap().count(), 1);
+/// ```
+pub trait Bundle: MaybeSendSync + 'static {
+    /// Adds every component of the bundle to an entity, replacing the ones of the same type the
+    /// entity already had.
+    ///
+    /// # Arguments
+    ///
+    /// * `entities` - The entities.
+    /// * `entity` - The handle of the entity.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the entity doesn't exist, an error is returned.
+    fn add_to_entity(self, entities: &mut Entities, entity: Entity) -> Result<()>;
+}
+
+/// Implements `Bundle` for the tuples of the given components.
+macro_rules! impl_bundle {
+    ($($component:ident),*) => {
+        impl<$($component: Any + MaybeSendSync),*> Bundle for ($($component,)*) {
+            #[allow(non_snake_case, unused_variables)]
+            fn add_to_entity(self, entities: &mut Entities, entity: Entity) -> Result<()> {
+                let ($($component,)*) = self;
+                $(entities.add_component_by_entity_id($component, entity)?;)*
+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_bundle!();
+impl_bundle!(C1);
+impl_bundle!(C1, C2);
+impl_bundle!(C1, C2, C3);
+impl_bundle!(C1, C2, C3, C4);
+impl_bundle!(C1, C2, C3, C4, C5);
+impl_bundle!(C1, C2, C3, C4, C5, C6);
+impl_bundle!(C1, C2, C3, C4, C5, C6, C7);
+impl_bundle!(C1, C2, C3, C4, C5, C6, C7, C8);
+impl_bundle!(C1, C2, C3, C4, C5, C6, C7, C8, C9);
+impl_bundle!(C1, C2, C3, C4, C5, C6, C7, C8, C9, C10);
+impl_bundle!(C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11);
+impl_bundle!(C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12);
+
+impl Entities {
+    /// Adds every component of a bundle to the current entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `bundle` - The components to add.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains a mutable reference to the `Entities` struct if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the `create_entity` function was not called before, an error is returned.
+    pub fn with_bundle(&mut self, bundle: impl Bundle) -> Result<&mut Self> {
+        let entity = self.entity();
+
+        if !self.is_alive(entity) {
+            return Err(CustomErrors::CreateEntityNeverCalled.into());
+        }
+
+        bundle.add_to_entity(self, entity)?;
+
+        Ok(self)
+    }
+
+    /// Adds every component of a bundle to an entity by the given handle, replacing the ones of
+    /// the same type the entity already had.
+    ///
+    /// # Arguments
+    ///
+    /// * `bundle` - The components to add.
+    /// * `entity` - The handle of the entity to add the components to.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the entity doesn't exist, an error is returned and no component is added.
+    pub fn add_bundle_by_entity_id(&mut self, bundle: impl Bundle, entity: Entity) -> Result<()> {
+        self.validate_entity(entity)?;
+
+        bundle.add_to_entity(self, entity)
+    }
+}
+
+impl World {
+    /// Creates a new entity with every component of a bundle.
+    ///
+    /// # Arguments
+    ///
+    /// * `bundle` - The components of the entity.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the handle of the new entity if succeeds or an error if it fails.
+    pub fn spawn(&mut self, bundle: impl Bundle) -> Result<Entity> {
+        Ok(self.entities.create_entity().with_bundle(bundle)?.entity())
+    }
+
+    /// Adds every component of a bundle to an entity by the given handle, replacing the ones of
+    /// the same type the entity already had.
+    ///
+    /// # Arguments
+    ///
+    /// * `bundle` - The components to add.
+    /// * `entity` - The handle of the entity to add the components to.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the entity doesn't exist, an error is returned and no component is added.
+    pub fn add_bundle_to_entity_by_id(&mut self, bundle: impl Bundle, entity: Entity) -> Result<()> {
+        self.entities.add_bundle_by_entity_id(bundle, entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundles_add_all_their_components() -> Result<()> {
+        let mut entities = Entities::default();
+        assert!(entities.with_bundle((Health(1), Speed(1.0))).is_err());
+
+        let first = entities
+            .create_entity()
+            .with_bundle(Character { health: Health(10), speed: Speed(2.0) })?
+            .entity();
+        let second = entities
+            .create_entity()
+            .with_bundle(Player { character: Character { health: Health(20), speed: Speed(3.0) }, marker: Marker })?
+            .entity();
+
+        // Bundles replace the components the entity already had
+        entities.add_bundle_by_entity_id((Health(15), Marker), first)?;
+
+        let healths = entities.get_storage::<Health>().unwrap();
+        assert_eq!(healths.get(first.index()).map(|health| health.borrow().0), Some(15));
+        assert_eq!(healths.get(second.index()).map(|health| health.borrow().0), Some(20));
+        assert_eq!(entities.get_storage::<Speed>().unwrap().iter().count(), 2);
+        assert_eq!(entities.get_storage::<Marker>().unwrap().iter().count(), 2);
+
+        entities.delete_entity_by_id(second)?;
+        assert!(entities.add_bundle_by_entity_id((Health(1),), second).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn commands_add_bundles() -> Result<()> {
+        let mut world = World::new();
+        let existing = world.spawn((Health(5),))?;
+
+        let mut commands = Commands::new(&world.entities);
+        commands.create_entity().with_bundle(Character { health: Health(1), speed: Speed(1.0) })?;
+        commands.add_bundle_to_entity_by_id(Wrapper(Speed(4.0), Marker), existing);
+        commands.apply(&mut world.entities)?;
+
+        assert_eq!(world.query::<(&Health, &Speed)>()?.count(), 2);
+        assert_eq!(world.query::<&Marker>()?.count(), 1);
+
+        Ok(())
+    }
+
+    struct Health(u32);
+    struct Speed(#[allow(dead_code)] f32);
+    struct Marker;
+
+    #[derive(Bundle)]
+    struct Character {
+        health: Health,
+        speed: Speed,
+    }
+
+    #[derive(Bundle)]
+    struct Player {
+        #[bundle]
+        character: Character,
+        marker: Marker,
+    }
+
+    #[derive(Bundle)]
+    struct Wrapper(Speed, Marker);
+}
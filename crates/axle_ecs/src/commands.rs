@@ -0,0 +1,388 @@
+use std::any::Any;
+use eyre::Result;
+
+use crate::bundle::Bundle;
+use crate::entities::{allocator::EntityAllocator, Entities};
+use crate::prelude::*;
+use crate::sync::MaybeSendSync;
+
+/// A structural change to the entities waiting to be applied.
+#[cfg(not(feature = "parallel"))]
+type Command = Box<dyn FnOnce(&mut Entities) -> Result<()>>;
+
+/// A structural change to the entities waiting to be applied.
+#[cfg(feature = "parallel")]
+type Command = Box<dyn FnOnce(&mut Entities) -> Result<()> + Send + Sync>;
+
+/// A buffer of structural changes to the entities.
+///
+/// Systems only get shared access to the entities, so they can't create or delete entities, or
+/// add or remove components, directly. Instead they queue those changes in the `Commands`
+/// resource of the world, and the world applies them right after the system finishes.
+///
+/// The handle of an entity queued for creation is reserved right away, so it can be used in the
+/// commands queued after it, for example to make the new entity the child of another one.
+///
+/// # Example
+///
+/// ```
+/// use axle_ecs::{commands::Commands, entities::query_entity::QueryEntity, resources::Resources, World};
+///
+/// let mut world = World::new();
+/// world.register_component::<u32>();
+///
+/// world.create_system(&|_: &Vec<QueryEntity>, resources: &mut Resources| {
+///     let commands = resources.get_mut::<Commands>().unwrap();
+///     let parent = commands.create_entity().with_component(10_u32)?.entity()?;
+///     let child = commands.create_entity().with_component(20_u32)?.entity()?;
+///     commands.set_parent(child, parent);
+///
+///     Ok(())
+/// });
+///
+/// world.run_all_systems().unwrap();
+///
+/// assert_eq!(world.query::<&u32>().unwrap().count(), 2);
+/// ```
+pub struct Commands {
+    /// The queued changes, in the order they will be applied.
+    queue: Vec<Command>,
+    /// The allocator the handles of the entities queued for creation are reserved from.
+    allocator: EntityAllocator,
+    /// The handle of the last entity queued for creation since the commands were last applied.
+    creating_entity: Option<Entity>,
+    /// The handles reserved since the commands were last applied, given back if the commands are
+    /// dropped without being applied.
+    reserved: Vec<Entity>,
+}
+
+impl Commands {
+    /// Creates an empty commands buffer for the given entities.
+    ///
+    /// # Arguments
+    ///
+    /// * `entities` - The entities the handles of the entities queued for creation are reserved from.
+    ///
+    /// # Returns
+    ///
+    /// A new `Commands` struct.
+    pub fn new(entities: &Entities) -> Self {
+        Self {
+            queue: vec![],
+            allocator: entities.allocator().clone(),
+            creating_entity: None,
+            reserved: vec![],
+        }
+    }
+
+    /// Queues the creation of a new entity and returns a mutable reference to the `Commands`
+    /// struct so that you can queue adding components to it.
+    ///
+    /// The handle of the entity is reserved right away and can be retrieved with the `entity`
+    /// function.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the `Commands` struct.
+    pub fn create_entity(&mut self) -> &mut Self {
+        let entity = self.allocator.allocate();
+
+        self.queue.push(Box::new(move |entities| {
+            entities.create_reserved_entity(entity);
+            Ok(())
+        }));
+        self.creating_entity = Some(entity);
+        self.reserved.push(entity);
+
+        self
+    }
+
+    /// Gets the handle reserved for the last entity queued for creation.
+    ///
+    /// The entity only exists once the commands are applied, but its handle can already be used
+    /// in the commands queued after it.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the handle of the entity if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the `create_entity` function was not called before, an error is returned.
+    pub fn entity(&self) -> Result<Entity> {
+        self.creating_entity.ok_or_else(|| CustomErrors::CreateEntityNeverCalled.into())
+    }
+
+    /// Queues adding a component to the last entity queued for creation.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The component's data.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains a mutable reference to the `Commands` struct if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the `create_entity` function was not called before, an error is returned.
+    pub fn with_component<T: Any + MaybeSendSync>(&mut self, data: T) -> Result<&mut Self> {
+        let entity = self.entity()?;
+        self.add_component_to_entity_by_id(data, entity);
+
+        Ok(self)
+    }
+
+    /// Queues adding every component of a bundle to the last entity queued for creation.
+    ///
+    /// # Arguments
+    ///
+    /// * `bundle` - The components to add.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains a mutable reference to the `Commands` struct if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the `create_entity` function was not called before, an error is returned.
+    pub fn with_bundle(&mut self, bundle: impl Bundle) -> Result<&mut Self> {
+        let entity = self.entity()?;
+        self.add_bundle_to_entity_by_id(bundle, entity);
+
+        Ok(self)
+    }
+
+    /// Queues deleting an entity by its handle.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity to delete.
+    pub fn delete_entity_by_id(&mut self, entity: Entity) {
+        self.queue.push(Box::new(move |entities| entities.delete_entity_by_id(entity)));
+    }
+
+    /// Queues deleting an entity together with all its descendants.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity to delete.
+    pub fn delete_entity_recursive(&mut self, entity: Entity) {
+        self.queue.push(Box::new(move |entities| entities.delete_entity_recursive(entity)));
+    }
+
+    /// Queues making an entity the child of another one.
+    ///
+    /// # Arguments
+    ///
+    /// * `child` - The handle of the entity to attach.
+    /// * `parent` - The handle of the new parent.
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) {
+        self.queue.push(Box::new(move |entities| entities.set_parent(child, parent)));
+    }
+
+    /// Queues detaching an entity from its parent.
+    ///
+    /// # Arguments
+    ///
+    /// * `child` - The handle of the entity to detach.
+    pub fn remove_parent(&mut self, child: Entity) {
+        self.queue.push(Box::new(move |entities| entities.remove_parent(child)));
+    }
+
+    /// Queues adding a component to an entity by the given handle.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The component's data.
+    /// * `entity` - The handle of the entity to add the component to.
+    pub fn add_component_to_entity_by_id<T: Any + MaybeSendSync>(&mut self, data: T, entity: Entity) {
+        self.queue.push(Box::new(move |entities| entities.add_component_by_entity_id(data, entity)));
+    }
+
+    /// Queues adding every component of a bundle to an entity by the given handle.
+    ///
+    /// # Arguments
+    ///
+    /// * `bundle` - The components to add.
+    /// * `entity` - The handle of the entity to add the components to.
+    pub fn add_bundle_to_entity_by_id(&mut self, bundle: impl Bundle, entity: Entity) {
+        self.queue.push(Box::new(move |entities| entities.add_bundle_by_entity_id(bundle, entity)));
+    }
+
+    /// Queues deleting a component from an entity by the given handle.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component to delete.
+    /// * `entity` - The handle of the entity to delete the component from.
+    pub fn delete_component_by_entity_id<T: Any>(&mut self, entity: Entity) {
+        self.queue.push(Box::new(move |entities| entities.delete_component_by_entity_id::<T>(entity)));
+    }
+
+    /// Gets the amount of queued commands.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Checks if there are no queued commands.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Applies every queued command to the entities, in the order they were queued, and empties
+    /// the buffer.
+    ///
+    /// A failing command doesn't stop the rest from being applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `entities` - The entities to apply the commands to.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or the error of the first failing command if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If a command fails, for example because it references a deleted entity, its error is returned.
+    pub fn apply(&mut self, entities: &mut Entities) -> Result<()> {
+        self.creating_entity = None;
+        self.reserved.clear();
+
+        let mut result = Ok(());
+
+        for command in self.queue.drain(..) {
+            let command_result = command(entities);
+
+            if result.is_ok() {
+                result = command_result;
+            }
+        }
+
+        result
+    }
+}
+
+impl Drop for Commands {
+    fn drop(&mut self) {
+        // The reserved entities will never be created, so their slots are given back with a new
+        // generation, which makes their handles stale
+        for entity in self.reserved.drain(..) {
+            self.allocator.free(Entity::new(entity.index(), entity.generation().wrapping_add(1)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::typed_query::TypedQuery;
+
+    #[test]
+    fn commands_are_applied_in_order() -> Result<()> {
+        let mut entities = Entities::default();
+        entities.register_component::<Health>();
+        entities.register_component::<Speed>();
+
+        let entity = entities.create_entity().with_component(Health(100))?.entity();
+
+        let mut commands = Commands::new(&entities);
+        commands
+            .create_entity()
+            .with_component(Health(50))?
+            .with_component(Speed(10))?;
+        commands.add_component_to_entity_by_id(Speed(5), entity);
+        commands.delete_component_by_entity_id::<Health>(entity);
+        assert_eq!(commands.len(), 5);
+
+        // Nothing happens until the commands are applied
+        assert_eq!(TypedQuery::<&Speed>::new(&entities)?.count(), 0);
+
+        commands.apply(&mut entities)?;
+        assert!(commands.is_empty());
+
+        let query = TypedQuery::<(Option<&Health>, &Speed)>::new(&entities)?;
+        let mut results: Vec<(Option<u32>, u32)> = query
+            .iter()
+            .map(|(health, speed)| (health.map(|health| health.0), speed.0))
+            .collect();
+        results.sort();
+        assert_eq!(results, vec![(None, 5), (Some(50), 10)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn failing_commands_are_reported() -> Result<()> {
+        let mut entities = Entities::default();
+        entities.register_component::<Health>();
+
+        let entity = entities.create_entity().with_component(Health(100))?.entity();
+
+        let mut commands = Commands::new(&entities);
+        assert!(commands.with_component(Health(10)).is_err());
+
+        commands.delete_entity_by_id(entity);
+        commands.delete_entity_by_id(entity);
+        commands.create_entity().with_component(Speed(10))?;
+        commands.create_entity().with_component(Health(20))?;
+
+        assert!(commands.apply(&mut entities).is_err());
+        assert!(commands.is_empty());
+
+        // The commands after the failing ones were still applied
+        let query = TypedQuery::<&Health>::new(&entities)?;
+        assert_eq!(query.iter().map(|health| health.0).collect::<Vec<u32>>(), vec![20]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn queued_entities_can_be_referenced_by_later_commands() -> Result<()> {
+        let mut entities = Entities::default();
+        let gun = entities.create_entity().with_component(Speed(1))?.entity();
+
+        let mut commands = Commands::new(&entities);
+        let bullet = commands.create_entity().with_component(Speed(10))?.entity()?;
+        commands.set_parent(bullet, gun);
+        commands.add_component_to_entity_by_id(Health(1), bullet);
+
+        // The handle is reserved, but the entity doesn't exist until the commands are applied
+        let other = entities.create_entity().entity();
+        assert_ne!(other, bullet);
+        assert!(!entities.is_alive(bullet));
+
+        commands.apply(&mut entities)?;
+
+        assert!(entities.is_alive(bullet));
+        assert_eq!(entities.get_parent(bullet), Some(gun));
+
+        let query = TypedQuery::<(&Speed, &Health)>::new(&entities)?;
+        assert_eq!(query.get(bullet).map(|(speed, health)| (speed.0, health.0)), Some((10, 1)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn dropped_commands_give_back_their_reserved_entities() -> Result<()> {
+        let mut entities = Entities::default();
+
+        let mut commands = Commands::new(&entities);
+        assert!(commands.entity().is_err());
+
+        let reserved = commands.create_entity().entity()?;
+        drop(commands);
+
+        // The slot is reused, but the handle that was reserved stays stale
+        let entity = entities.create_entity().entity();
+        assert_eq!(entity.index(), reserved.index());
+        assert!(entities.is_alive(entity));
+        assert!(!entities.is_alive(reserved));
+
+        Ok(())
+    }
+
+    struct Health(pub u32);
+    struct Speed(pub u32);
+}
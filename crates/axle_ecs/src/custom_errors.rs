@@ -11,6 +11,12 @@ pub enum CustomErrors {
     EntityDoesNotExist,
     #[error("Attempting to get component's data that doesn't exist")]
     ComponentDataDoesNotExist,
+    #[error("Attempting to query a component mutably more than once, or both mutably and immutably")]
+    ConflictingComponentAccess,
+    #[error("Attempting to access a component the system didn't declare, or to write one it declared as read only")]
+    UndeclaredComponentAccess,
+    #[error("Attempting to make an entity the parent of itself or of one of its ancestors")]
+    HierarchyCycle,
 
     #[error("Attempting to downcast to the wrong type")]
     DowncastToWrongType,
@@ -23,4 +29,17 @@ pub enum CustomErrors {
     ComponentInSystemDoesNotExist,
     #[error("Attempting to reference a resource inside a system that doesn't exist")]
     ResourceInSystemDoesNotExist,
+    #[error("Attempting to access the same resource more than once in a system")]
+    ConflictingResourceAccess,
+    #[error("The ordering constraints of the systems form a cycle")]
+    SystemOrderCycle,
+
+    #[error("The type {0} wasn't registered for serialization")]
+    SerializableTypeNotRegistered(String),
+    #[error("The type {0} wasn't registered for reflection")]
+    ReflectTypeNotRegistered(String),
+    #[error("The reflected path {0} doesn't exist")]
+    ReflectPathDoesNotExist(String),
+    #[error("The prefab {0} wasn't registered")]
+    PrefabNotRegistered(String),
 }
@@ -1,23 +1,36 @@
-use std::{any::{Any, TypeId}, cell::RefCell, collections::HashMap, rc::Rc};
+use std::{any::{Any, TypeId}, collections::HashMap, sync::atomic::{AtomicU64, Ordering}};
 use eyre::Result;
 
 use crate::prelude::*;
-
+use crate::sync::{ComponentCell, MaybeSendSync};
+use allocator::EntityAllocator;
+use bit_mask::BitMask;
+use hooks::DespawnObservers;
+use storage::{ComponentStorage, SparseSet, TypeIdBuildHasher};
+
+pub mod allocator;
+pub mod bit_mask;
+pub mod entity;
+pub mod filter;
+pub mod hierarchy;
+pub mod hooks;
 pub mod query;
 pub mod query_entity;
+pub mod storage;
+pub mod typed_query;
 
-pub type Component = Rc<RefCell<dyn Any + 'static>>;
-pub type Components = HashMap<TypeId, Vec<Option<Component>>>;
+pub type Component<'a> = &'a ComponentCell<dyn Any + 'static>;
+pub type Components = HashMap<TypeId, Box<dyn ComponentStorage>, TypeIdBuildHasher>;
 
 /// The main struct for storing and managing entities and their components.
 #[derive(Debug, Default)]
 pub struct Entities {
-    /// A hasmap containing the components for every entity.
+    /// A hasmap containing the storage of every component type.
     ///
-    /// The type id is used to identify the type of the component, and the vector
-    /// contains the actual components.
+    /// The type id is used to identify the type of the component, and the storage
+    /// is a sparse set that keeps all the components of that type packed together.
     /// 
-    /// For example, to get the the component `Health` of the second entity, it will be like this: `components.get(&TypeId::of::<Health>())[1]`
+    /// For example, to get the the component `Health` of the second entity, it will be like this: `components.get(&TypeId::of::<Health>()).get_cell(1)`
     components: Components,
 
     /// The bitmasks of every component's registered type id
@@ -27,7 +40,9 @@ pub struct Entities {
     /// 
     /// The bitmsk of every component is a bit shifted an adition than the previous component.
     /// For example, the first component is `0001` and the second component is `0010`.
-    bit_masks: HashMap<TypeId, u32>,
+    ///
+    /// The masks grow as needed, so there is no limit on the amount of components that can be registered.
+    bit_masks: HashMap<TypeId, BitMask, TypeIdBuildHasher>,
 
     /// A vector of bit masks for every entity.
     ///
@@ -35,52 +50,142 @@ pub struct Entities {
     /// entity.
     /// 
     /// For example, if the entity has the first and third registered component, its map will be `101`.
-    map: Vec<u32>,
+    map: Vec<BitMask>,
+
+    /// The generation of every entity slot.
+    ///
+    /// The generation is increased every time the entity living in the slot is deleted, so that
+    /// `Entity` handles pointing to the deleted entity no longer match it.
+    generations: Vec<u32>,
+
+    /// Whether the slot of every entity is currently in use or not.
+    alive: Vec<bool>,
+
+    /// Hands out the slots of the new entities, reusing the ones that belonged to deleted entities.
+    ///
+    /// It is shared with the `Commands` buffers, which reserve the slots of the entities they
+    /// will create.
+    allocator: EntityAllocator,
 
     /// The index of the entity that is being inserted into.
     ///
     /// This is used to keep track of which entity is being inserted into when
     /// creating and inserting components in it.
     inserting_into_index: usize,
+
+    /// The current change tick.
+    ///
+    /// Every component stores the tick at which it was added and last changed, which is what the
+    /// `Added` and `Changed` filters compare against. The tick is increased after every system run.
+    change_tick: AtomicU64,
+
+    /// The change tick at which the last run of the systems started.
+    ///
+    /// Queries run outside of the systems consider added or changed the components that were so
+    /// since this tick.
+    frame_tick: AtomicU64,
+
+    /// The observers called right before an entity is deleted.
+    despawn_observers: DespawnObservers,
 }
 
 impl Entities {
     /// Registers a component for later use in entities.
     ///
+    /// Components are registered automatically the first time they are added to an entity, so
+    /// calling this is only needed to query a component type before any entity has it. Registering
+    /// a component that was already registered does nothing.
+    ///
     /// # Arguments
     /// 
     /// * `T` - The type of the component to register.
-    pub fn register_component<T: Any>(&mut self) {
+    pub fn register_component<T: Any + MaybeSendSync>(&mut self) {
         let type_id = TypeId::of::<T>();
 
-        self.components.insert(type_id, vec![]);
-        self.bit_masks.insert(type_id, 1 << self.bit_masks.len());
+        if self.components.contains_key(&type_id) {
+            return;
+        }
+
+        self.components.insert(type_id, Box::new(SparseSet::<T>::default()));
+        self.bit_masks.insert(type_id, BitMask::with_bit(self.bit_masks.len()));
     }
 
     /// Creates a new entity and returns a mutable reference to the `Entities` struct
     /// so that you can add components to it.
     ///
+    /// The handle of the created entity can be retrieved with the `entity` function.
+    ///
     /// # Returns
     ///
     /// A mutable reference to the Entities struct.
     pub fn create_entity(&mut self) -> &mut Self {
-        if let Some((index, _)) = self.map.iter().enumerate().find(|(_index, mask)| **mask == 0) {
-            self.inserting_into_index = index;
-        }
-        else {
-            self.components
-                .iter_mut()
-                .for_each(|(_key, components)| components.push(None));
+        let entity = self.allocator.allocate();
+        self.create_reserved_entity(entity)
+    }
 
-            self.map.push(0);
-            self.inserting_into_index = self.map.len() - 1;
+    /// Creates the entity of a handle reserved beforehand from the allocator, so that the
+    /// commands that reference it before it was created apply to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The reserved handle.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the Entities struct.
+    pub(crate) fn create_reserved_entity(&mut self, entity: Entity) -> &mut Self {
+        let index = entity.index();
+
+        // The slots reserved after this one may not have been created yet
+        if index >= self.map.len() {
+            self.map.resize_with(index + 1, BitMask::default);
+            self.generations.resize(index + 1, 0);
+            self.alive.resize(index + 1, false);
         }
 
+        self.generations[index] = entity.generation();
+        self.alive[index] = true;
+        self.inserting_into_index = index;
+
         self
     }
 
+    /// Gets the allocator that hands out the slots of the entities.
+    pub(crate) fn allocator(&self) -> &EntityAllocator {
+        &self.allocator
+    }
+
+    /// Gets the handle of the entity that is being inserted into, that is, the last entity
+    /// created with the `create_entity` function.
+    ///
+    /// # Returns
+    ///
+    /// The handle of the entity.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::entities::{entity::Entity, Entities};
+    ///
+    /// let mut entities = Entities::default();
+    /// entities.register_component::<u32>();
+    ///
+    /// let entity: Entity = entities
+    ///     .create_entity()
+    ///     .with_component(100_u32).unwrap()
+    ///     .entity();
+    ///
+    /// assert!(entities.is_alive(entity));
+    /// ```
+    pub fn entity(&self) -> Entity {
+        let index = self.inserting_into_index;
+        Entity::new(index, self.generations.get(index).copied().unwrap_or_default())
+    }
+
     /// Adds a component to the current entity.
     ///
+    /// The component type is registered if it wasn't yet.
+    ///
     /// # Arguments
     ///
     /// * `data` - The component's data.
@@ -91,27 +196,17 @@ impl Entities {
     ///
     /// # Errors
     ///
-    /// If the component was not registered, or if the `create_entity` function was not called before,
-    /// an error is returned.
-    pub fn with_component(&mut self, data: impl Any) -> Result<&mut Self> {
-        let type_id = data.type_id();
+    /// If the `create_entity` function was not called before, an error is returned.
+    pub fn with_component<T: Any + MaybeSendSync>(&mut self, data: T) -> Result<&mut Self> {
         let index = self.inserting_into_index;
-        
-        // Check if the component was registered and if the create entity function was called before
-        if let Some(components) = self.components.get_mut(&type_id) {
-            let component = components
-                .get_mut(index)
-                .ok_or(CustomErrors::CreateEntityNeverCalled)?;
-            *component = Some(Rc::new(RefCell::new(data)));
-
-            // Add the component's bitmask to the entity's bitmask
-            let bit_mask = self.bit_masks.get(&type_id).unwrap();
-            self.map[index] |= *bit_mask;
-        }
-        else {
-            return Err(CustomErrors::ComponentNotRegistered.into());
+
+        // Check if the create entity function was called before
+        if !self.alive.get(index).copied().unwrap_or(false) {
+            return Err(CustomErrors::CreateEntityNeverCalled.into());
         }
 
+        self.insert_component(index, data);
+
         Ok(self)
     }
 
@@ -124,55 +219,89 @@ impl Entities {
     /// # Returns
     ///
     /// The bitmask of the component type if it exists, otherwise `None`.
-    pub fn get_bitmask(&self, type_id: &TypeId) -> Option<u32> {
-        self.bit_masks.get(type_id).copied()
+    pub fn get_bitmask(&self, type_id: &TypeId) -> Option<&BitMask> {
+        self.bit_masks.get(type_id)
     }
 
     /// Gets the bitmask of an entity.
     ///
     /// # Arguments
     ///
-    /// * `entity_id` - The id of the entity.
+    /// * `entity` - The handle of the entity.
     ///
     /// # Returns
     ///
     /// The bitmask of the entity if it exists, otherwise `None`.
-    pub fn get_map(&self, entity_id: usize) -> Option<u32> {
-        self.map.get(entity_id).copied()
+    pub fn get_map(&self, entity: Entity) -> Option<&BitMask> {
+        if self.is_alive(entity) {
+            self.map.get(entity.index())
+        }
+        else {
+            None
+        }
     }
 
-    /// Deletes a component from an entity.
+    /// Checks if the entity pointed by the given handle still exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the entity exists, `false` if it was deleted or never existed.
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index();
+
+        self.alive.get(index).copied().unwrap_or(false) && self.generations[index] == entity.generation()
+    }
+
+    /// Gets the handles of every existing entity, in the order of their slots.
+    pub fn alive_entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        (0..self.alive.len()).filter_map(|index| self.entity_at(index))
+    }
+
+    /// Deletes a component from an entity, calling its `on_remove` hooks and dropping it.
+    ///
+    /// Nothing happens if the entity doesn't have the component.
     ///
     /// # Arguments
     ///
     /// * `T` - The type of the component to delete.
-    /// * `index` - The id of the entity to delete the component from.
+    /// * `entity` - The handle of the entity to delete the component from.
     ///
     /// # Returns
     ///
     /// A result that contains nothing if succeeds or an error if it fails.
-    pub fn delete_component_by_entity_id<T: Any>(&mut self, index: usize) -> Result<()> {
+    ///
+    /// # Errors
+    ///
+    /// If the entity doesn't exist, an error is returned.
+    pub fn delete_component_by_entity_id<T: Any>(&mut self, entity: Entity) -> Result<()> {
+        let index = self.validate_entity(entity)?;
         let type_id = TypeId::of::<T>();
-        let mask = match self.bit_masks.get(&type_id) {
-            Some(mask) => mask,
-            None => return Err(CustomErrors::ComponentNotRegistered.into()),
+
+        // A component that was never registered can't be in the entity
+        let (Some(mask), Some(components)) = (self.bit_masks.get(&type_id), self.components.get_mut(&type_id)) else {
+            return Ok(());
         };
 
-        // Check if the entity has the component
-        if self.has_component(index, *mask) {
-            // Remove the component's bitmask from the entity's bitmask
-            self.map[index] ^= *mask;
-        }
+        // Remove the component's bitmask from the entity's bitmask and drop its data
+        self.map[index].remove(mask);
+        components.remove(entity);
 
         Ok(())
     }
 
-    /// Adds a component to an entity by the given id.
+    /// Adds a component to an entity by the given handle, replacing the one of the same type if
+    /// the entity already had it.
+    ///
+    /// The component type is registered if it wasn't yet.
     ///
     /// # Arguments
     ///
     /// * `data` - The component's data.
-    /// * `index` - The id of the entity to add the component to.
+    /// * `entity` - The handle of the entity to add the component to.
     ///
     /// # Returns
     ///
@@ -180,30 +309,23 @@ impl Entities {
     ///
     /// # Errors
     ///
-    /// If the component was not registered, or if the `create_entity` function was not called before,
-    /// an error is returned.
-    pub fn add_component_by_entity_id(&mut self, data: impl Any, index: usize) -> Result<()> {
-        let type_id = data.type_id();
-        let mask = match self.bit_masks.get(&type_id) {
-            Some(mask) => mask,
-            None => return Err(CustomErrors::ComponentNotRegistered.into()),
-        };
-
-        // Add the component's bitmask to the entity's bitmask
-        self.map[index] |= *mask;
-
-        let components = self.components.get_mut(&type_id).unwrap();
-        // Add the component's data to the entity
-        components[index] = Some(Rc::new(RefCell::new(data)));
+    /// If the entity doesn't exist, an error is returned.
+    pub fn add_component_by_entity_id<T: Any + MaybeSendSync>(&mut self, data: T, entity: Entity) -> Result<()> {
+        let index = self.validate_entity(entity)?;
+        self.insert_component(index, data);
 
         Ok(())
     }
 
-    /// Deletes an entity by its id.
+    /// Deletes an entity by its handle.
+    ///
+    /// The despawn observers are called first, then the entity is detached from its parent, and its
+    /// children become root entities, and finally its components are dropped after calling their
+    /// `on_remove` hooks. Use `delete_entity_recursive` to delete the children too.
     ///
     /// # Arguments
     ///
-    /// * `index` - The id of the entity to delete.
+    /// * `entity` - The handle of the entity to delete.
     ///
     /// # Returns
     ///
@@ -212,32 +334,139 @@ impl Entities {
     /// # Errors
     ///
     /// If the entity doesn't exist, an error is returned.
-    pub fn delete_entity_by_id(&mut self, index: usize) -> Result<()> {
-        // Check if the entity exists
-        if let Some(map) = self.map.get_mut(index) {
-            // Reset the entity's bitmask, effectively deleting it
-            *map = 0;
+    pub fn delete_entity_by_id(&mut self, entity: Entity) -> Result<()> {
+        let index = self.validate_entity(entity)?;
+        self.despawn_observers.notify(entity);
+        self.unlink_hierarchy(entity);
+
+        // Reset the entity's bitmask, drop its components and free its slot, effectively deleting it
+        self.map[index].clear();
+        self.components
+            .values_mut()
+            .for_each(|components| components.remove(entity));
+        self.alive[index] = false;
+        // Increase the generation so that the handles of the deleted entity become stale
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.allocator.free(Entity::new(index, self.generations[index]));
+
+        Ok(())
+    }
+
+    /// Gets the current change tick.
+    pub fn change_tick(&self) -> u64 {
+        self.change_tick.load(Ordering::Relaxed)
+    }
+
+    /// Gets the change tick at which the last run of the systems started.
+    pub fn frame_tick(&self) -> u64 {
+        self.frame_tick.load(Ordering::Relaxed)
+    }
+
+    /// Increases the change tick, so that the changes made from now on are newer than the
+    /// previous ones.
+    pub(crate) fn increment_change_tick(&self) {
+        self.change_tick.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Marks the start of a run of the systems.
+    pub(crate) fn start_frame(&self) {
+        self.frame_tick.store(self.change_tick.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    /// Checks that the given handle points to an existing entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity to check.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the index of the entity's slot if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the entity was deleted or never existed, an error is returned.
+    pub(crate) fn validate_entity(&self, entity: Entity) -> Result<usize> {
+        if self.is_alive(entity) {
+            Ok(entity.index())
         }
         else {
-            // If the entity doesn't exist, return an error
-            return Err(CustomErrors::EntityDoesNotExist.into());
+            Err(CustomErrors::EntityDoesNotExist.into())
         }
+    }
 
-        Ok(())
+    /// Stores a component in the given slot, registering its type if it wasn't yet, and calls its
+    /// `on_add` and `on_insert` hooks.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the slot of an existing entity.
+    /// * `data` - The component's data.
+    fn insert_component<T: Any + MaybeSendSync>(&mut self, index: usize, data: T) {
+        let type_id = TypeId::of::<T>();
+        self.register_component::<T>();
+
+        let components = Self::downcast_storage_mut::<T>(self.components.get_mut(&type_id).unwrap());
+        let added = components.insert(index, data, self.change_tick.load(Ordering::Relaxed));
+
+        // Add the component's bitmask to the entity's bitmask
+        let bit_mask = self.bit_masks.get(&type_id).unwrap();
+        self.map[index].insert(bit_mask);
+
+        components.run_insert_hooks(Entity::new(index, self.generations[index]), added);
     }
 
-    /// Checks if an entity has a component.
+    /// Gets the storage of a component type.
     ///
     /// # Arguments
     ///
-    /// * `index` - The id of the entity to check.
-    /// * `mask` - The bitmask of the component to check for.
+    /// * `T` - The type of the component.
     ///
     /// # Returns
     ///
-    /// `true` if the entity has the component, `false` otherwise.
-    fn has_component(&self, index: usize, mask: u32) -> bool {
-        self.map[index] & mask == mask
+    /// The sparse set containing the components of type `T` if it was registered, otherwise `None`.
+    pub(crate) fn get_storage<T: Any>(&self) -> Option<&SparseSet<T>> {
+        self.components
+            .get(&TypeId::of::<T>())
+            .map(|components| components.as_any().downcast_ref::<SparseSet<T>>().unwrap())
+    }
+
+    /// Gets the mutable storage of a component type.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component.
+    ///
+    /// # Returns
+    ///
+    /// The sparse set containing the components of type `T` if it was registered, otherwise `None`.
+    pub(crate) fn get_storage_mut<T: Any>(&mut self) -> Option<&mut SparseSet<T>> {
+        self.components.get_mut(&TypeId::of::<T>()).map(Self::downcast_storage_mut::<T>)
+    }
+
+    /// Gets the type-erased storage of a component type.
+    pub(crate) fn get_storage_by_type_id(&self, type_id: &TypeId) -> Option<&dyn ComponentStorage> {
+        self.components.get(type_id).map(|components| components.as_ref())
+    }
+
+    /// Iterates over the type-erased storages of every registered component type.
+    pub(crate) fn storages(&self) -> impl Iterator<Item = (&TypeId, &dyn ComponentStorage)> {
+        self.components.iter().map(|(type_id, components)| (type_id, components.as_ref()))
+    }
+
+    /// Downcasts a type-erased storage to the sparse set of the component type `T`.
+    fn downcast_storage_mut<T: Any>(components: &mut Box<dyn ComponentStorage>) -> &mut SparseSet<T> {
+        components.as_any_mut().downcast_mut::<SparseSet<T>>().unwrap()
+    }
+
+    /// Gets the handle of the entity living in the given slot if the slot is in use.
+    pub(crate) fn entity_at(&self, index: usize) -> Option<Entity> {
+        if self.alive.get(index).copied().unwrap_or(false) {
+            Some(Entity::new(index, self.generations[index]))
+        }
+        else {
+            None
+        }
     }
 }
 
@@ -265,12 +494,12 @@ mod tests {
         entities.register_component::<Health>();
         let type_id = TypeId::of::<Health>();
         let mask = entities.bit_masks.get(&type_id).unwrap();
-        assert_eq!(*mask, 1);
+        assert_eq!(*mask, BitMask::from(1));
 
         entities.register_component::<Speed>();
         let type_id = TypeId::of::<Speed>();
         let mask = entities.bit_masks.get(&type_id).unwrap();
-        assert_eq!(*mask, 2);
+        assert_eq!(*mask, BitMask::from(2));
     }
 
     #[test]
@@ -283,8 +512,9 @@ mod tests {
         let health = entities.components.get(&TypeId::of::<Health>()).unwrap();
         let speed = entities.components.get(&TypeId::of::<Speed>()).unwrap();
         
-        assert!(health.len() == speed.len() && health.len() == 1);
-        assert!(health[0].is_none() && speed[0].is_none());
+        assert_eq!(entities.map.len(), 1);
+        assert!(health.is_empty() && speed.is_empty());
+        assert!(!health.contains(0) && !speed.contains(0));
     }
 
     #[test]
@@ -297,8 +527,8 @@ mod tests {
             .with_component(Health(100))?
             .with_component(Speed(15))?;
 
-        let first_health = &entities.components.get(&TypeId::of::<Health>()).unwrap()[0];
-        let wrapped_health = first_health.as_ref().unwrap();
+        let healths = entities.components.get(&TypeId::of::<Health>()).unwrap();
+        let wrapped_health = healths.get_cell(0).unwrap();
         let borrowed_health = wrapped_health.borrow();
         let health = borrowed_health.downcast_ref::<Health>().unwrap();
 
@@ -316,15 +546,15 @@ mod tests {
             .with_component(Health(100))?
             .with_component(Speed(15))?;
 
-        let entity_map = entities.map[0];
-        assert_eq!(entity_map, 3);
+        let entity_map = &entities.map[0];
+        assert_eq!(*entity_map, BitMask::from(3));
         
         entities
             .create_entity()
             .with_component(Speed(15))?;
 
-        let entity_map = entities.map[1];
-        assert_eq!(entity_map, 2);
+        let entity_map = &entities.map[1];
+        assert_eq!(*entity_map, BitMask::from(2));
 
         Ok(())
     }
@@ -335,14 +565,15 @@ mod tests {
         entities.register_component::<Health>();
         entities.register_component::<Speed>();
 
-        entities
+        let entity = entities
             .create_entity()
             .with_component(Health(100))?
-            .with_component(Speed(50))?;
+            .with_component(Speed(50))?
+            .entity();
 
-        entities.delete_component_by_entity_id::<Health>(0)?;
+        entities.delete_component_by_entity_id::<Health>(entity)?;
 
-        assert_eq!(entities.map[0], 2);
+        assert_eq!(entities.map[0], BitMask::from(2));
 
         Ok(())
     }
@@ -353,17 +584,18 @@ mod tests {
         entities.register_component::<Health>();
         entities.register_component::<Speed>();
 
-        entities
+        let entity = entities
             .create_entity()
-            .with_component(Health(100))?;
+            .with_component(Health(100))?
+            .entity();
 
-        entities.add_component_by_entity_id(Speed(50), 0)?;
+        entities.add_component_by_entity_id(Speed(50), entity)?;
 
-        assert_eq!(entities.map[0], 3);
+        assert_eq!(entities.map[0], BitMask::from(3));
 
         let speed_type_id = TypeId::of::<Speed>();
         let wrapped_speeds = entities.components.get(&speed_type_id).unwrap();
-        let wrapped_speed = wrapped_speeds[0].as_ref().unwrap();
+        let wrapped_speed = wrapped_speeds.get_cell(0).unwrap();
         let borowed_speed = wrapped_speed.borrow();
         let speed = borowed_speed.downcast_ref::<Speed>().unwrap();
 
@@ -377,13 +609,15 @@ mod tests {
         let mut entities = Entities::default();
         entities.register_component::<Health>();
         
-        entities
+        let entity = entities
             .create_entity()
-            .with_component(Health(100))?;
+            .with_component(Health(100))?
+            .entity();
 
-        entities.delete_entity_by_id(0)?;
+        entities.delete_entity_by_id(entity)?;
 
-        assert_eq!(entities.map[0], 0);
+        assert_eq!(entities.map[0], BitMask::from(0));
+        assert!(!entities.is_alive(entity));
 
         Ok(())
     }
@@ -393,14 +627,15 @@ mod tests {
         let mut entities = Entities::default();
         entities.register_component::<Health>();
 
-        entities
+        let first_entity = entities
             .create_entity()
-            .with_component(Health(100))?;
+            .with_component(Health(100))?
+            .entity();
         entities
             .create_entity()
             .with_component(Health(50))?;
 
-        entities.delete_entity_by_id(0)?;
+        entities.delete_entity_by_id(first_entity)?;
 
         entities
             .create_entity()
@@ -410,20 +645,20 @@ mod tests {
             .create_entity()
             .with_component(Health(75))?;
 
-        assert_eq!(entities.map[0], 1);
+        assert_eq!(entities.map[0], BitMask::from(1));
 
         let type_id = TypeId::of::<Health>();
         let healths = entities.components.get(&type_id).unwrap();
-        let wrapped_health = &healths[0];
-        let borrowed_health = wrapped_health.as_ref().unwrap().borrow();
+        let wrapped_health = healths.get_cell(0).unwrap();
+        let borrowed_health = wrapped_health.borrow();
         let health = borrowed_health.downcast_ref::<Health>().unwrap();
 
         assert_eq!(health.0, 25);
 
         let type_id = TypeId::of::<Health>();
         let healths = entities.components.get(&type_id).unwrap();
-        let wrapped_health = &healths[2];
-        let borrowed_health = wrapped_health.as_ref().unwrap().borrow();
+        let wrapped_health = healths.get_cell(2).unwrap();
+        let borrowed_health = wrapped_health.borrow();
         let health = borrowed_health.downcast_ref::<Health>().unwrap();
 
         assert_eq!(health.0, 75);
@@ -437,19 +672,157 @@ mod tests {
         entities.register_component::<u32>();
         entities.register_component::<f32>();
 
-        entities
+        let entity = entities
             .create_entity()
             .with_component(100_u32)?
-            .with_component(50.0_f32)?;
+            .with_component(50.0_f32)?
+            .entity();
+
+        entities.delete_component_by_entity_id::<u32>(entity)?;
+        entities.delete_component_by_entity_id::<u32>(entity)?;
+
+        assert_eq!(entities.map[0], BitMask::from(2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn stale_entity_handles_are_rejected() -> Result<()> {
+        let mut entities = Entities::default();
+        entities.register_component::<Health>();
+        entities.register_component::<Speed>();
+
+        let old_entity = entities
+            .create_entity()
+            .with_component(Health(100))?
+            .entity();
+
+        entities.delete_entity_by_id(old_entity)?;
+
+        let new_entity = entities
+            .create_entity()
+            .with_component(Health(50))?
+            .entity();
+
+        // The new entity reuses the slot but not the generation
+        assert_eq!(old_entity.index(), new_entity.index());
+        assert_ne!(old_entity.generation(), new_entity.generation());
+        assert!(!entities.is_alive(old_entity));
+        assert!(entities.is_alive(new_entity));
+
+        assert!(entities.get_map(old_entity).is_none());
+        assert!(entities.delete_entity_by_id(old_entity).is_err());
+        assert!(entities.add_component_by_entity_id(Speed(10), old_entity).is_err());
+        assert!(entities.delete_component_by_entity_id::<Health>(old_entity).is_err());
+
+        // The new entity is left untouched
+        assert_eq!(entities.get_map(new_entity), Some(&BitMask::from(1)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn deleting_entities_and_components_removes_their_data() -> Result<()> {
+        let mut entities = Entities::default();
+        entities.register_component::<Health>();
+        entities.register_component::<Speed>();
+
+        let first_entity = entities
+            .create_entity()
+            .with_component(Health(100))?
+            .with_component(Speed(10))?
+            .entity();
+        let second_entity = entities
+            .create_entity()
+            .with_component(Health(50))?
+            .entity();
+
+        entities.delete_component_by_entity_id::<Speed>(first_entity)?;
+        entities.delete_entity_by_id(second_entity)?;
+
+        let healths = entities.get_storage::<Health>().unwrap();
+        let speeds = entities.get_storage::<Speed>().unwrap();
+        assert_eq!(healths.len(), 1);
+        assert!(speeds.is_empty());
+
+        // The entity reusing the slot doesn't see the data of the deleted one
+        let new_entity = entities.create_entity().entity();
+        assert!(entities.get_storage::<Health>().unwrap().get(new_entity.index()).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn components_are_registered_when_first_added() -> Result<()> {
+        let mut entities = Entities::default();
+        let entity = entities.create_entity().with_component(Health(100))?.entity();
+
+        // Deleting a component that was never registered is not an error
+        entities.delete_component_by_entity_id::<Speed>(entity)?;
+        entities.add_component_by_entity_id(Speed(10), entity)?;
+
+        // Registering again keeps the stored components
+        entities.register_component::<Health>();
+        assert_eq!(entities.get_storage::<Health>().unwrap().len(), 1);
+        assert_eq!(entities.get_storage::<Speed>().unwrap().len(), 1);
+
+        // Handles that point past the last slot are rejected instead of panicking
+        let unknown = Entity::new(50, 0);
+        assert!(entities.add_component_by_entity_id(Health(1), unknown).is_err());
+        assert!(entities.delete_component_by_entity_id::<Health>(unknown).is_err());
+        assert!(entities.delete_entity_by_id(unknown).is_err());
+
+        Ok(())
+    }
+
+    /// Registers a `Marker` component for every combination of the given numbers.
+    macro_rules! register_markers {
+        ($entities:ident; $($a:literal)*; $bs:tt) => {
+            $( register_markers!(@inner $entities; $a; $bs); )*
+        };
+        (@inner $entities:ident; $a:literal; [$($b:literal)*]) => {
+            $( $entities.register_component::<Marker<$a, $b>>(); )*
+        };
+    }
+
+    #[test]
+    fn register_more_components_than_bits_in_a_word() -> Result<()> {
+        let mut entities = Entities::default();
+        register_markers!(entities; 0 1 2 3 4 5 6 7 8 9; [0 1 2 3 4 5 6 7 8 9 10 11 12]);
+        entities.register_component::<Health>();
+
+        let mask = entities.bit_masks.get(&TypeId::of::<Health>()).unwrap();
+        assert_eq!(*mask, BitMask::with_bit(130));
+
+        let entity = entities
+            .create_entity()
+            .with_component(Marker::<0, 0>)?
+            .with_component(Marker::<9, 12>)?
+            .with_component(Health(100))?
+            .entity();
+
+        let entity_map = entities.get_map(entity).unwrap();
+        assert!(entity_map.contains(&BitMask::with_bit(0)));
+        assert!(entity_map.contains(&BitMask::with_bit(129)));
+        assert!(entity_map.contains(&BitMask::with_bit(130)));
+        assert!(!entity_map.contains(&BitMask::with_bit(64)));
+
+        entities.create_entity().with_component(Health(50))?;
 
-        entities.delete_component_by_entity_id::<u32>(0)?;
-        entities.delete_component_by_entity_id::<u32>(0)?;
+        let mut query = query::Query::new(&entities);
+        let (indexes, _) = query
+            .with_component::<Marker<9, 12>>()?
+            .with_component::<Health>()?
+            .run();
+        assert_eq!(indexes, vec![entity]);
 
-        assert_eq!(entities.map[0], 2);
+        entities.delete_component_by_entity_id::<Marker<9, 12>>(entity)?;
+        assert!(!entities.get_map(entity).unwrap().contains(&BitMask::with_bit(129)));
 
         Ok(())
     }
 
+    struct Marker<const A: usize, const B: usize>;
     struct Health(pub u32);
     struct Speed(pub u32);
 }
\ No newline at end of file
@@ -0,0 +1,72 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::prelude::*;
+
+/// Hands out the slots of the entities.
+///
+/// The allocator is shared between the entities and the `Commands` buffers, so that a system can
+/// reserve the handle of an entity when it queues its creation and use it in later commands,
+/// before the entity actually exists. Cloning the allocator shares its slots.
+#[derive(Debug, Default, Clone)]
+pub struct EntityAllocator {
+    slots: Arc<Mutex<Slots>>,
+}
+
+/// The slots handed out by an `EntityAllocator`.
+#[derive(Debug, Default)]
+struct Slots {
+    /// The handles the free slots will have once they are reused.
+    free: Vec<Entity>,
+    /// The amount of slots handed out, including the reserved ones whose entities weren't created yet.
+    len: usize,
+}
+
+impl EntityAllocator {
+    /// Takes a free slot, or a new one if there are none.
+    ///
+    /// # Returns
+    ///
+    /// The handle of the entity that will live in the slot.
+    pub fn allocate(&self) -> Entity {
+        let mut slots = self.lock();
+
+        if let Some(entity) = slots.free.pop() {
+            return entity;
+        }
+
+        slots.len += 1;
+        Entity::new(slots.len - 1, 0)
+    }
+
+    /// Gives a slot back so that it can be reused by later entities.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle the next entity living in the slot will have.
+    pub fn free(&self, entity: Entity) {
+        self.lock().free.push(entity);
+    }
+
+    /// Locks the slots, ignoring the poisoning since they are never left in an invalid state.
+    fn lock(&self) -> MutexGuard<'_, Slots> {
+        self.slots.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freed_slots_are_reused_first() {
+        let allocator = EntityAllocator::default();
+        let shared = allocator.clone();
+
+        assert_eq!(allocator.allocate(), Entity::new(0, 0));
+        assert_eq!(shared.allocate(), Entity::new(1, 0));
+
+        shared.free(Entity::new(0, 1));
+        assert_eq!(allocator.allocate(), Entity::new(0, 1));
+        assert_eq!(allocator.allocate(), Entity::new(2, 0));
+    }
+}
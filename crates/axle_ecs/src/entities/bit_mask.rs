@@ -0,0 +1,189 @@
+use std::hash::{Hash, Hasher};
+
+/// The number of bits stored in every word of a `BitMask`.
+const BITS_PER_WORD: usize = u64::BITS as usize;
+
+/// A growable set of bits used to store which components an entity or a query has.
+///
+/// Every registered component type gets its own bit, and the mask grows as more words are
+/// needed, so there is no limit on the amount of component types that can be registered.
+#[derive(Debug, Clone, Default)]
+pub struct BitMask {
+    /// The words that store the bits. The first word stores the bits `0..64`, the second word
+    /// the bits `64..128`, and so on.
+    words: Vec<u64>,
+}
+
+impl BitMask {
+    /// Creates a new `BitMask` with only the given bit set.
+    ///
+    /// # Arguments
+    ///
+    /// * `bit` - The index of the bit to set.
+    ///
+    /// # Returns
+    ///
+    /// A new `BitMask`.
+    pub fn with_bit(bit: usize) -> Self {
+        let mut mask = Self::default();
+        mask.set_bit(bit);
+
+        mask
+    }
+
+    /// Sets the given bit.
+    ///
+    /// # Arguments
+    ///
+    /// * `bit` - The index of the bit to set.
+    pub fn set_bit(&mut self, bit: usize) {
+        let word = bit / BITS_PER_WORD;
+
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+
+        self.words[word] |= 1 << (bit % BITS_PER_WORD);
+    }
+
+    /// Adds all the bits of another mask to this one.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The mask whose bits are added.
+    pub fn insert(&mut self, other: &BitMask) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+
+        for (word, other_word) in self.words.iter_mut().zip(&other.words) {
+            *word |= other_word;
+        }
+    }
+
+    /// Removes all the bits of another mask from this one.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The mask whose bits are removed.
+    pub fn remove(&mut self, other: &BitMask) {
+        for (word, other_word) in self.words.iter_mut().zip(&other.words) {
+            *word &= !other_word;
+        }
+    }
+
+    /// Removes every bit of the mask.
+    pub fn clear(&mut self) {
+        self.words.clear();
+    }
+
+    /// Checks if all the bits of another mask are set in this one.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The mask to check for.
+    ///
+    /// # Returns
+    ///
+    /// `true` if every bit of `other` is set in this mask, `false` otherwise.
+    pub fn contains(&self, other: &BitMask) -> bool {
+        other.words.iter().enumerate().all(|(index, other_word)| {
+            let word = self.words.get(index).copied().unwrap_or(0);
+            word & other_word == *other_word
+        })
+    }
+
+    /// Checks if at least one bit of another mask is set in this one.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The mask to check for.
+    ///
+    /// # Returns
+    ///
+    /// `true` if both masks share at least one bit, `false` otherwise.
+    pub fn intersects(&self, other: &BitMask) -> bool {
+        self.words
+            .iter()
+            .zip(&other.words)
+            .any(|(word, other_word)| word & other_word != 0)
+    }
+
+    /// Checks if no bit is set.
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    /// Gets the words of the mask without the trailing empty ones, so that masks with the same
+    /// bits set compare equal regardless of how much they grew.
+    fn significant_words(&self) -> &[u64] {
+        let length = self
+            .words
+            .iter()
+            .rposition(|word| *word != 0)
+            .map_or(0, |index| index + 1);
+
+        &self.words[..length]
+    }
+}
+
+impl From<u64> for BitMask {
+    fn from(bits: u64) -> Self {
+        Self { words: vec![bits] }
+    }
+}
+
+impl PartialEq for BitMask {
+    fn eq(&self, other: &Self) -> bool {
+        self.significant_words() == other.significant_words()
+    }
+}
+
+impl Eq for BitMask {}
+
+impl Hash for BitMask {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.significant_words().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_bits_beyond_the_first_word() {
+        let mut mask = BitMask::with_bit(3);
+        mask.set_bit(130);
+
+        assert!(mask.contains(&BitMask::with_bit(3)));
+        assert!(mask.contains(&BitMask::with_bit(130)));
+        assert!(!mask.contains(&BitMask::with_bit(64)));
+    }
+
+    #[test]
+    fn insert_and_remove_masks() {
+        let mut mask = BitMask::from(0b101);
+        mask.insert(&BitMask::with_bit(100));
+
+        assert!(mask.intersects(&BitMask::with_bit(100)));
+
+        mask.remove(&BitMask::with_bit(100));
+        mask.remove(&BitMask::with_bit(0));
+
+        assert_eq!(mask, BitMask::from(0b100));
+        assert!(!mask.is_empty());
+
+        mask.clear();
+        assert!(mask.is_empty());
+    }
+
+    #[test]
+    fn masks_compare_equal_regardless_of_their_length() {
+        let mut mask = BitMask::with_bit(200);
+        mask.remove(&BitMask::with_bit(200));
+
+        assert_eq!(mask, BitMask::default());
+        assert!(BitMask::default().contains(&mask));
+    }
+}
@@ -0,0 +1,39 @@
+/// A handle that points to an entity stored in `Entities`.
+///
+/// Besides the index of the slot the entity lives in, the handle stores the generation of that
+/// slot. Every time an entity is deleted the generation of its slot is increased, so handles
+/// that point to a deleted entity are detected instead of silently addressing whatever entity
+/// reused the slot later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    /// The index of the slot the entity lives in.
+    index: usize,
+    /// The generation of the slot at the moment the entity was created.
+    generation: u32,
+}
+
+impl Entity {
+    /// Creates a new `Entity` handle.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the slot the entity lives in.
+    /// * `generation` - The generation of the slot.
+    ///
+    /// # Returns
+    ///
+    /// A new `Entity` handle.
+    pub(crate) fn new(index: usize, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    /// Gets the index of the slot the entity lives in.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Gets the generation of the slot at the moment the entity was created.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
@@ -0,0 +1,255 @@
+use std::{any::{Any, TypeId}, marker::PhantomData};
+
+use super::Entities;
+
+/// A condition that the entities of a query must fulfill besides having the required components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// The entity must have the component.
+    With(TypeId),
+    /// The entity must not have the component.
+    Without(TypeId),
+    /// The entity must have the component and it must have been added since the query's tick.
+    Added(TypeId),
+    /// The entity must have the component and it must have been added or changed since the
+    /// query's tick.
+    Changed(TypeId),
+    /// Every filter must match.
+    And(Vec<Filter>),
+    /// At least one filter must match.
+    Or(Vec<Filter>),
+}
+
+impl Filter {
+    /// Checks if the entity in the given slot matches the filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `entities` - The entities the filter is used on.
+    /// * `index` - The slot index of the entity.
+    /// * `since_tick` - The tick from which the components are considered added or changed.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the entity matches the filter, `false` otherwise.
+    pub fn matches(&self, entities: &Entities, index: usize, since_tick: u64) -> bool {
+        match self {
+            Filter::With(type_id) => Self::has_component(entities, type_id, index),
+            Filter::Without(type_id) => !Self::has_component(entities, type_id, index),
+            Filter::Added(type_id) => entities
+                .get_storage_by_type_id(type_id)
+                .and_then(|storage| storage.get_ticks(index))
+                .is_some_and(|ticks| ticks.added() >= since_tick),
+            Filter::Changed(type_id) => entities
+                .get_storage_by_type_id(type_id)
+                .and_then(|storage| storage.get_ticks(index))
+                .is_some_and(|ticks| ticks.changed() >= since_tick),
+            Filter::And(filters) => filters
+                .iter()
+                .all(|filter| filter.matches(entities, index, since_tick)),
+            Filter::Or(filters) => filters
+                .iter()
+                .any(|filter| filter.matches(entities, index, since_tick)),
+        }
+    }
+
+    /// Checks if the entity in the given slot has the component with the given type id.
+    fn has_component(entities: &Entities, type_id: &TypeId, index: usize) -> bool {
+        entities
+            .get_bitmask(type_id)
+            .is_some_and(|mask| entities.map[index].contains(mask))
+    }
+}
+
+/// A filter described as a type, so that it can be used in typed queries and systems.
+///
+/// It is implemented for `With<T>`, `Without<T>`, `Added<T>`, `Changed<T>`, `Or<(..)>` and
+/// tuples of them, which require every filter of the tuple to match.
+pub trait QueryFilter {
+    /// Adds the filters described by this type to the given vector.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` - The vector the filters are added to.
+    fn filters(filters: &mut Vec<Filter>);
+}
+
+/// Matches the entities that have the component `T`, without fetching it.
+pub struct With<T>(PhantomData<T>);
+
+/// Matches the entities that don't have the component `T`.
+pub struct Without<T>(PhantomData<T>);
+
+/// Matches the entities whose component `T` was added since the last time the query ran.
+pub struct Added<T>(PhantomData<T>);
+
+/// Matches the entities whose component `T` was added or changed since the last time the query
+/// ran.
+pub struct Changed<T>(PhantomData<T>);
+
+/// Matches the entities that match at least one of the filters of the tuple `T`.
+pub struct Or<T>(PhantomData<T>);
+
+impl<T: Any> QueryFilter for With<T> {
+    fn filters(filters: &mut Vec<Filter>) {
+        filters.push(Filter::With(TypeId::of::<T>()));
+    }
+}
+
+impl<T: Any> QueryFilter for Without<T> {
+    fn filters(filters: &mut Vec<Filter>) {
+        filters.push(Filter::Without(TypeId::of::<T>()));
+    }
+}
+
+impl<T: Any> QueryFilter for Added<T> {
+    fn filters(filters: &mut Vec<Filter>) {
+        filters.push(Filter::Added(TypeId::of::<T>()));
+    }
+}
+
+impl<T: Any> QueryFilter for Changed<T> {
+    fn filters(filters: &mut Vec<Filter>) {
+        filters.push(Filter::Changed(TypeId::of::<T>()));
+    }
+}
+
+impl QueryFilter for () {
+    fn filters(_filters: &mut Vec<Filter>) {}
+}
+
+/// Implements `QueryFilter` for a tuple of filters and for an `Or` of them.
+macro_rules! impl_query_filter_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: QueryFilter),*> QueryFilter for ($($name,)*) {
+            fn filters(filters: &mut Vec<Filter>) {
+                $($name::filters(filters);)*
+            }
+        }
+
+        impl<$($name: QueryFilter),*> QueryFilter for Or<($($name,)*)> {
+            fn filters(filters: &mut Vec<Filter>) {
+                let mut any = vec![];
+
+                $(
+                    let mut all = vec![];
+                    $name::filters(&mut all);
+                    any.push(Filter::And(all));
+                )*
+
+                filters.push(Filter::Or(any));
+            }
+        }
+    };
+}
+
+impl_query_filter_tuple!(A);
+impl_query_filter_tuple!(A, B);
+impl_query_filter_tuple!(A, B, C);
+impl_query_filter_tuple!(A, B, C, D);
+impl_query_filter_tuple!(A, B, C, D, E);
+impl_query_filter_tuple!(A, B, C, D, E, F);
+impl_query_filter_tuple!(A, B, C, D, E, F, G);
+impl_query_filter_tuple!(A, B, C, D, E, F, G, H);
+
+#[cfg(test)]
+mod tests {
+    use eyre::Result;
+
+    use crate::prelude::*;
+    use super::*;
+
+    #[test]
+    fn typed_filters_describe_their_filters() {
+        let mut filters = vec![];
+        <(Without<Health>, Or<(Added<Speed>, (With<Health>, Changed<Speed>))>)>::filters(&mut filters);
+
+        let health = TypeId::of::<Health>();
+        let speed = TypeId::of::<Speed>();
+
+        assert_eq!(filters, vec![
+            Filter::Without(health),
+            Filter::Or(vec![
+                Filter::And(vec![Filter::Added(speed)]),
+                Filter::And(vec![Filter::With(health), Filter::Changed(speed)]),
+            ]),
+        ]);
+    }
+
+    #[test]
+    fn match_filters() -> Result<()> {
+        let mut entities = Entities::default();
+        entities.register_component::<Health>();
+        entities.register_component::<Speed>();
+
+        let both = entities
+            .create_entity()
+            .with_component(Health(100))?
+            .with_component(Speed)?
+            .entity();
+        entities.increment_change_tick();
+        let speed = entities
+            .create_entity()
+            .with_component(Speed)?
+            .entity();
+
+        let health_id = TypeId::of::<Health>();
+        let speed_id = TypeId::of::<Speed>();
+
+        let without_health = Filter::Without(health_id);
+        assert!(!without_health.matches(&entities, both.index(), 0));
+        assert!(without_health.matches(&entities, speed.index(), 0));
+
+        let added_speed = Filter::Added(speed_id);
+        assert!(added_speed.matches(&entities, both.index(), 0));
+        assert!(!added_speed.matches(&entities, both.index(), 1));
+        assert!(added_speed.matches(&entities, speed.index(), 1));
+
+        let either = Filter::Or(vec![Filter::With(health_id), Filter::Added(speed_id)]);
+        assert!(either.matches(&entities, both.index(), 1));
+        assert!(either.matches(&entities, speed.index(), 1));
+        assert!(!either.matches(&entities, speed.index(), 2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn changed_components_are_detected() -> Result<()> {
+        let mut entities = Entities::default();
+        entities.register_component::<Health>();
+
+        let entity = entities
+            .create_entity()
+            .with_component(Health(100))?
+            .entity();
+        entities.increment_change_tick();
+
+        let changed = Filter::Changed(TypeId::of::<Health>());
+        assert!(!changed.matches(&entities, entity.index(), 1));
+
+        // Borrowing the component mutably without writing it doesn't mark it as changed
+        assert_eq!(QueryEntity::new(entity, &entities).get_component_mut::<Health>()?.0, 100);
+        assert!(!changed.matches(&entities, entity.index(), 1));
+
+        // Writing the component marks it as changed, but not as added
+        QueryEntity::new(entity, &entities).get_component_mut::<Health>()?.0 -= 10;
+
+        assert!(changed.matches(&entities, entity.index(), 1));
+        assert!(!Filter::Added(TypeId::of::<Health>()).matches(&entities, entity.index(), 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn filters_of_unregistered_components_see_them_as_missing() {
+        let mut entities = Entities::default();
+        let entity = entities.create_entity().entity();
+
+        assert!(Filter::Without(TypeId::of::<Health>()).matches(&entities, entity.index(), 0));
+        assert!(!Filter::With(TypeId::of::<Health>()).matches(&entities, entity.index(), 0));
+        assert!(!Filter::Or(vec![Filter::Changed(TypeId::of::<Speed>())]).matches(&entities, entity.index(), 0));
+    }
+
+    struct Health(pub u32);
+    struct Speed;
+}
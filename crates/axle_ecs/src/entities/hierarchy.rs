@@ -0,0 +1,286 @@
+use std::ops::Deref;
+use eyre::Result;
+
+use crate::prelude::*;
+use super::Entities;
+
+/// The parent of an entity.
+///
+/// It is maintained by `Entities::set_parent` and `Entities::remove_parent`, together with the
+/// `Children` component of the parent, so it can't be modified directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parent(Entity);
+
+impl Parent {
+    /// Gets the handle of the parent entity.
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+/// The children of an entity, in the order they were attached to it.
+///
+/// It is maintained by `Entities::set_parent` and `Entities::remove_parent`, together with the
+/// `Parent` component of the children, so it can't be modified directly.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Children(Vec<Entity>);
+
+impl Deref for Children {
+    type Target = [Entity];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Entities {
+    /// Makes an entity the child of another one, detaching it from its previous parent if it had one.
+    ///
+    /// # Arguments
+    ///
+    /// * `child` - The handle of the entity to attach.
+    /// * `parent` - The handle of the new parent.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If one of the entities doesn't exist, or if the child is the parent itself or one of its
+    /// ancestors, an error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::entities::Entities;
+    ///
+    /// let mut entities = Entities::default();
+    /// let car = entities.create_entity().entity();
+    /// let wheel = entities.create_entity().entity();
+    ///
+    /// entities.set_parent(wheel, car).unwrap();
+    ///
+    /// assert_eq!(entities.get_parent(wheel), Some(car));
+    /// assert_eq!(entities.get_children(car), vec![wheel]);
+    /// ```
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) -> Result<()> {
+        self.validate_entity(child)?;
+        self.validate_entity(parent)?;
+
+        if child == parent || self.is_ancestor_of(child, parent) {
+            return Err(CustomErrors::HierarchyCycle.into());
+        }
+
+        self.detach_from_parent(child);
+        self.add_component_by_entity_id(Parent(parent), child)?;
+
+        let mut children = self.get_children(parent);
+        children.push(child);
+        self.add_component_by_entity_id(Children(children), parent)
+    }
+
+    /// Detaches an entity from its parent, making it a root entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `child` - The handle of the entity to detach.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the entity doesn't exist, an error is returned.
+    pub fn remove_parent(&mut self, child: Entity) -> Result<()> {
+        self.validate_entity(child)?;
+        self.detach_from_parent(child);
+
+        Ok(())
+    }
+
+    /// Gets the parent of an entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity.
+    ///
+    /// # Returns
+    ///
+    /// The handle of the parent if the entity exists and has one, otherwise `None`.
+    pub fn get_parent(&self, entity: Entity) -> Option<Entity> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+
+        self.get_storage::<Parent>()?
+            .get(entity.index())
+            .map(|parent| parent.borrow().get())
+    }
+
+    /// Gets the children of an entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity.
+    ///
+    /// # Returns
+    ///
+    /// The handles of the children, empty if the entity doesn't exist or has no children.
+    pub fn get_children(&self, entity: Entity) -> Vec<Entity> {
+        if !self.is_alive(entity) {
+            return vec![];
+        }
+
+        self.get_storage::<Children>()
+            .and_then(|children| children.get(entity.index()))
+            .map(|children| children.borrow().0.clone())
+            .unwrap_or_default()
+    }
+
+    /// Deletes an entity together with all its descendants.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity to delete.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the entity doesn't exist, an error is returned.
+    pub fn delete_entity_recursive(&mut self, entity: Entity) -> Result<()> {
+        self.validate_entity(entity)?;
+
+        let mut to_delete = vec![entity];
+        let mut next = 0;
+
+        while next < to_delete.len() {
+            to_delete.extend(self.get_children(to_delete[next]));
+            next += 1;
+        }
+
+        for entity in to_delete {
+            self.delete_entity_by_id(entity)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes an entity that is being deleted from the hierarchy: it is detached from its
+    /// parent, and its children become root entities.
+    pub(crate) fn unlink_hierarchy(&mut self, entity: Entity) {
+        self.detach_from_parent(entity);
+
+        for child in self.get_children(entity) {
+            self.delete_component_by_entity_id::<Parent>(child).ok();
+        }
+    }
+
+    /// Checks if `ancestor` is the parent of `entity`, or the parent of its parent, and so on.
+    fn is_ancestor_of(&self, ancestor: Entity, entity: Entity) -> bool {
+        let mut current = self.get_parent(entity);
+
+        while let Some(parent) = current {
+            if parent == ancestor {
+                return true;
+            }
+
+            current = self.get_parent(parent);
+        }
+
+        false
+    }
+
+    /// Removes the `Parent` component of an entity and the entity from the `Children` of its parent.
+    fn detach_from_parent(&mut self, child: Entity) {
+        let Some(parent) = self.get_parent(child) else {
+            return;
+        };
+
+        self.delete_component_by_entity_id::<Parent>(child).ok();
+
+        let mut children = self.get_children(parent);
+        children.retain(|sibling| *sibling != child);
+
+        if children.is_empty() {
+            self.delete_component_by_entity_id::<Children>(parent).ok();
+        } else {
+            self.add_component_by_entity_id(Children(children), parent).ok();
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reparenting_keeps_both_sides_in_sync() -> Result<()> {
+        let mut entities = Entities::default();
+        let first = entities.create_entity().entity();
+        let second = entities.create_entity().entity();
+        let child = entities.create_entity().entity();
+
+        entities.set_parent(child, first)?;
+        entities.set_parent(child, second)?;
+
+        assert_eq!(entities.get_parent(child), Some(second));
+        assert!(entities.get_children(first).is_empty());
+        assert_eq!(entities.get_children(second), vec![child]);
+
+        entities.remove_parent(child)?;
+        assert_eq!(entities.get_parent(child), None);
+        assert!(entities.get_children(second).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cycles_are_rejected() -> Result<()> {
+        let mut entities = Entities::default();
+        let root = entities.create_entity().entity();
+        let middle = entities.create_entity().entity();
+        let leaf = entities.create_entity().entity();
+
+        entities.set_parent(middle, root)?;
+        entities.set_parent(leaf, middle)?;
+
+        assert!(entities.set_parent(root, leaf).is_err());
+        assert!(entities.set_parent(root, root).is_err());
+        assert_eq!(entities.get_parent(root), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn deleting_entities_updates_the_hierarchy() -> Result<()> {
+        let mut entities = Entities::default();
+        let root = entities.create_entity().entity();
+        let middle = entities.create_entity().entity();
+        let leaf = entities.create_entity().entity();
+        let other = entities.create_entity().entity();
+
+        entities.set_parent(middle, root)?;
+        entities.set_parent(leaf, middle)?;
+        entities.set_parent(other, root)?;
+
+        // Deleting a single entity turns its children into root entities
+        entities.delete_entity_by_id(middle)?;
+        assert_eq!(entities.get_children(root), vec![other]);
+        assert_eq!(entities.get_parent(leaf), None);
+
+        entities.set_parent(leaf, other)?;
+        entities.delete_entity_recursive(root)?;
+
+        assert!(!entities.is_alive(root));
+        assert!(!entities.is_alive(other));
+        assert!(!entities.is_alive(leaf));
+
+        Ok(())
+    }
+}
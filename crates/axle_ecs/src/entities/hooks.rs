@@ -0,0 +1,286 @@
+use std::{any::Any, fmt::Debug};
+
+use crate::prelude::*;
+use crate::sync::MaybeSendSync;
+use super::Entities;
+
+/// A function called with an entity and its component when the component is added, inserted or removed.
+#[cfg(not(feature = "parallel"))]
+pub type ComponentHook<T> = Box<dyn Fn(Entity, &T)>;
+/// A function called with an entity and its component when the component is added, inserted or removed.
+#[cfg(feature = "parallel")]
+pub type ComponentHook<T> = Box<dyn Fn(Entity, &T) + Send + Sync>;
+
+/// A function called with an entity right before it is deleted.
+#[cfg(not(feature = "parallel"))]
+pub type DespawnObserver = Box<dyn Fn(Entity)>;
+/// A function called with an entity right before it is deleted.
+#[cfg(feature = "parallel")]
+pub type DespawnObserver = Box<dyn Fn(Entity) + Send + Sync>;
+
+/// The lifecycle hooks of a component type.
+///
+/// They are stored together with the components of the type, so that they can be called with the
+/// component even when it is removed through the type-erased storage.
+pub struct ComponentHooks<T> {
+    /// Called when the component is added to an entity that didn't have it.
+    on_add: Vec<ComponentHook<T>>,
+    /// Called every time the component is inserted, after `on_add` and when it replaces a previous one.
+    on_insert: Vec<ComponentHook<T>>,
+    /// Called right before the component is removed from an entity, or the entity is deleted.
+    on_remove: Vec<ComponentHook<T>>,
+}
+
+impl<T> Default for ComponentHooks<T> {
+    fn default() -> Self {
+        Self {
+            on_add: vec![],
+            on_insert: vec![],
+            on_remove: vec![],
+        }
+    }
+}
+
+impl<T> Debug for ComponentHooks<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ComponentHooks")
+            .field("on_add", &self.on_add.len())
+            .field("on_insert", &self.on_insert.len())
+            .field("on_remove", &self.on_remove.len())
+            .finish()
+    }
+}
+
+impl<T> ComponentHooks<T> {
+    /// Calls the hooks of a component that was just inserted.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity.
+    /// * `component` - The inserted component.
+    /// * `added` - Whether the entity didn't have the component before.
+    pub fn inserted(&self, entity: Entity, component: &T, added: bool) {
+        if added {
+            self.on_add.iter().for_each(|hook| hook(entity, component));
+        }
+
+        self.on_insert.iter().for_each(|hook| hook(entity, component));
+    }
+
+    /// Calls the hooks of a component that is about to be removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity.
+    /// * `component` - The component being removed.
+    pub fn removed(&self, entity: Entity, component: &T) {
+        self.on_remove.iter().for_each(|hook| hook(entity, component));
+    }
+}
+
+/// The observers called when an entity is deleted.
+#[derive(Default)]
+pub(crate) struct DespawnObservers(Vec<DespawnObserver>);
+
+impl Debug for DespawnObservers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("DespawnObservers").field(&self.0.len()).finish()
+    }
+}
+
+impl DespawnObservers {
+    /// Calls every observer with the entity being deleted.
+    pub fn notify(&self, entity: Entity) {
+        self.0.iter().for_each(|observer| observer(entity));
+    }
+}
+
+impl Entities {
+    /// Adds a hook that is called when a component is added to an entity that didn't have it.
+    ///
+    /// The component type is registered if it wasn't yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component.
+    /// * `hook` - The function called with the entity and its new component.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::{atomic::{AtomicUsize, Ordering}, Arc};
+    /// use axle_ecs::entities::Entities;
+    ///
+    /// let added = Arc::new(AtomicUsize::new(0));
+    /// let counter = added.clone();
+    ///
+    /// let mut entities = Entities::default();
+    /// entities.on_add::<u32>(move |_, _| {
+    ///     counter.fetch_add(1, Ordering::Relaxed);
+    /// });
+    ///
+    /// let entity = entities.create_entity().with_component(10_u32).unwrap().entity();
+    /// // Replacing the component doesn't add it again
+    /// entities.add_component_by_entity_id(20_u32, entity).unwrap();
+    ///
+    /// assert_eq!(added.load(Ordering::Relaxed), 1);
+    /// ```
+    pub fn on_add<T: Any + MaybeSendSync>(&mut self, hook: impl Fn(Entity, &T) + MaybeSendSync + 'static) {
+        self.hooks_mut::<T>().on_add.push(Box::new(hook));
+    }
+
+    /// Adds a hook that is called every time a component is inserted into an entity, both when it
+    /// is added and when it replaces the previous one.
+    ///
+    /// The component type is registered if it wasn't yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component.
+    /// * `hook` - The function called with the entity and its new component.
+    pub fn on_insert<T: Any + MaybeSendSync>(&mut self, hook: impl Fn(Entity, &T) + MaybeSendSync + 'static) {
+        self.hooks_mut::<T>().on_insert.push(Box::new(hook));
+    }
+
+    /// Adds a hook that is called right before a component is removed from an entity, including
+    /// when the entity is deleted. The component is dropped after the hook returns.
+    ///
+    /// Replacing a component doesn't call this hook.
+    ///
+    /// The component type is registered if it wasn't yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component.
+    /// * `hook` - The function called with the entity and the component being removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use axle_ecs::entities::Entities;
+    ///
+    /// let removed = Arc::new(Mutex::new(vec![]));
+    /// let log = removed.clone();
+    ///
+    /// let mut entities = Entities::default();
+    /// entities.on_remove::<u32>(move |_, value| log.lock().unwrap().push(*value));
+    ///
+    /// let entity = entities.create_entity().with_component(10_u32).unwrap().entity();
+    /// entities.delete_entity_by_id(entity).unwrap();
+    ///
+    /// assert_eq!(*removed.lock().unwrap(), vec![10]);
+    /// ```
+    pub fn on_remove<T: Any + MaybeSendSync>(&mut self, hook: impl Fn(Entity, &T) + MaybeSendSync + 'static) {
+        self.hooks_mut::<T>().on_remove.push(Box::new(hook));
+    }
+
+    /// Adds an observer that is called right before any entity is deleted, while it still has
+    /// its components.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - The function called with the entity being deleted.
+    pub fn observe_despawn(&mut self, observer: impl Fn(Entity) + MaybeSendSync + 'static) {
+        self.despawn_observers.0.push(Box::new(observer));
+    }
+
+    /// Gets the hooks of a component type, registering the type if it wasn't yet.
+    fn hooks_mut<T: Any + MaybeSendSync>(&mut self) -> &mut ComponentHooks<T> {
+        self.register_component::<T>();
+
+        &mut self.get_storage_mut::<T>().unwrap().hooks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::{AtomicUsize, Ordering}, Arc, Mutex};
+    use eyre::Result;
+
+    use super::*;
+    use crate::entities::storage::ComponentStorage;
+
+    #[test]
+    fn hooks_are_called_on_every_change() -> Result<()> {
+        let log = Arc::new(Mutex::new(vec![]));
+        let mut entities = Entities::default();
+
+        let add_log = log.clone();
+        entities.on_add::<Health>(move |_, health| add_log.lock().unwrap().push(format!("add {}", health.0)));
+        let insert_log = log.clone();
+        entities.on_insert::<Health>(move |_, health| insert_log.lock().unwrap().push(format!("insert {}", health.0)));
+        let remove_log = log.clone();
+        entities.on_remove::<Health>(move |_, health| remove_log.lock().unwrap().push(format!("remove {}", health.0)));
+
+        let entity = entities.create_entity().with_component(Health(1))?.entity();
+        entities.add_component_by_entity_id(Health(2), entity)?;
+        entities.delete_component_by_entity_id::<Health>(entity)?;
+        // Deleting a component the entity doesn't have calls nothing
+        entities.delete_component_by_entity_id::<Health>(entity)?;
+        entities.add_component_by_entity_id(Health(3), entity)?;
+        entities.delete_entity_by_id(entity)?;
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["add 1", "insert 1", "insert 2", "remove 2", "add 3", "insert 3", "remove 3"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn despawn_observers_are_called_before_deleting() -> Result<()> {
+        let despawned = Arc::new(Mutex::new(vec![]));
+        let mut entities = Entities::default();
+
+        let log = despawned.clone();
+        entities.observe_despawn(move |entity| log.lock().unwrap().push(entity));
+
+        let parent = entities.create_entity().entity();
+        let child = entities.create_entity().entity();
+        let other = entities.create_entity().entity();
+        entities.set_parent(child, parent)?;
+
+        entities.delete_entity_recursive(parent)?;
+        assert!(entities.delete_entity_by_id(parent).is_err());
+
+        assert_eq!(*despawned.lock().unwrap(), vec![parent, child]);
+        assert!(entities.is_alive(other));
+
+        Ok(())
+    }
+
+    #[test]
+    fn removed_components_are_dropped() -> Result<()> {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let mut entities = Entities::default();
+
+        let first = entities.create_entity().with_component(Tracked(drops.clone()))?.entity();
+        let second = entities.create_entity().with_component(Tracked(drops.clone()))?.entity();
+
+        // Replacing a component drops the previous one
+        entities.add_component_by_entity_id(Tracked(drops.clone()), first)?;
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+
+        entities.delete_component_by_entity_id::<Tracked>(first)?;
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+
+        entities.delete_entity_by_id(second)?;
+        assert_eq!(drops.load(Ordering::Relaxed), 3);
+        assert!(entities.get_storage::<Tracked>().unwrap().is_empty());
+
+        Ok(())
+    }
+
+    struct Health(u32);
+
+    /// Counts how many times it was dropped.
+    struct Tracked(Arc<AtomicUsize>);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
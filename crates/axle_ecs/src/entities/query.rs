@@ -2,21 +2,29 @@ use std::any::{Any, TypeId};
 use eyre::Result;
 
 use crate::prelude::*;
-use super::Entities;
+use super::{bit_mask::BitMask, filter::Filter, Entities};
 
-pub type QueryIndexes = Vec<usize>;
-pub type QueryComponents = Vec<Vec<Component>>;
+pub type QueryIndexes = Vec<Entity>;
+pub type QueryComponents<'a> = Vec<Vec<Component<'a>>>;
 
 /// A query that can be used to query the entities in the world and its components in an easy way.
 #[derive(Debug)]
 pub struct Query<'a> {
     /// The bit mask of the query. This is used to filter out entities that don't have all the
     /// components that are required in the query.
-    map: u32,
+    map: BitMask,
     /// The entities that the query is run on.
     entities: &'a Entities,
     /// A vector of type ids of the components that are required in the query.
     type_ids: Vec<TypeId>,
+    /// The filters the entities must match besides having the required components.
+    filters: Vec<Filter>,
+    /// The components added or changed at this tick or later are considered added or changed by
+    /// the filters.
+    since_tick: u64,
+    /// Whether one of the required components was never registered, in which case no entity can
+    /// match the query.
+    missing_component: bool,
 }
 
 impl<'a> Query<'a> {
@@ -30,94 +38,137 @@ impl<'a> Query<'a> {
     ///
     /// A new `Query` struct.
     pub fn new(entities: &'a Entities) -> Self {
-        Self { entities, map: 0, type_ids: vec![] }
+        Self {
+            entities,
+            map: BitMask::default(),
+            type_ids: vec![],
+            filters: vec![],
+            since_tick: entities.frame_tick(),
+            missing_component: false,
+        }
     }
 
     /// Adds a component to the query.
     ///
+    /// If the component was never registered, no entity has it, so the query matches no entities.
+    ///
     /// # Arguments
     ///
     /// * `T` - The type of the component to add.
     ///
     /// # Returns
     ///
-    /// A result that contains a mutable reference to the `Query` struct if succeeds or an error if it fails.
+    /// A result that contains a mutable reference to the `Query` struct.
+    pub fn with_component<T: Any>(&mut self) -> Result<&mut Self> {
+        self.with_component_by_type_id(TypeId::of::<T>())
+    }
+
+    /// Adds a component to the query by its type id.
     ///
-    /// # Errors
+    /// If the component was never registered, no entity has it, so the query matches no entities.
     ///
-    /// If the component was not registered, an error is returned.
-    pub fn with_component<T: Any>(&mut self) -> Result<&mut Self> {
-        let type_id = TypeId::of::<T>();
-        if let Some(bit_mask) = self.entities.get_bitmask(&type_id) {
-            self.map |= bit_mask;
-            self.type_ids.push(type_id);
-        }
-        else {
-            return Err(CustomErrors::ComponentNotRegistered.into());
+    /// # Arguments
+    ///
+    /// * `type_id` - The type id of the component to add.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains a mutable reference to the `Query` struct.
+    pub fn with_component_by_type_id(&mut self, type_id: TypeId) -> Result<&mut Self> {
+        match self.entities.get_bitmask(&type_id) {
+            Some(bit_mask) => self.map.insert(bit_mask),
+            None => self.missing_component = true,
         }
 
+        self.type_ids.push(type_id);
+
         Ok(self)
     }
 
-    /// Adds a component to the query by its type id.
+    /// Adds a filter to the query.
     ///
     /// # Arguments
     ///
-    /// * `type_id` - The type id of the component to add.
+    /// * `filter` - The filter the entities must match.
+    ///
+    /// The components that were never registered are treated as missing from every entity.
     ///
     /// # Returns
     ///
-    /// A result that contains a mutable reference to the `Query` struct if succeeds or an error if it fails.
+    /// A result that contains a mutable reference to the `Query` struct.
     ///
-    /// # Errors
+    /// # Example
     ///
-    /// If the component was not registered, an error is returned.
-    pub fn with_component_by_type_id(&mut self, type_id: TypeId) -> Result<&mut Self> {
-        if let Some(bit_mask) = self.entities.get_bitmask(&type_id) {
-            self.map |= bit_mask;
-            self.type_ids.push(type_id);
-        }
-        else {
-            return Err(CustomErrors::ComponentNotRegistered.into());
-        }
+    /// ```
+    /// use std::any::TypeId;
+    /// use axle_ecs::World;
+    /// use axle_ecs::entities::filter::Filter;
+    ///
+    /// let mut world = World::new();
+    /// world.register_component::<u32>();
+    /// world.register_component::<f32>();
+    /// world.create_entity().with_component(10_u32).unwrap();
+    /// world
+    ///     .create_entity()
+    ///     .with_component(20_u32).unwrap()
+    ///     .with_component(1.0_f32).unwrap();
+    ///
+    /// let (indexes, _) = world
+    ///     .query_builder()
+    ///     .with_component::<u32>().unwrap()
+    ///     .with_filter(Filter::Without(TypeId::of::<f32>())).unwrap()
+    ///     .run();
+    ///
+    /// assert_eq!(indexes.len(), 1);
+    /// ```
+    pub fn with_filter(&mut self, filter: Filter) -> Result<&mut Self> {
+        self.filters.push(filter);
 
         Ok(self)
     }
 
+    /// Sets the tick from which the components are considered added or changed by the filters.
+    ///
+    /// By default it is the tick at which the last run of the systems started, so the `Added`
+    /// and `Changed` filters match what happened during the current frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `tick` - The change tick.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the `Query` struct.
+    pub fn changed_since(&mut self, tick: u64) -> &mut Self {
+        self.since_tick = tick;
+        self
+    }
+
     /// Runs the query and returns the results.
     ///
     /// # Returns
     ///
     /// A tuple containing two items:
     ///
-    /// - The first item is a vector of indexes of the entities that matched the query.
+    /// - The first item is a vector of handles of the entities that matched the query.
     ///
     /// - The second item is a vector of vectors of components that matched the query.
-    pub fn run(&self) -> (QueryIndexes, QueryComponents) {
-        // Get the indexes of the entities that matched the query
-        let indexes: Vec<usize> = self.entities.map
-            .iter()
-            .enumerate()
-            .filter_map(|(index, entity_map)| {
-                if entity_map & self.map == self.map {
-                    Some(index)
-                }
-                else {
-                    None
-                }
-            })
-            .collect();
+    pub fn run(&self) -> (QueryIndexes, QueryComponents<'a>) {
+        // Get the handles of the entities that matched the query
+        let indexes = self.matching_entities();
 
         // Get the components of the entities that matched the query
         let mut result = vec![];
 
         for type_id in &self.type_ids {
-            let entity_components = self.entities.components.get(type_id).unwrap();
-            let mut components_to_keep = vec![];
-
-            for index in &indexes {
-                components_to_keep.push(entity_components[*index].as_ref().unwrap().clone());
-            }
+            let components_to_keep = match self.entities.get_storage_by_type_id(type_id) {
+                Some(entity_components) => indexes
+                    .iter()
+                    .filter_map(|entity| entity_components.get_cell(entity.index()))
+                    .collect(),
+                // Only happens for components that were never registered, which no entity matched
+                None => vec![],
+            };
 
             result.push(components_to_keep);
         }
@@ -132,29 +183,67 @@ impl<'a> Query<'a> {
     /// # Returns
     ///
     /// A vector of `QueryEntity`s
-    pub fn run_entity(&self) -> Vec<QueryEntity> {
-        // Iterate over the entities and their bitmasks
-        self.entities.map
-            .iter()
-            .enumerate()
-            .filter_map(|(index, entity_map)| {
-                // Check if the entity's bitmask matches the query's bitmask
-                if entity_map & self.map == self.map {
-                    // If it matches, create a new `QueryEntity` and return it
-                    Some(QueryEntity::new(index, self.entities))
-                }
-                else {
-                    // If it doesn't match, return None
-                    None
-                }
-            })
+    pub fn run_entity(&self) -> Vec<QueryEntity<'a>> {
+        self.matching_entities()
+            .into_iter()
+            .map(|entity| QueryEntity::new(entity, self.entities))
+            .collect()
+    }
+
+    /// Gets the handles of the entities that match the query, sorted by their slot index.
+    fn matching_entities(&self) -> Vec<Entity> {
+        let mut indexes: Vec<usize> = self.candidate_indexes().collect();
+        indexes.sort_unstable();
+
+        indexes
+            .into_iter()
+            .filter(|index| self.matches(*index))
+            .filter_map(|index| self.entities.entity_at(index))
             .collect()
     }
+
+    /// Gets the slot indexes of the entities that may match the query.
+    ///
+    /// Instead of walking every entity slot, only the entities that have the least common
+    /// component of the query are returned. A query without components returns every slot.
+    pub(crate) fn candidate_indexes(&self) -> Box<dyn Iterator<Item = usize> + 'a> {
+        let entities = self.entities;
+
+        if self.missing_component {
+            return Box::new(std::iter::empty());
+        }
+
+        let smallest_storage = self.type_ids
+            .iter()
+            .filter_map(|type_id| entities.get_storage_by_type_id(type_id))
+            .min_by_key(|storage| storage.len());
+
+        match smallest_storage {
+            Some(storage) => Box::new(storage.indexes().iter().copied()),
+            None => Box::new(0..entities.map.len()),
+        }
+    }
+
+    /// Checks if the entity in the given slot is in use, has all the components of the query and
+    /// matches its filters.
+    pub(crate) fn matches(&self, index: usize) -> bool {
+        !self.missing_component
+            && self.entities.alive.get(index).copied().unwrap_or(false)
+            && self.entities.map[index].contains(&self.map)
+            && self.filters
+                .iter()
+                .all(|filter| filter.matches(self.entities, index, self.since_tick))
+    }
+
+    /// Gets the entities that the query is run on.
+    pub(crate) fn entities(&self) -> &'a Entities {
+        self.entities
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::cell::{Ref, RefMut};
+    use crate::sync::ComponentRef;
 
     use crate::entities::query_entity::QueryEntity;
     use super::*;
@@ -170,7 +259,7 @@ mod tests {
             .with_component::<u32>()?
             .with_component::<f32>()?;
 
-        assert_eq!(query.map, 3);
+        assert_eq!(query.map, BitMask::from(3));
         assert_eq!(TypeId::of::<u32>(), query.type_ids[0]);
         assert_eq!(TypeId::of::<f32>(), query.type_ids[1]);
 
@@ -230,8 +319,8 @@ mod tests {
         let second_f32 = wrapped_second_f32.downcast_ref::<f32>().unwrap();
         assert_eq!(*second_f32, 25.0);
 
-        assert_eq!(indexes[0], 0);
-        assert_eq!(indexes[1], 3);
+        assert_eq!(indexes[0].index(), 0);
+        assert_eq!(indexes[1].index(), 3);
 
         Ok(())
     }
@@ -257,8 +346,8 @@ mod tests {
         assert_eq!(entitities.len(), 1);
 
         for entity in entitities {
-            assert_eq!(entity.id, 0);
-            let health: Ref<u32> = entity.get_component::<u32>()?;
+            assert_eq!(entity.id.index(), 0);
+            let health: ComponentRef<u32> = entity.get_component::<u32>()?;
             assert_eq!(*health, 100);
         }
 
@@ -286,8 +375,8 @@ mod tests {
         assert_eq!(entitities.len(), 1);
 
         for entity in entitities {
-            assert_eq!(entity.id, 0);
-            let mut health: RefMut<u32> = entity.get_component_mut::<u32>()?;
+            assert_eq!(entity.id.index(), 0);
+            let mut health: Mut<u32> = entity.get_component_mut::<u32>()?;
             assert_eq!(*health, 100);
             *health += 1;
         }
@@ -296,7 +385,7 @@ mod tests {
             .with_component::<u32>()?
             .run_entity();
         for entity in entitities {
-            let health: Ref<u32> = entity.get_component::<u32>()?;
+            let health: ComponentRef<u32> = entity.get_component::<u32>()?;
             assert_eq!(*health, 101);
         }
 
@@ -1,24 +1,28 @@
-use std::{any::{Any, TypeId}, cell::{Ref, RefCell, RefMut}, rc::Rc};
+use std::any::{Any, TypeId};
 use eyre::Result;
 
 use crate::prelude::*;
-use super::Entities;
-
-type ExtractedComponents = Vec<Option<Rc<RefCell<dyn Any>>>>;
+use crate::sync::{ComponentCell, ComponentRef};
+use super::{storage::Mut, typed_query::{self, ComponentAccess, QueryData}, Entities};
 
 /// A struct that represents an entity in a query.
 ///
 /// This struct is used in the query system to represent an entity that matches
 /// a query.
 ///
-/// It contains the id of the entity and a reference to the `Entities` struct
+/// It contains the handle of the entity and a reference to the `Entities` struct
 /// that it is a part of.
+///
+/// The entities passed to a system that declared its access only give access to the components
+/// it declared, so that the systems that run at the same time can't alias each other's components.
 #[derive(Clone, Copy)]
 pub struct QueryEntity<'a> {
-    /// The id of the entity.
-    pub id: usize,
+    /// The handle of the entity.
+    pub id: Entity,
     /// A reference to the `Entities` struct that this entity is a part of.
     entities: &'a Entities,
+    /// The components that can be accessed, or `None` if any of them can.
+    access: Option<&'a [ComponentAccess]>,
 }
 
 impl<'a> QueryEntity<'a> {
@@ -26,36 +30,74 @@ impl<'a> QueryEntity<'a> {
     ///
     /// # Arguments
     ///
-    /// * `id` - The id of the entity.
+    /// * `id` - The handle of the entity.
     /// * `entities` - A reference to the `Entities` struct that this entity is a part of.
     ///
     /// # Returns
     ///
     /// A new `QueryEntity` struct.
-    pub fn new(id: usize, entities: &'a Entities) -> Self {
-        Self { id, entities }
+    pub fn new(id: Entity, entities: &'a Entities) -> Self {
+        Self { id, entities, access: None }
     }
 
-    /// Gets the components of type `T` from the `Entities` struct that this `QueryEntity` is a part of.
+    /// Restricts the components that can be accessed through this `QueryEntity` to the given ones.
     ///
     /// # Arguments
     ///
-    /// * `T` - The type of the components to get.
+    /// * `access` - The components declared by the system the entity is passed to.
     ///
     /// # Returns
     ///
-    /// A reference to the components of type `T`.
+    /// The restricted `QueryEntity`.
+    pub(crate) fn with_access(mut self, access: &'a [ComponentAccess]) -> Self {
+        self.access = Some(access);
+        self
+    }
+
+    /// Checks that the component with the given type id can be accessed, and how.
     ///
     /// # Errors
     ///
-    /// If the component type `T` is not registered, an error is returned.
-    fn extract_components<T: Any>(&self) -> Result<&ExtractedComponents> {
-        let type_id = TypeId::of::<T>();
-        self
+    /// If the access is restricted and the component wasn't declared, or it is accessed mutably
+    /// but was only declared as read only, an error is returned.
+    fn check_access(&self, type_id: TypeId, mutable: bool) -> Result<()> {
+        let Some(access) = self.access else {
+            return Ok(());
+        };
+
+        let allowed = access
+            .iter()
+            .any(|declared| declared.type_id == type_id && (declared.mutable || !mutable));
+
+        if !allowed {
+            return Err(CustomErrors::UndeclaredComponentAccess.into());
+        }
+
+        Ok(())
+    }
+
+    /// Gets the cell that stores the component of type `T` of the entity pointed by this `QueryEntity`.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component to get.
+    ///
+    /// # Returns
+    ///
+    /// A reference to the cell of the entity's component of type `T`.
+    ///
+    /// # Errors
+    ///
+    /// If the component type `T` is not registered or the entity doesn't have it, an error is returned.
+    fn extract_component<T: Any>(&self) -> Result<&'a ComponentCell<T>> {
+        let components = self
             .entities
-            .components
-            .get(&type_id)
-            .ok_or_else(|| CustomErrors::ComponentNotRegistered.into())
+            .get_storage::<T>()
+            .ok_or(CustomErrors::ComponentNotRegistered)?;
+
+        components
+            .get(self.id.index())
+            .ok_or_else(|| CustomErrors::ComponentDataDoesNotExist.into())
     }
 
     /// Gets a reference to the components of type `T` from the `Entities` struct that this
@@ -71,41 +113,84 @@ impl<'a> QueryEntity<'a> {
     ///
     /// # Errors
     ///
-    /// If the component type `T` is not in the entity, an error is returned.
-    pub fn get_component<T: Any>(&self) -> Result<Ref<T>> {
-        let components = self.extract_components::<T>()?;
-        let borrowed_component = components[self.id]
-            .as_ref()
-            .ok_or(CustomErrors::ComponentDataDoesNotExist)?
-            .borrow();
-
-        Ok(
-            Ref::map(borrowed_component, |any: &dyn Any| any.downcast_ref::<T>().unwrap())
-        )
+    /// If the component type `T` is not in the entity, or the system didn't declare it, an error
+    /// is returned.
+    pub fn get_component<T: Any>(&self) -> Result<ComponentRef<'a, T>> {
+        self.check_access(TypeId::of::<T>(), false)?;
+
+        Ok(self.extract_component::<T>()?.borrow())
     }
 
     /// Gets a mutable reference to the component of type `T` from the entity pointed by this `QueryEntity`
     ///
+    /// The component is marked as changed when it is written through the returned `Mut`, so it
+    /// will then match the `Changed` filter.
+    ///
     /// # Arguments
     ///
     /// * `T` - The type of the components to get.
     ///
     /// # Returns
     ///
-    /// A mutable reference to the entity's component of type `T`.
+    /// A `Mut` to the entity's component of type `T`.
+    ///
+    /// # Errors
+    ///
+    /// If the component type `T` is not in the entity, or the system didn't declare it as
+    /// written, an error is returned.
+    pub fn get_component_mut<T: Any>(&self) -> Result<Mut<'a, T>> {
+        self.check_access(TypeId::of::<T>(), true)?;
+
+        let components = self
+            .entities
+            .get_storage::<T>()
+            .ok_or(CustomErrors::ComponentNotRegistered)?;
+
+        components
+            .get_mut(self.id.index(), self.entities.change_tick())
+            .ok_or_else(|| CustomErrors::ComponentDataDoesNotExist.into())
+    }
+
+    /// Gets several components of the entity pointed by this `QueryEntity` at once, already
+    /// downcasted to their types.
+    ///
+    /// # Arguments
+    ///
+    /// * `Q` - The components to get, for example `(&Transform, &mut RigidBody)`.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the references to the entity's components if succeeds or an error if it fails.
     ///
     /// # Errors
     ///
-    /// If the component type `T` is not in the entity, an error is returned.
-    pub fn get_component_mut<T: Any>(&self) -> Result<RefMut<T>> {
-        let components = self.extract_components::<T>()?;
-        let borrowed_component = components[self.id]
-            .as_ref()
-            .ok_or(CustomErrors::ComponentDataDoesNotExist)?
-            .borrow_mut();
-
-        Ok(
-            RefMut::map(borrowed_component, |any: &mut dyn Any| any.downcast_mut::<T>().unwrap())
-        )
+    /// If the entity doesn't have one of the required components, if a component is accessed
+    /// mutably more than once, or both mutably and immutably, or if the system didn't declare the
+    /// access, an error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::World;
+    ///
+    /// let mut world = World::new();
+    /// world.register_component::<u32>();
+    /// world.register_component::<f32>();
+    /// world
+    ///     .create_entity()
+    ///     .with_component(10_u32).unwrap()
+    ///     .with_component(1.5_f32).unwrap();
+    ///
+    /// let entities = world.query_builder().with_component::<u32>().unwrap().run_entity();
+    /// let (number, mut decimal) = entities[0].get_components::<(&u32, &mut f32)>().unwrap();
+    /// *decimal += *number as f32;
+    /// ```
+    pub fn get_components<Q: QueryData>(&self) -> Result<Q::Item<'a>> {
+        for access in typed_query::validated_access::<Q>()? {
+            self.check_access(access.type_id, access.mutable)?;
+        }
+
+        Q::fetch(Q::init_fetch(self.entities), self.id.index())
+            .ok_or_else(|| CustomErrors::ComponentDataDoesNotExist.into())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,437 @@
+use std::{
+    any::Any,
+    fmt::Debug,
+    hash::{BuildHasherDefault, Hasher},
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::entities::entity::Entity;
+use crate::sync::{ComponentCell, ComponentRefMut, MaybeSendSync};
+use super::hooks::ComponentHooks;
+
+/// The hasher used for the maps keyed by the type id of the components.
+pub type TypeIdBuildHasher = BuildHasherDefault<TypeIdHasher>;
+
+/// A hasher for `TypeId` keys.
+///
+/// A `TypeId` is already a hash computed by the compiler, so instead of hashing it again it is
+/// used as is. This makes the component lookups done for every entity in a query much cheaper.
+#[derive(Default)]
+pub struct TypeIdHasher {
+    hash: u64,
+}
+
+impl Hasher for TypeIdHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        // Fallback for keys that don't hash themselves as a single integer
+        for byte in bytes {
+            self.hash = self.hash.rotate_left(8) ^ u64::from(*byte);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.hash ^= value;
+    }
+
+    fn write_u128(&mut self, value: u128) {
+        self.hash ^= value as u64 ^ (value >> 64) as u64;
+    }
+}
+
+/// The ticks at which a component was added and last changed.
+///
+/// The ticks are compared against the tick a query was last run at to know if the component was
+/// added or changed since then.
+#[derive(Debug)]
+pub struct ComponentTicks {
+    /// The tick at which the component was added.
+    added: AtomicU64,
+    /// The tick at which the component was last changed.
+    changed: AtomicU64,
+}
+
+impl ComponentTicks {
+    /// Creates the ticks of a component added at the given tick.
+    ///
+    /// # Arguments
+    ///
+    /// * `tick` - The tick at which the component was added.
+    ///
+    /// # Returns
+    ///
+    /// A new `ComponentTicks` struct.
+    pub fn new(tick: u64) -> Self {
+        Self { added: AtomicU64::new(tick), changed: AtomicU64::new(tick) }
+    }
+
+    /// Gets the tick at which the component was added.
+    pub fn added(&self) -> u64 {
+        self.added.load(Ordering::Relaxed)
+    }
+
+    /// Gets the tick at which the component was last changed.
+    ///
+    /// A component is considered changed when it is added, replaced or written through a `Mut`.
+    pub fn changed(&self) -> u64 {
+        self.changed.load(Ordering::Relaxed)
+    }
+
+    /// Marks the component as changed at the given tick.
+    ///
+    /// # Arguments
+    ///
+    /// * `tick` - The tick at which the component was changed.
+    pub fn set_changed(&self, tick: u64) {
+        self.changed.store(tick, Ordering::Relaxed);
+    }
+}
+
+/// A mutable reference to a component that marks it as changed when it is written.
+///
+/// Borrowing a component mutably doesn't mark it as changed by itself, only dereferencing the
+/// `Mut` mutably does, so the `Changed` filter doesn't match the components that were only read.
+pub struct Mut<'a, T> {
+    /// The borrowed component.
+    value: ComponentRefMut<'a, T>,
+    /// The ticks of the borrowed component.
+    ticks: &'a ComponentTicks,
+    /// The tick the component is marked as changed at when it is written.
+    tick: u64,
+}
+
+impl<T> Deref for Mut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Mut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.ticks.set_changed(self.tick);
+        &mut self.value
+    }
+}
+
+impl<T: Debug> Debug for Mut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// Type-erased access to the storage of a single component type.
+///
+/// This trait lets `Entities` keep the storages of every component type in the same map while
+/// still being able to remove and read components without knowing their type.
+pub trait ComponentStorage: Debug + MaybeSendSync {
+    /// Gets the storage as `Any` so that it can be downcasted to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Gets the name of the component type, as given by `std::any::type_name`.
+    fn type_name(&self) -> &'static str;
+
+    /// Gets the storage as a mutable `Any` so that it can be downcasted to its concrete type.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Checks if the entity in the given slot has a component in this storage.
+    fn contains(&self, index: usize) -> bool;
+
+    /// Gets the component of the entity in the given slot, if it has one.
+    fn get_cell(&self, index: usize) -> Option<&ComponentCell<dyn Any>>;
+
+    /// Gets the ticks of the component of the entity in the given slot, if it has one.
+    fn get_ticks(&self, index: usize) -> Option<&ComponentTicks>;
+
+    /// Removes and drops the component of the given entity, if it has one.
+    ///
+    /// The `on_remove` hooks of the component type are called before the component is dropped.
+    fn remove(&mut self, entity: Entity);
+
+    /// Gets the slot indexes of every entity that has a component in this storage.
+    fn indexes(&self) -> &[usize];
+
+    /// Gets the amount of components stored.
+    fn len(&self) -> usize;
+
+    /// Checks if there are no components stored.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A sparse set that stores all the components of a single type contiguously in memory.
+///
+/// The components live in a dense vector without gaps, so iterating over them is cache
+/// friendly, while a sparse vector indexed by the entity's slot gives constant time lookups.
+pub struct SparseSet<T> {
+    /// For every entity slot, the position of its component in `dense`, if it has one.
+    sparse: Vec<Option<usize>>,
+    /// The slot index of the entity that owns every component in `dense`.
+    indexes: Vec<usize>,
+    /// The components packed together.
+    dense: Vec<ComponentCell<T>>,
+    /// The ticks of every component in `dense`.
+    ticks: Vec<ComponentTicks>,
+    /// The lifecycle hooks of the component type.
+    pub(super) hooks: ComponentHooks<T>,
+}
+
+impl<T> Default for SparseSet<T> {
+    fn default() -> Self {
+        Self {
+            sparse: vec![],
+            indexes: vec![],
+            dense: vec![],
+            ticks: vec![],
+            hooks: ComponentHooks::default(),
+        }
+    }
+}
+
+impl<T> Debug for SparseSet<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SparseSet")
+            .field("type", &std::any::type_name::<T>())
+            .field("indexes", &self.indexes)
+            .finish()
+    }
+}
+
+impl<T> SparseSet<T> {
+    /// Inserts the component of the entity in the given slot, replacing the previous one if
+    /// there was any.
+    ///
+    /// Replacing a component marks it as changed, while inserting a new one marks it as added.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The slot index of the entity.
+    /// * `data` - The component's data.
+    /// * `tick` - The current change tick.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the entity didn't have the component before, `false` if it was replaced.
+    pub fn insert(&mut self, index: usize, data: T, tick: u64) -> bool {
+        if let Some(Some(position)) = self.sparse.get(index) {
+            self.dense[*position] = ComponentCell::new(data);
+            self.ticks[*position].set_changed(tick);
+            return false;
+        }
+
+        if index >= self.sparse.len() {
+            self.sparse.resize(index + 1, None);
+        }
+
+        self.sparse[index] = Some(self.dense.len());
+        self.indexes.push(index);
+        self.dense.push(ComponentCell::new(data));
+        self.ticks.push(ComponentTicks::new(tick));
+
+        true
+    }
+
+    /// Calls the `on_add` and `on_insert` hooks with the component that was just inserted into
+    /// the given entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity.
+    /// * `added` - Whether the entity didn't have the component before.
+    pub fn run_insert_hooks(&self, entity: Entity, added: bool) {
+        if let Some(cell) = self.get(entity.index()) {
+            self.hooks.inserted(entity, &cell.borrow(), added);
+        }
+    }
+
+    /// Removes the component of the entity in the given slot.
+    ///
+    /// The last component is moved into the gap so that the components stay packed.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The slot index of the entity.
+    ///
+    /// # Returns
+    ///
+    /// The removed component if the entity had one, otherwise `None`.
+    pub fn take(&mut self, index: usize) -> Option<T> {
+        let position = self.sparse.get_mut(index)?.take()?;
+
+        self.indexes.swap_remove(position);
+        let removed = self.dense.swap_remove(position);
+        self.ticks.swap_remove(position);
+
+        // Update the position of the component that was moved into the gap
+        if let Some(moved_index) = self.indexes.get(position) {
+            self.sparse[*moved_index] = Some(position);
+        }
+
+        Some(removed.into_inner())
+    }
+
+    /// Gets the component of the entity in the given slot.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The slot index of the entity.
+    ///
+    /// # Returns
+    ///
+    /// The cell containing the component if the entity has one, otherwise `None`.
+    pub fn get(&self, index: usize) -> Option<&ComponentCell<T>> {
+        let position = self.sparse.get(index).copied().flatten()?;
+        self.dense.get(position)
+    }
+
+    /// Borrows mutably the component of the entity in the given slot.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The slot index of the entity.
+    /// * `tick` - The tick the component is marked as changed at if it is written.
+    ///
+    /// # Returns
+    ///
+    /// A `Mut` to the component if the entity has one, otherwise `None`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component is already borrowed.
+    pub fn get_mut(&self, index: usize, tick: u64) -> Option<Mut<'_, T>> {
+        let position = self.sparse.get(index).copied().flatten()?;
+
+        Some(Mut { value: self.dense[position].borrow_mut(), ticks: &self.ticks[position], tick })
+    }
+
+    /// Marks the component of the entity in the given slot as changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The slot index of the entity.
+    /// * `tick` - The current change tick.
+    pub fn set_changed(&self, index: usize, tick: u64) {
+        if let Some(Some(position)) = self.sparse.get(index) {
+            self.ticks[*position].set_changed(tick);
+        }
+    }
+
+    /// Iterates over the slot indexes of the entities and their components.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &ComponentCell<T>)> {
+        self.indexes.iter().copied().zip(self.dense.iter())
+    }
+}
+
+impl<T: Any + MaybeSendSync> ComponentStorage for SparseSet<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn contains(&self, index: usize) -> bool {
+        matches!(self.sparse.get(index), Some(Some(_)))
+    }
+
+    fn get_cell(&self, index: usize) -> Option<&ComponentCell<dyn Any>> {
+        self.get(index).map(|cell| cell as &ComponentCell<dyn Any>)
+    }
+
+    fn get_ticks(&self, index: usize) -> Option<&ComponentTicks> {
+        let position = self.sparse.get(index).copied().flatten()?;
+        self.ticks.get(position)
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        if let Some(cell) = self.get(entity.index()) {
+            self.hooks.removed(entity, &cell.borrow());
+        }
+
+        self.take(entity.index());
+    }
+
+    fn indexes(&self) -> &[usize] {
+        &self.indexes
+    }
+
+    fn len(&self) -> usize {
+        self.dense.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_get_components() {
+        let mut set = SparseSet::<u32>::default();
+        set.insert(3, 30, 0);
+        set.insert(0, 10, 0);
+
+        assert_eq!(*set.get(3).unwrap().borrow(), 30);
+        assert_eq!(*set.get(0).unwrap().borrow(), 10);
+        assert!(set.get(1).is_none());
+        assert!(set.get(100).is_none());
+        assert_eq!(set.indexes(), &[3, 0]);
+    }
+
+    #[test]
+    fn inserting_twice_replaces_the_component() {
+        let mut set = SparseSet::<u32>::default();
+        set.insert(2, 1, 0);
+        set.insert(2, 5, 0);
+
+        assert_eq!(set.len(), 1);
+        assert_eq!(*set.get(2).unwrap().borrow(), 5);
+    }
+
+    #[test]
+    fn removing_keeps_components_packed() {
+        let mut set = SparseSet::<u32>::default();
+        set.insert(0, 10, 0);
+        set.insert(1, 11, 0);
+        set.insert(2, 12, 0);
+
+        assert_eq!(set.take(0), Some(10));
+        assert_eq!(set.take(0), None);
+
+        assert_eq!(set.len(), 2);
+        assert!(!set.contains(0));
+        assert_eq!(*set.get(1).unwrap().borrow(), 11);
+        assert_eq!(*set.get(2).unwrap().borrow(), 12);
+
+        let values: Vec<(usize, u32)> = set.iter().map(|(index, cell)| (index, *cell.borrow())).collect();
+        assert_eq!(values, vec![(2, 12), (1, 11)]);
+    }
+
+    #[test]
+    fn component_ticks_follow_their_components() {
+        let mut set = SparseSet::<u32>::default();
+        set.insert(0, 10, 1);
+        set.insert(1, 11, 2);
+
+        set.set_changed(0, 3);
+        set.insert(1, 21, 4);
+
+        assert_eq!(set.get_ticks(0).map(|ticks| (ticks.added(), ticks.changed())), Some((1, 3)));
+        assert_eq!(set.get_ticks(1).map(|ticks| (ticks.added(), ticks.changed())), Some((2, 4)));
+
+        // The ticks of the component moved into the gap are moved with it
+        set.take(0);
+        assert!(set.get_ticks(0).is_none());
+        assert_eq!(set.get_ticks(1).map(|ticks| (ticks.added(), ticks.changed())), Some((2, 4)));
+    }
+}
@@ -0,0 +1,478 @@
+use std::{any::{Any, TypeId}, marker::PhantomData};
+use eyre::Result;
+
+use crate::prelude::*;
+use crate::sync::ComponentRef;
+use super::{filter::QueryFilter, query::Query, storage::{Mut, SparseSet}, Entities};
+
+/// Describes how a query accesses a component type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComponentAccess {
+    /// The type id of the component.
+    pub type_id: TypeId,
+    /// Whether the component is accessed mutably or not.
+    pub mutable: bool,
+    /// Whether the entities must have the component to match the query or not.
+    pub required: bool,
+}
+
+/// The data that a typed query fetches for every entity.
+///
+/// It is implemented for `&T`, `&mut T`, `Option<&T>`, `Option<&mut T>`, `Entity` and tuples of
+/// them, so that a query like `(&Transform, &mut RigidBody)` yields already downcasted
+/// references to the components.
+pub trait QueryData {
+    /// The item yielded for every entity.
+    type Item<'a>;
+    /// The storages the items are fetched from, resolved once per query run.
+    type Fetch<'a>: Copy;
+
+    /// Adds the component accesses of this query data to the given vector.
+    ///
+    /// # Arguments
+    ///
+    /// * `accesses` - The vector the accesses are added to.
+    fn access(accesses: &mut Vec<ComponentAccess>);
+
+    /// Resolves the storages the items are fetched from.
+    ///
+    /// # Arguments
+    ///
+    /// * `entities` - The entities to fetch the items from.
+    fn init_fetch(entities: &Entities) -> Self::Fetch<'_>;
+
+    /// Fetches the item of the entity in the given slot.
+    ///
+    /// # Arguments
+    ///
+    /// * `fetch` - The resolved storages.
+    /// * `index` - The slot index of the entity.
+    ///
+    /// # Returns
+    ///
+    /// The item if the entity has all the required components, otherwise `None`.
+    fn fetch<'a>(fetch: Self::Fetch<'a>, index: usize) -> Option<Self::Item<'a>>;
+}
+
+impl<T: Any> QueryData for &T {
+    type Item<'a> = ComponentRef<'a, T>;
+    type Fetch<'a> = Option<&'a SparseSet<T>>;
+
+    fn access(accesses: &mut Vec<ComponentAccess>) {
+        accesses.push(ComponentAccess { type_id: TypeId::of::<T>(), mutable: false, required: true });
+    }
+
+    fn init_fetch(entities: &Entities) -> Self::Fetch<'_> {
+        entities.get_storage::<T>()
+    }
+
+    fn fetch<'a>(fetch: Self::Fetch<'a>, index: usize) -> Option<Self::Item<'a>> {
+        fetch?.get(index).map(|component| component.borrow())
+    }
+}
+
+impl<T: Any> QueryData for &mut T {
+    type Item<'a> = Mut<'a, T>;
+    type Fetch<'a> = (Option<&'a SparseSet<T>>, u64);
+
+    fn access(accesses: &mut Vec<ComponentAccess>) {
+        accesses.push(ComponentAccess { type_id: TypeId::of::<T>(), mutable: true, required: true });
+    }
+
+    fn init_fetch(entities: &Entities) -> Self::Fetch<'_> {
+        (entities.get_storage::<T>(), entities.change_tick())
+    }
+
+    fn fetch<'a>(fetch: Self::Fetch<'a>, index: usize) -> Option<Self::Item<'a>> {
+        let (components, tick) = fetch;
+        components?.get_mut(index, tick)
+    }
+}
+
+impl<T: Any> QueryData for Option<&T> {
+    type Item<'a> = Option<ComponentRef<'a, T>>;
+    type Fetch<'a> = Option<&'a SparseSet<T>>;
+
+    fn access(accesses: &mut Vec<ComponentAccess>) {
+        accesses.push(ComponentAccess { type_id: TypeId::of::<T>(), mutable: false, required: false });
+    }
+
+    fn init_fetch(entities: &Entities) -> Self::Fetch<'_> {
+        entities.get_storage::<T>()
+    }
+
+    fn fetch<'a>(fetch: Self::Fetch<'a>, index: usize) -> Option<Self::Item<'a>> {
+        Some(fetch.and_then(|components| components.get(index)).map(|component| component.borrow()))
+    }
+}
+
+impl<T: Any> QueryData for Option<&mut T> {
+    type Item<'a> = Option<Mut<'a, T>>;
+    type Fetch<'a> = (Option<&'a SparseSet<T>>, u64);
+
+    fn access(accesses: &mut Vec<ComponentAccess>) {
+        accesses.push(ComponentAccess { type_id: TypeId::of::<T>(), mutable: true, required: false });
+    }
+
+    fn init_fetch(entities: &Entities) -> Self::Fetch<'_> {
+        (entities.get_storage::<T>(), entities.change_tick())
+    }
+
+    fn fetch<'a>(fetch: Self::Fetch<'a>, index: usize) -> Option<Self::Item<'a>> {
+        let (components, tick) = fetch;
+        Some(components.and_then(|components| components.get_mut(index, tick)))
+    }
+}
+
+impl QueryData for Entity {
+    type Item<'a> = Entity;
+    type Fetch<'a> = &'a Entities;
+
+    fn access(_accesses: &mut Vec<ComponentAccess>) {}
+
+    fn init_fetch(entities: &Entities) -> Self::Fetch<'_> {
+        entities
+    }
+
+    fn fetch<'a>(fetch: Self::Fetch<'a>, index: usize) -> Option<Self::Item<'a>> {
+        fetch.entity_at(index)
+    }
+}
+
+/// Implements `QueryData` for a tuple of query datas.
+macro_rules! impl_query_data_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: QueryData),*> QueryData for ($($name,)*) {
+            type Item<'a> = ($($name::Item<'a>,)*);
+            type Fetch<'a> = ($($name::Fetch<'a>,)*);
+
+            fn access(accesses: &mut Vec<ComponentAccess>) {
+                $($name::access(accesses);)*
+            }
+
+            fn init_fetch(entities: &Entities) -> Self::Fetch<'_> {
+                ($($name::init_fetch(entities),)*)
+            }
+
+            #[allow(non_snake_case)]
+            fn fetch<'a>(fetch: Self::Fetch<'a>, index: usize) -> Option<Self::Item<'a>> {
+                let ($($name,)*) = fetch;
+                Some(($($name::fetch($name, index)?,)*))
+            }
+        }
+    };
+}
+
+impl_query_data_tuple!(A);
+impl_query_data_tuple!(A, B);
+impl_query_data_tuple!(A, B, C);
+impl_query_data_tuple!(A, B, C, D);
+impl_query_data_tuple!(A, B, C, D, E);
+impl_query_data_tuple!(A, B, C, D, E, F);
+impl_query_data_tuple!(A, B, C, D, E, F, G);
+impl_query_data_tuple!(A, B, C, D, E, F, G, H);
+
+/// Gets the component accesses of a query data and checks that they don't alias.
+///
+/// # Arguments
+///
+/// * `Q` - The query data to check.
+///
+/// # Returns
+///
+/// A result that contains the accesses if succeeds or an error if it fails.
+///
+/// # Errors
+///
+/// If a component is accessed mutably more than once, or both mutably and immutably, an error is
+/// returned.
+pub fn validated_access<Q: QueryData>() -> Result<Vec<ComponentAccess>> {
+    let mut accesses = vec![];
+    Q::access(&mut accesses);
+
+    for (index, access) in accesses.iter().enumerate() {
+        let aliases = accesses[index + 1..]
+            .iter()
+            .any(|other| other.type_id == access.type_id && (other.mutable || access.mutable));
+
+        if aliases {
+            return Err(CustomErrors::ConflictingComponentAccess.into());
+        }
+    }
+
+    Ok(accesses)
+}
+
+/// A query that yields already downcasted components for every matching entity.
+///
+/// Besides the fetched components, the entities can be filtered with a `QueryFilter` like
+/// `Without<T>` or `Changed<T>`.
+///
+/// # Example
+///
+/// ```
+/// use axle_ecs::World;
+///
+/// let mut world = World::new();
+/// world.register_component::<u32>();
+/// world.register_component::<f32>();
+///
+/// world
+///     .create_entity()
+///     .with_component(10_u32).unwrap()
+///     .with_component(1.5_f32).unwrap();
+///
+/// for (number, mut decimal) in world.query::<(&u32, &mut f32)>().unwrap().iter() {
+///     *decimal += *number as f32;
+/// }
+/// ```
+pub struct TypedQuery<'a, Q: QueryData, F: QueryFilter = ()> {
+    /// The untyped query that filters the entities.
+    query: Query<'a>,
+    /// The type of data fetched and the filter used.
+    marker: PhantomData<(Q, F)>,
+}
+
+impl<'a, Q: QueryData, F: QueryFilter> TypedQuery<'a, Q, F> {
+    /// Creates a new `TypedQuery`.
+    ///
+    /// # Arguments
+    ///
+    /// * `entities` - The entities to query.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the query if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the query data accesses a component mutably more than once, or both mutably and
+    /// immutably, an error is returned. Components that were never registered don't cause an
+    /// error: no entity has them.
+    pub fn new(entities: &'a Entities) -> Result<Self> {
+        let mut query = Query::new(entities);
+
+        for access in validated_access::<Q>()? {
+            if access.required {
+                query.with_component_by_type_id(access.type_id)?;
+            }
+        }
+
+        let mut filters = vec![];
+        F::filters(&mut filters);
+
+        for filter in filters {
+            query.with_filter(filter)?;
+        }
+
+        Ok(Self { query, marker: PhantomData })
+    }
+
+    /// Sets the tick from which the components are considered added or changed by the filters.
+    ///
+    /// By default it is the tick at which the last run of the systems started.
+    ///
+    /// # Arguments
+    ///
+    /// * `tick` - The change tick.
+    ///
+    /// # Returns
+    ///
+    /// The query.
+    pub fn changed_since(mut self, tick: u64) -> Self {
+        self.query.changed_since(tick);
+        self
+    }
+
+    /// Iterates over the items of the entities that match the query.
+    ///
+    /// The entities are visited in the order their least common component is stored in, which
+    /// is not necessarily the order they were created in.
+    pub fn iter(&self) -> impl Iterator<Item = Q::Item<'a>> + '_ {
+        let fetch = Q::init_fetch(self.query.entities());
+
+        self.query
+            .candidate_indexes()
+            .filter(|index| self.query.matches(*index))
+            .filter_map(move |index| Q::fetch(fetch, index))
+    }
+
+    /// Gets the item of a single entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity.
+    ///
+    /// # Returns
+    ///
+    /// The item if the entity exists and matches the query, otherwise `None`.
+    pub fn get(&self, entity: Entity) -> Option<Q::Item<'a>> {
+        let entities = self.query.entities();
+
+        if entities.is_alive(entity) && self.query.matches(entity.index()) {
+            Q::fetch(Q::init_fetch(entities), entity.index())
+        }
+        else {
+            None
+        }
+    }
+
+    /// Counts the entities that match the query.
+    pub fn count(&self) -> usize {
+        self.query
+            .candidate_indexes()
+            .filter(|index| self.query.matches(*index))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::filter::{Added, Changed, Or, With, Without};
+
+    #[test]
+    fn iterate_typed_components() -> Result<()> {
+        let mut entities = initialize_entities()?;
+
+        {
+            let query = TypedQuery::<(&Health, &mut Speed)>::new(&entities)?;
+            assert_eq!(query.count(), 2);
+
+            for (health, mut speed) in query.iter() {
+                speed.0 += health.0;
+            }
+        }
+
+        let query = TypedQuery::<(Entity, &Speed)>::new(&entities)?;
+        let mut speeds: Vec<u32> = query.iter().map(|(_, speed)| speed.0).collect();
+        speeds.sort();
+        assert_eq!(speeds, vec![3, 110, 220]);
+
+        entities.create_entity().with_component(Speed(0))?;
+        assert_eq!(TypedQuery::<&Speed>::new(&entities)?.count(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn optional_components() -> Result<()> {
+        let entities = initialize_entities()?;
+
+        let query = TypedQuery::<(&Speed, Option<&Health>)>::new(&entities)?;
+        let mut results: Vec<(u32, Option<u32>)> = query
+            .iter()
+            .map(|(speed, health)| (speed.0, health.map(|health| health.0)))
+            .collect();
+        results.sort();
+
+        assert_eq!(results, vec![(3, None), (10, Some(100)), (20, Some(200))]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_a_single_entity() -> Result<()> {
+        let mut entities = initialize_entities()?;
+        let entity = entities.create_entity().with_component(Health(5))?.entity();
+
+        let query = TypedQuery::<&Health>::new(&entities)?;
+        assert_eq!(query.get(entity).unwrap().0, 5);
+
+        let query = TypedQuery::<(&Health, &Speed)>::new(&entities)?;
+        assert!(query.get(entity).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn aliasing_queries_are_rejected() -> Result<()> {
+        let entities = initialize_entities()?;
+
+        assert!(TypedQuery::<(&Health, &mut Health)>::new(&entities).is_err());
+        assert!(TypedQuery::<(&mut Health, Option<&mut Health>)>::new(&entities).is_err());
+        assert!(TypedQuery::<(&Health, &Health)>::new(&entities).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn filtered_queries() -> Result<()> {
+        let mut entities = initialize_entities()?;
+        entities.increment_change_tick();
+
+        let query = TypedQuery::<&Speed, Without<Health>>::new(&entities)?;
+        assert_eq!(query.iter().map(|speed| speed.0).collect::<Vec<u32>>(), vec![3]);
+
+        // Only the written components are marked as changed
+        for mut speed in TypedQuery::<&mut Speed, With<Health>>::new(&entities)?.iter() {
+            speed.0 += 1;
+        }
+
+        let entity = entities.create_entity().with_component(Health(1))?.entity();
+
+        let query = TypedQuery::<Entity, Or<(Changed<Speed>, Added<Health>)>>::new(&entities)?.changed_since(1);
+        assert_eq!(query.count(), 3);
+        assert!(query.get(entity).is_some());
+
+        let query = TypedQuery::<&Speed, (Changed<Speed>, Without<Health>)>::new(&entities)?.changed_since(1);
+        assert_eq!(query.count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn mutable_fetches_without_writes_are_not_changed() -> Result<()> {
+        let entities = initialize_entities()?;
+        entities.increment_change_tick();
+
+        for speed in TypedQuery::<&mut Speed>::new(&entities)?.iter() {
+            assert!(speed.0 > 0);
+        }
+
+        let query = TypedQuery::<Entity, Changed<Speed>>::new(&entities)?.changed_since(1);
+        assert_eq!(query.count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn unregistered_components_match_no_entities() -> Result<()> {
+        let entities = initialize_entities()?;
+
+        assert_eq!(TypedQuery::<(&Health, &Unregistered)>::new(&entities)?.count(), 0);
+        assert_eq!(TypedQuery::<(&Speed, Option<&Unregistered>)>::new(&entities)?.count(), 3);
+        assert_eq!(TypedQuery::<&Speed, Without<Unregistered>>::new(&entities)?.count(), 3);
+        assert_eq!(TypedQuery::<&Speed, With<Unregistered>>::new(&entities)?.count(), 0);
+
+        let (indexes, components) = Query::new(&entities).with_component::<Unregistered>()?.run();
+        assert!(indexes.is_empty());
+        assert_eq!(components.len(), 1);
+
+        Ok(())
+    }
+
+    fn initialize_entities() -> Result<Entities> {
+        let mut entities = Entities::default();
+        entities.register_component::<Health>();
+        entities.register_component::<Speed>();
+
+        entities
+            .create_entity()
+            .with_component(Health(100))?
+            .with_component(Speed(10))?;
+        entities
+            .create_entity()
+            .with_component(Health(200))?
+            .with_component(Speed(20))?;
+        entities
+            .create_entity()
+            .with_component(Speed(3))?;
+
+        Ok(entities)
+    }
+
+    struct Health(pub u32);
+    struct Speed(pub u32);
+    struct Unregistered;
+}
@@ -0,0 +1,251 @@
+use std::marker::PhantomData;
+
+/// A double-buffered channel of events of type `T`, stored as a resource.
+///
+/// Systems send events with `send` and read them with an `EventReader`, which remembers which
+/// events it has already seen. Events live for two frames: the frame they are sent in and the
+/// next one, so every system that runs once per frame sees them regardless of the order the
+/// systems run in. The world drops the older events at the start of every run of the systems.
+///
+/// # Example
+///
+/// ```
+/// use axle_ecs::{entities::query_entity::QueryEntity, resources::Resources, World};
+/// use axle_ecs::events::{EventReader, Events};
+///
+/// struct Collision(u32);
+///
+/// let mut world = World::new();
+/// world.add_event::<Collision>();
+///
+/// world.create_system(&|_: &Vec<QueryEntity>, resources: &mut Resources| {
+///     resources.get_mut::<Events<Collision>>().unwrap().send(Collision(10));
+///     Ok(())
+/// });
+///
+/// let mut reader = EventReader::<Collision>::default();
+/// world.create_system(move |_: &Vec<QueryEntity>, resources: &mut Resources| {
+///     let events = resources.get_ref::<Events<Collision>>().unwrap();
+///
+///     for collision in reader.read(events) {
+///         assert_eq!(collision.0, 10);
+///     }
+///
+///     Ok(())
+/// });
+///
+/// world.run_all_systems().unwrap();
+/// ```
+pub struct Events<T> {
+    /// The events sent during the previous frame.
+    previous: Vec<T>,
+    /// The events sent during the current frame.
+    current: Vec<T>,
+    /// The id of the first event in `previous`. Events are numbered in the order they are sent.
+    previous_start: usize,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: vec![],
+            current: vec![],
+            previous_start: 0,
+        }
+    }
+}
+
+impl<T> Events<T> {
+    /// Sends an event, so that the readers see it the next time they read.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event to send.
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    /// Sends several events at once, in the order they are iterated.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - The events to send.
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.current.extend(events);
+    }
+
+    /// Swaps the buffers, dropping the events sent before the previous frame.
+    ///
+    /// The world calls it at the start of every run of the systems for the events added with
+    /// `World::add_event`, so there is usually no need to call it manually.
+    pub fn update(&mut self) {
+        self.previous_start += self.previous.len();
+        self.previous = std::mem::take(&mut self.current);
+    }
+
+    /// Creates a reader that only sees the events sent from now on.
+    ///
+    /// A reader created with `EventReader::default` sees every event that is still stored instead.
+    ///
+    /// # Returns
+    ///
+    /// A new `EventReader` struct.
+    pub fn get_reader_current(&self) -> EventReader<T> {
+        EventReader {
+            last_event_count: self.event_count(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Gets the amount of events stored, from both the previous and the current frame.
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    /// Checks if there are no events stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops every stored event.
+    pub fn clear(&mut self) {
+        self.previous_start = self.event_count();
+        self.previous.clear();
+        self.current.clear();
+    }
+
+    /// Gets the amount of events sent since the creation of the channel.
+    fn event_count(&self) -> usize {
+        self.previous_start + self.len()
+    }
+}
+
+/// Reads the events of type `T` that it hasn't seen yet.
+///
+/// Every system that reads events keeps its own reader, for example in the state of a closure or
+/// of a `System` struct.
+pub struct EventReader<T> {
+    /// The amount of events sent before the last event this reader has seen.
+    last_event_count: usize,
+    /// Makes the reader bound to the events of type `T` without owning any.
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EventReader<T> {
+    fn default() -> Self {
+        Self {
+            last_event_count: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> EventReader<T> {
+    /// Reads the events that this reader hasn't seen yet, oldest first, and marks them as seen.
+    ///
+    /// Events dropped before the reader could see them, because it didn't read for more than a
+    /// frame, are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - The events to read from.
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the unseen events.
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> {
+        let unseen = self.unseen(events);
+        self.last_event_count = events.event_count();
+
+        let seen = events.len() - unseen;
+        events.previous.iter().chain(events.current.iter()).skip(seen)
+    }
+
+    /// Gets the amount of events this reader hasn't seen yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - The events to check.
+    pub fn len(&self, events: &Events<T>) -> usize {
+        self.unseen(events)
+    }
+
+    /// Checks if this reader has seen every event.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - The events to check.
+    pub fn is_empty(&self, events: &Events<T>) -> bool {
+        self.len(events) == 0
+    }
+
+    /// Marks every event as seen without reading them.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - The events to mark as seen.
+    pub fn clear(&mut self, events: &Events<T>) {
+        self.last_event_count = events.event_count();
+    }
+
+    /// Gets the amount of stored events this reader hasn't seen yet.
+    fn unseen(&self, events: &Events<T>) -> usize {
+        let first_unseen = self.last_event_count.max(events.previous_start);
+        events.event_count().saturating_sub(first_unseen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readers_only_see_new_events() {
+        let mut events = Events::<Hit>::default();
+        let mut reader = EventReader::default();
+
+        events.send(Hit(1));
+        events.send_batch([Hit(2), Hit(3)]);
+
+        let late_reader = events.get_reader_current();
+
+        assert_eq!(reader.len(&events), 3);
+        assert_eq!(reader.read(&events).map(|hit| hit.0).collect::<Vec<u32>>(), vec![1, 2, 3]);
+        assert!(reader.is_empty(&events));
+        assert!(late_reader.is_empty(&events));
+
+        events.update();
+        events.send(Hit(4));
+
+        assert_eq!(reader.read(&events).map(|hit| hit.0).collect::<Vec<u32>>(), vec![4]);
+        assert_eq!(late_reader.len(&events), 1);
+    }
+
+    #[test]
+    fn events_live_for_two_frames() {
+        let mut events = Events::<Hit>::default();
+        let mut reader = EventReader::default();
+
+        events.send(Hit(1));
+        events.update();
+        events.send(Hit(2));
+
+        // The events of the previous frame can still be read
+        assert_eq!(events.len(), 2);
+
+        events.update();
+        assert_eq!(events.len(), 1);
+
+        // The reader never saw the first event, but it was already dropped
+        assert_eq!(reader.read(&events).map(|hit| hit.0).collect::<Vec<u32>>(), vec![2]);
+
+        events.update();
+        assert!(events.is_empty());
+
+        events.send(Hit(3));
+        events.clear();
+        assert!(reader.is_empty(&events));
+    }
+
+    struct Hit(pub u32);
+}
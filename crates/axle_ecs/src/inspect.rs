@@ -0,0 +1,269 @@
+use std::{any::Any, fmt, fmt::Debug, fs, path::Path};
+use eyre::Result;
+
+use crate::prelude::*;
+use crate::sync::MaybeSendSync;
+
+/// A component of an entity, as shown by `World::describe_entity`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentDescription {
+    /// The name of the component type, as given by `std::any::type_name`.
+    pub type_name: &'static str,
+    /// The `Debug` output of the component, if its type was registered with
+    /// `World::register_debug_component` or for reflection.
+    pub value: Option<String>,
+}
+
+/// The description of an entity and its components, used for debugging.
+///
+/// It can be printed with `{}` to get a readable listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityDescription {
+    /// The handle of the entity.
+    pub entity: Entity,
+    /// The parent of the entity, if it has one.
+    pub parent: Option<Entity>,
+    /// The components of the entity, sorted by their type name.
+    pub components: Vec<ComponentDescription>,
+}
+
+impl fmt::Display for EntityDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Entity {} (generation {})", self.entity.index(), self.entity.generation())?;
+
+        if let Some(parent) = self.parent {
+            write!(f, ", child of entity {}", parent.index())?;
+        }
+
+        writeln!(f)?;
+
+        for component in &self.components {
+            match &component.value {
+                Some(value) => writeln!(f, "  {}: {}", component.type_name, value)?,
+                None => writeln!(f, "  {}", component.type_name)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A dump of every entity of a world and the amount of components of every type, used for debugging.
+///
+/// It can be printed with `{}` to get a readable listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorldDump {
+    /// The descriptions of every entity, in the order of their slots.
+    pub entities: Vec<EntityDescription>,
+    /// The amount of components of every registered type, sorted by the type name.
+    pub component_counts: Vec<(&'static str, usize)>,
+}
+
+impl WorldDump {
+    /// Prints the dump to the standard output.
+    pub fn print(&self) {
+        print!("{}", self);
+    }
+
+    /// Writes the dump to a file, replacing the file if it already exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, self.to_string())?;
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for WorldDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "World with {} entities", self.entities.len())?;
+        writeln!(f, "Components:")?;
+
+        for (type_name, count) in &self.component_counts {
+            writeln!(f, "  {}: {}", type_name, count)?;
+        }
+
+        for entity in &self.entities {
+            writeln!(f)?;
+            write!(f, "{}", entity)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl World {
+    /// Registers a component type and its `Debug` implementation, so that its value is shown when
+    /// describing the entities.
+    ///
+    /// The component is registered in the entities too if it wasn't yet, keeping its existing data
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component.
+    pub fn register_debug_component<T: Any + MaybeSendSync + Debug>(&mut self) {
+        self.entities.register_component::<T>();
+
+        self.registry.register_debug::<T>();
+    }
+
+    /// Describes an entity: its parent and its components, with their values when their type was
+    /// registered for debugging or reflection.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the description of the entity if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the entity doesn't exist, an error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::World;
+    ///
+    /// #[derive(Debug)]
+    /// struct Health(u32);
+    /// struct Player;
+    ///
+    /// let mut world = World::new();
+    /// world.register_debug_component::<Health>();
+    /// world.register_component::<Player>();
+    ///
+    /// let player = world.create_entity().with_component(Health(10)).unwrap().with_component(Player).unwrap().entity();
+    ///
+    /// let description = world.describe_entity(player).unwrap();
+    /// assert_eq!(description.components.len(), 2);
+    ///
+    /// println!("{}", description);
+    /// ```
+    pub fn describe_entity(&self, entity: Entity) -> Result<EntityDescription> {
+        if !self.entities.is_alive(entity) {
+            return Err(CustomErrors::EntityDoesNotExist.into());
+        }
+
+        let mut components: Vec<ComponentDescription> = self
+            .entities
+            .storages()
+            .filter_map(|(type_id, storage)| {
+                let cell = storage.get_cell(entity.index())?;
+
+                Some(ComponentDescription {
+                    type_name: storage.type_name(),
+                    value: self.registry.debug_string(type_id, &*cell.borrow()),
+                })
+            })
+            .collect();
+
+        components.sort_by_key(|component| component.type_name);
+
+        Ok(EntityDescription {
+            entity,
+            parent: self.entities.get_parent(entity),
+            components,
+        })
+    }
+
+    /// Dumps every entity of the world and the amount of components of every type.
+    ///
+    /// # Returns
+    ///
+    /// The dump of the world, that can be printed or written to a file.
+    pub fn dump(&self) -> WorldDump {
+        let entities = self
+            .entities
+            .alive_entities()
+            .filter_map(|entity| self.describe_entity(entity).ok())
+            .collect();
+
+        let mut component_counts: Vec<(&'static str, usize)> = self
+            .entities
+            .storages()
+            .map(|(_, storage)| (storage.type_name(), storage.len()))
+            .collect();
+
+        component_counts.sort();
+
+        WorldDump { entities, component_counts }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reflect::Reflect;
+
+    #[test]
+    fn entities_are_described_with_their_values() -> Result<()> {
+        let mut world = World::new();
+        world.register_debug_component::<Health>();
+        world.register_reflect_component::<Speed>();
+        world.register_component::<Marker>();
+
+        let parent = world.create_entity().with_component(Marker)?.entity();
+        let child = world
+            .create_entity()
+            .with_component(Health(3))?
+            .with_component(Speed { value: 1.5 })?
+            .with_component(Marker)?
+            .entity();
+        world.set_parent(child, parent)?;
+
+        let description = world.describe_entity(child)?;
+        let values: Vec<Option<&str>> = description.components.iter().map(|component| component.value.as_deref()).collect();
+
+        assert_eq!(description.parent, Some(parent));
+        // The components are sorted by type name, so the `Parent` component comes first
+        assert!(values[0].is_some_and(|value| value.starts_with("Parent(")));
+        assert_eq!(values[1..], [Some("Health(3)"), None, Some("Speed { value: 1.5 }")]);
+        assert!(description.to_string().contains("child of entity 0"));
+
+        world.delete_entity_by_id(parent)?;
+        assert!(world.describe_entity(parent).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn dumps_count_the_components() -> Result<()> {
+        let mut world = World::new();
+        world.register_component::<Marker>();
+        world.register_debug_component::<Health>();
+
+        world.create_entity().with_component(Marker)?.with_component(Health(1))?;
+        world.create_entity().with_component(Marker)?;
+
+        let dump = world.dump();
+        let counts: Vec<usize> = dump.component_counts.iter().map(|(_, count)| *count).collect();
+
+        assert_eq!(dump.entities.len(), 2);
+        assert_eq!(counts, vec![1, 2]);
+        assert!(dump.to_string().starts_with("World with 2 entities"));
+
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Health(u32);
+
+    #[derive(Reflect)]
+    struct Speed {
+        value: f32,
+    }
+
+    struct Marker;
+}
@@ -1,11 +1,28 @@
 //! Axle_ECS is a simple Entity Component System (ECS) mainly designed for the axle_engine game engine.
 
+// Lets the derive macros refer to this crate as `axle_ecs` from inside it too
+extern crate self as axle_ecs;
+
+// Used by the code generated by the derive macros
+#[doc(hidden)]
+pub use eyre;
+
 // mods
+pub mod bundle;
+pub mod commands;
+pub mod events;
+pub mod reflect;
+pub mod registry;
 pub mod resources;
+#[cfg(feature = "serde")]
+pub mod scene;
+pub mod state;
 pub mod entities;
 pub mod custom_errors;
+pub mod inspect;
 pub mod world;
 pub mod systems;
+pub mod sync;
 
 //re-exports
 pub use world::World;
@@ -14,10 +31,27 @@ pub use custom_errors::CustomErrors;
 pub mod prelude {
     pub use crate::world::World;
     pub use crate::custom_errors::CustomErrors;
+    pub use crate::bundle::Bundle;
+    pub use crate::commands::Commands;
+    pub use crate::events::{EventReader, Events};
+    pub use crate::reflect::Reflect;
+    #[cfg(feature = "serde")]
+    pub use crate::scene::{Scene, SceneEntity};
+    #[cfg(feature = "serde")]
+    pub use crate::scene::prefab::Prefab;
+    pub use crate::state::{in_state, on_enter, on_exit, State, StateScoped, States};
 
     //types
     pub use crate::entities::{Component, Components};
+    pub use crate::entities::entity::Entity;
+    pub use crate::entities::filter::{Added, Changed, Filter, Or, QueryFilter, With, Without};
+    pub use crate::entities::hierarchy::{Children, Parent};
     pub use crate::entities::query::{QueryComponents, QueryIndexes};
     pub use crate::entities::query_entity::QueryEntity;
+    pub use crate::entities::storage::Mut;
+    pub use crate::entities::typed_query::{QueryData, TypedQuery};
     pub use crate::systems::{SystemComponents, SystemFunction};
+    pub use crate::systems::executor::Executor;
+    pub use crate::systems::param::{IntoSystem, NonSend, NonSendMut, Res, ResMut};
+    pub use crate::systems::system::System;
 }
\ No newline at end of file
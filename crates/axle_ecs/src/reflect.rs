@@ -0,0 +1,506 @@
+use std::any::{Any, TypeId};
+use eyre::Result;
+
+use crate::prelude::*;
+use crate::sync::{ComponentCell, ComponentRef, ComponentRefMut, MaybeSendSync};
+
+pub use axle_ecs_derive::Reflect;
+
+/// Gives access at runtime to the name and the fields of a type.
+///
+/// It is usually derived with `#[derive(Reflect)]`, which exposes every field of a struct under its
+/// name, or its position for tuple structs. Fields can be skipped with `#[reflect(ignore)]`. The
+/// primitive types, `String` and `Entity` are reflected as values without fields.
+///
+/// # Example
+///
+/// ```
+/// use axle_ecs::reflect::Reflect;
+///
+/// #[derive(Reflect)]
+/// struct Position {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// let mut position = Position { x: 1.0, y: 2.0 };
+/// let reflect: &mut dyn Reflect = &mut position;
+///
+/// assert_eq!(reflect.type_name(), "Position");
+/// assert_eq!(reflect.field_names(), &["x", "y"]);
+///
+/// reflect.set_path("y", 5.0_f32).unwrap();
+/// assert_eq!(*reflect.get_path::<f32>("y").unwrap(), 5.0);
+/// assert_eq!(reflect.debug_string(), "Position { x: 1.0, y: 5.0 }");
+/// ```
+pub trait Reflect: Any {
+    /// Gets the name of the type without an instance of it.
+    fn static_type_name() -> &'static str
+    where
+        Self: Sized;
+
+    /// Gets the name of the type.
+    fn type_name(&self) -> &'static str;
+
+    /// Gets the names of the fields, in the order they are declared.
+    fn field_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Gets a field by its name.
+    fn field(&self, _name: &str) -> Option<&dyn Reflect> {
+        None
+    }
+
+    /// Gets a field mutably by its name.
+    fn field_mut(&mut self, _name: &str) -> Option<&mut dyn Reflect> {
+        None
+    }
+
+    /// Upcasts to `Any`, so that the value can be downcasted to its type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Upcasts to `Any` mutably, so that the value can be downcasted to its type.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Formats the value for debugging, with the same layout as `Debug` for structs.
+    fn debug_string(&self) -> String {
+        let fields: Vec<String> = self
+            .field_names()
+            .iter()
+            .filter_map(|name| self.field(name).map(|field| format!("{}: {}", name, field.debug_string())))
+            .collect();
+
+        if fields.is_empty() {
+            self.type_name().to_owned()
+        } else {
+            format!("{} {{ {} }}", self.type_name(), fields.join(", "))
+        }
+    }
+}
+
+impl dyn Reflect {
+    /// Gets a nested field by its path, made of the names of the fields separated by dots, for
+    /// example `"position.x"`. An empty path points to the value itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the field.
+    ///
+    /// # Returns
+    ///
+    /// The field if it exists, otherwise `None`.
+    pub fn path(&self, path: &str) -> Option<&dyn Reflect> {
+        if path.is_empty() {
+            return Some(self);
+        }
+
+        path.split('.').try_fold(self, |current, name| current.field(name))
+    }
+
+    /// Gets a nested field mutably by its path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the field, as in `path`.
+    ///
+    /// # Returns
+    ///
+    /// The field if it exists, otherwise `None`.
+    pub fn path_mut(&mut self, path: &str) -> Option<&mut dyn Reflect> {
+        if path.is_empty() {
+            return Some(self);
+        }
+
+        path.split('.').try_fold(self, |current, name| current.field_mut(name))
+    }
+
+    /// Gets a nested field by its path, downcasted to its type.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the field.
+    /// * `path` - The path of the field, as in `path`.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains a reference to the field if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the field doesn't exist or isn't of type `T`, an error is returned.
+    pub fn get_path<T: Any>(&self, path: &str) -> Result<&T> {
+        self.path(path)
+            .ok_or_else(|| CustomErrors::ReflectPathDoesNotExist(path.to_owned()))?
+            .as_any()
+            .downcast_ref::<T>()
+            .ok_or_else(|| CustomErrors::DowncastToWrongType.into())
+    }
+
+    /// Replaces the value of a nested field.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the field, as in `path`.
+    /// * `value` - The new value of the field.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the field doesn't exist or isn't of the type of the value, an error is returned.
+    pub fn set_path<T: Any>(&mut self, path: &str, value: T) -> Result<()> {
+        let field = self
+            .path_mut(path)
+            .ok_or_else(|| CustomErrors::ReflectPathDoesNotExist(path.to_owned()))?
+            .as_any_mut()
+            .downcast_mut::<T>()
+            .ok_or(CustomErrors::DowncastToWrongType)?;
+
+        *field = value;
+
+        Ok(())
+    }
+}
+
+/// Implements `Reflect` for types that are reflected as values without fields.
+macro_rules! impl_reflect_value {
+    ($($type:ty),*) => {
+        $(
+            impl Reflect for $type {
+                fn static_type_name() -> &'static str {
+                    stringify!($type)
+                }
+
+                fn type_name(&self) -> &'static str {
+                    stringify!($type)
+                }
+
+                fn as_any(&self) -> &dyn Any {
+                    self
+                }
+
+                fn as_any_mut(&mut self) -> &mut dyn Any {
+                    self
+                }
+
+                fn debug_string(&self) -> String {
+                    format!("{:?}", self)
+                }
+            }
+        )*
+    };
+}
+
+impl_reflect_value!(bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, String, Entity);
+
+/// The functions used to get a registered type as `Reflect` from type-erased data.
+#[derive(Debug, Clone)]
+pub(crate) struct ReflectRegistration {
+    /// The name of the type, as given by `Reflect::static_type_name`.
+    pub name: &'static str,
+    /// The type id of the registered type.
+    pub type_id: TypeId,
+    /// Gets the data as `Reflect` if it is of the registered type.
+    pub as_reflect: fn(&dyn Any) -> Option<&dyn Reflect>,
+    /// Gets the data as `Reflect` mutably if it is of the registered type.
+    pub as_reflect_mut: fn(&mut dyn Any) -> Option<&mut dyn Reflect>,
+}
+
+impl ReflectRegistration {
+    /// Creates the registration of the type `T`.
+    pub(crate) fn of<T: Reflect>() -> Self {
+        Self {
+            name: T::static_type_name(),
+            type_id: TypeId::of::<T>(),
+            as_reflect: |data| data.downcast_ref::<T>().map(|data| data as &dyn Reflect),
+            as_reflect_mut: |data| data.downcast_mut::<T>().map(|data| data as &mut dyn Reflect),
+        }
+    }
+}
+
+impl World {
+    /// Registers a component type and its reflection, so that it can be inspected and edited by name.
+    ///
+    /// The component is registered in the entities too if it wasn't yet, keeping its existing data
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component.
+    pub fn register_reflect_component<T: Reflect + MaybeSendSync>(&mut self) {
+        self.entities.register_component::<T>();
+
+        self.registry.register_reflect::<T>();
+    }
+
+    /// Registers the reflection of a resource type, so that it can be inspected and edited by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the resource.
+    pub fn register_reflect_resource<T: Reflect>(&mut self) {
+        self.registry.register_reflect::<T>();
+    }
+
+    /// Gets the names of the reflected components of an entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the names of the components, in the order they were registered, if
+    /// succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the entity doesn't exist, an error is returned.
+    pub fn reflect_component_names(&self, entity: Entity) -> Result<Vec<&'static str>> {
+        if !self.entities.is_alive(entity) {
+            return Err(CustomErrors::EntityDoesNotExist.into());
+        }
+
+        Ok(self
+            .registry
+            .reflected()
+            .iter()
+            .filter(|registration| {
+                self.entities
+                    .get_storage_by_type_id(&registration.type_id)
+                    .is_some_and(|storage| storage.contains(entity.index()))
+            })
+            .map(|registration| registration.name)
+            .collect())
+    }
+
+    /// Gets a reflected component of an entity by the name of its type.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity.
+    /// * `name` - The name of the component type.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains a reference to the component if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the entity doesn't exist, the type wasn't registered for reflection or the entity
+    /// doesn't have the component, an error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::{reflect::Reflect, World};
+    ///
+    /// #[derive(Reflect)]
+    /// struct Health {
+    ///     current: u32,
+    ///     max: u32,
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register_reflect_component::<Health>();
+    ///
+    /// let player = world.create_entity().with_component(Health { current: 80, max: 100 }).unwrap().entity();
+    ///
+    /// world.get_reflect_component_mut(player, "Health").unwrap().set_path("current", 100_u32).unwrap();
+    ///
+    /// let health = world.get_reflect_component(player, "Health").unwrap();
+    /// assert_eq!(*health.get_path::<u32>("current").unwrap(), 100);
+    /// ```
+    pub fn get_reflect_component(&self, entity: Entity, name: &str) -> Result<ComponentRef<'_, dyn Reflect>> {
+        let (registration, cell) = self.reflect_component_cell(entity, name)?;
+
+        // The storage of a type id always holds components of that type
+        Ok(ComponentRef::map(cell.borrow(), |data| (registration.as_reflect)(data).unwrap()))
+    }
+
+    /// Gets a reflected component of an entity mutably by the name of its type, marking it as changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity.
+    /// * `name` - The name of the component type.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains a mutable reference to the component if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the entity doesn't exist, the type wasn't registered for reflection or the entity
+    /// doesn't have the component, an error is returned.
+    pub fn get_reflect_component_mut(&self, entity: Entity, name: &str) -> Result<ComponentRefMut<'_, dyn Reflect>> {
+        let (registration, cell) = self.reflect_component_cell(entity, name)?;
+        let component = ComponentRefMut::map(cell.borrow_mut(), |data| (registration.as_reflect_mut)(data).unwrap());
+
+        if let Some(ticks) = self
+            .entities
+            .get_storage_by_type_id(&registration.type_id)
+            .and_then(|storage| storage.get_ticks(entity.index()))
+        {
+            ticks.set_changed(self.entities.change_tick());
+        }
+
+        Ok(component)
+    }
+
+    /// Gets a reflected resource by the name of its type.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the resource type.
+    ///
+    /// # Returns
+    ///
+    /// The resource if its type was registered for reflection and it exists, otherwise `None`.
+    pub fn get_reflect_resource(&self, name: &str) -> Option<&dyn Reflect> {
+        let registration = self.registry.get_reflect(name)?;
+
+        (registration.as_reflect)(self.resources.get_by_type_id(&registration.type_id)?)
+    }
+
+    /// Gets a reflected resource mutably by the name of its type.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the resource type.
+    ///
+    /// # Returns
+    ///
+    /// The resource if its type was registered for reflection and it exists, otherwise `None`.
+    pub fn get_reflect_resource_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+        let registration = self.registry.get_reflect(name)?;
+
+        (registration.as_reflect_mut)(self.resources.get_mut_by_type_id(&registration.type_id)?)
+    }
+
+    /// Finds the registration and the cell of a reflected component of an entity.
+    fn reflect_component_cell(
+        &self,
+        entity: Entity,
+        name: &str,
+    ) -> Result<(&ReflectRegistration, &ComponentCell<dyn Any>)> {
+        if !self.entities.is_alive(entity) {
+            return Err(CustomErrors::EntityDoesNotExist.into());
+        }
+
+        let registration = self
+            .registry
+            .get_reflect(name)
+            .ok_or_else(|| CustomErrors::ReflectTypeNotRegistered(name.to_owned()))?;
+
+        let cell = self
+            .entities
+            .get_storage_by_type_id(&registration.type_id)
+            .ok_or(CustomErrors::ComponentNotRegistered)?
+            .get_cell(entity.index())
+            .ok_or(CustomErrors::ComponentDataDoesNotExist)?;
+
+        Ok((registration, cell))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::TypeRegistry;
+
+    #[test]
+    fn nested_fields_can_be_read_and_written() -> Result<()> {
+        let mut body = Body {
+            position: Position { x: 1.0, y: 2.0 },
+            mass: 3.0,
+            cache: vec![],
+        };
+        let reflect: &mut dyn Reflect = &mut body;
+
+        assert_eq!(reflect.field_names(), &["position", "mass"]);
+        assert_eq!(*reflect.get_path::<f32>("position.y")?, 2.0);
+
+        reflect.set_path("position.x", 10.0_f32)?;
+        assert_eq!(body.position.x, 10.0);
+
+        let reflect: &dyn Reflect = &body;
+        assert!(reflect.get_path::<f32>("position.z").is_err());
+        assert!(reflect.get_path::<u32>("mass").is_err());
+        assert!(reflect.field("cache").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn tuple_and_unit_structs_are_reflected() {
+        let wrapper = Wrapper(5, String::from("five"));
+
+        assert_eq!(wrapper.field_names(), &["0", "1"]);
+        assert_eq!(wrapper.debug_string(), "Wrapper { 0: 5, 1: \"five\" }");
+        assert_eq!(Marker.debug_string(), "Marker");
+        assert_eq!(Marker::static_type_name(), "Marker");
+    }
+
+    #[test]
+    fn generic_structs_are_named_after_their_arguments() {
+        let mut registry = TypeRegistry::default();
+        registry.register_reflect::<Generic<u32>>();
+        registry.register_reflect::<Generic<f32>>();
+
+        assert_ne!(Generic::<u32>::static_type_name(), Generic::<f32>::static_type_name());
+        assert!(registry.contains_reflect::<Generic<u32>>());
+        assert!(registry.contains_reflect::<Generic<f32>>());
+        assert_eq!(Generic(1_u32).type_name(), Generic::<u32>::static_type_name());
+    }
+
+    #[test]
+    fn components_and_resources_can_be_reflected_by_name() -> Result<()> {
+        let mut world = World::new();
+        world.register_reflect_component::<Position>();
+        world.register_reflect_component::<Marker>();
+        world.register_reflect_resource::<Wrapper>();
+        world.add_resource(Wrapper(1, String::new()));
+
+        let entity = world.create_entity().with_component(Position { x: 0.0, y: 0.0 })?.entity();
+
+        assert_eq!(world.reflect_component_names(entity)?, vec!["Position"]);
+        assert!(world.get_reflect_component(entity, "Marker").is_err());
+        assert!(world.get_reflect_component(entity, "Velocity").is_err());
+
+        world.get_reflect_component_mut(entity, "Position")?.set_path("y", 4.0_f32)?;
+        assert_eq!(world.get_reflect_component(entity, "Position")?.debug_string(), "Position { x: 0.0, y: 4.0 }");
+
+        world.get_reflect_resource_mut("Wrapper").unwrap().set_path("0", 7_u32)?;
+        assert_eq!(*world.get_reflect_resource("Wrapper").unwrap().get_path::<u32>("0")?, 7);
+
+        Ok(())
+    }
+
+    #[derive(Reflect)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Reflect)]
+    struct Body {
+        position: Position,
+        mass: f32,
+        #[reflect(ignore)]
+        #[allow(dead_code)]
+        cache: Vec<f32>,
+    }
+
+    #[derive(Reflect)]
+    struct Wrapper(u32, String);
+
+    #[derive(Reflect)]
+    struct Marker;
+
+    #[derive(Reflect)]
+    struct Generic<T>(T);
+}
@@ -0,0 +1,200 @@
+use std::{any::{Any, TypeId}, collections::HashMap, fmt::Debug};
+#[cfg(feature = "serde")]
+use eyre::Result;
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "serde")]
+use serde_json::Value;
+
+use crate::reflect::{Reflect, ReflectRegistration};
+#[cfg(feature = "serde")]
+use crate::{entities::Entities, prelude::*, sync::{AnyBox, MaybeSendSync}};
+
+/// Formats type-erased data with the `Debug` implementation of its type, if it is of that type.
+type DebugFormatter = fn(&dyn Any) -> Option<String>;
+
+/// The functions used to save and load a registered type.
+///
+/// They are generated for the concrete type when it is registered, so that the type-erased data
+/// stored in the world can be converted to and from its serialized form.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone)]
+pub(crate) struct Registration {
+    /// The name the type is saved under. It must be stable between versions of the game.
+    pub name: String,
+    /// The type id of the registered type.
+    pub type_id: TypeId,
+    /// Converts the data of the type into its serialized form.
+    pub serialize: fn(&dyn Any) -> Result<Value>,
+    /// Converts the serialized form back into boxed data of the type.
+    pub deserialize: fn(&Value) -> Result<AnyBox>,
+    /// Adds deserialized data as a component of an entity. It is only used for components.
+    pub add_component: fn(&mut Entities, Entity, AnyBox) -> Result<()>,
+}
+
+/// The types of components and resources that opted into serialization, reflection or debug output.
+///
+/// Every serializable type is registered under a name, which is what identifies it in the scene
+/// files, so the names must not change if old files have to keep loading. Reflected types are
+/// registered under the name of the type.
+#[derive(Debug, Default)]
+pub struct TypeRegistry {
+    /// The registered component types.
+    #[cfg(feature = "serde")]
+    components: Vec<Registration>,
+    /// The registered resource types.
+    #[cfg(feature = "serde")]
+    resources: Vec<Registration>,
+    /// The types registered for reflection, both components and resources.
+    reflected: Vec<ReflectRegistration>,
+    /// The functions that format the types registered for debugging, keyed by their type id.
+    debug: HashMap<TypeId, DebugFormatter>,
+}
+
+impl TypeRegistry {
+    /// Registers the reflection of a type.
+    ///
+    /// Registering a type or a type name that was already registered replaces the previous registration.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type to reflect.
+    pub fn register_reflect<T: Reflect>(&mut self) {
+        let registration = ReflectRegistration::of::<T>();
+
+        self.reflected
+            .retain(|reflected| reflected.type_id != registration.type_id && reflected.name != registration.name);
+        self.reflected.push(registration);
+    }
+
+    /// Checks if a type was registered for reflection.
+    pub fn contains_reflect<T: Any>(&self) -> bool {
+        self.reflected.iter().any(|registration| registration.type_id == TypeId::of::<T>())
+    }
+
+    /// Registers a type so that its `Debug` output is shown when inspecting the world.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type to format.
+    pub fn register_debug<T: Any + Debug>(&mut self) {
+        self.debug.insert(TypeId::of::<T>(), |data| data.downcast_ref::<T>().map(|data| format!("{:?}", data)));
+    }
+
+    /// Formats type-erased data with the `Debug` implementation of its type if it was registered
+    /// for debugging, or with its reflection if it was reflected.
+    pub(crate) fn debug_string(&self, type_id: &TypeId, data: &dyn Any) -> Option<String> {
+        if let Some(format) = self.debug.get(type_id) {
+            return format(data);
+        }
+
+        self.reflected
+            .iter()
+            .find(|registration| registration.type_id == *type_id)
+            .and_then(|registration| (registration.as_reflect)(data))
+            .map(|data| data.debug_string())
+    }
+
+    /// Gets the registrations of the reflected types.
+    pub(crate) fn reflected(&self) -> &[ReflectRegistration] {
+        &self.reflected
+    }
+
+    /// Gets the registration of the reflected type with the given name.
+    pub(crate) fn get_reflect(&self, name: &str) -> Option<&ReflectRegistration> {
+        self.reflected.iter().find(|registration| registration.name == name)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TypeRegistry {
+    /// Registers a component type for serialization.
+    ///
+    /// Registering a type or a name that was already registered replaces the previous registration.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component.
+    /// * `name` - The name the component is saved under.
+    pub fn register_component<T: Any + MaybeSendSync + Serialize + DeserializeOwned>(&mut self, name: &str) {
+        Self::register::<T>(&mut self.components, name);
+    }
+
+    /// Registers a resource type for serialization.
+    ///
+    /// Registering a type or a name that was already registered replaces the previous registration.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the resource.
+    /// * `name` - The name the resource is saved under.
+    pub fn register_resource<T: Any + MaybeSendSync + Serialize + DeserializeOwned>(&mut self, name: &str) {
+        Self::register::<T>(&mut self.resources, name);
+    }
+
+    /// Checks if a component type was registered for serialization.
+    pub fn contains_component<T: Any>(&self) -> bool {
+        self.components.iter().any(|registration| registration.type_id == TypeId::of::<T>())
+    }
+
+    /// Checks if a resource type was registered for serialization.
+    pub fn contains_resource<T: Any>(&self) -> bool {
+        self.resources.iter().any(|registration| registration.type_id == TypeId::of::<T>())
+    }
+
+    /// Gets the registrations of the component types.
+    pub(crate) fn components(&self) -> &[Registration] {
+        &self.components
+    }
+
+    /// Gets the registrations of the resource types.
+    pub(crate) fn resources(&self) -> &[Registration] {
+        &self.resources
+    }
+
+    /// Gets the registration of the component type saved under the given name.
+    pub(crate) fn get_component(&self, name: &str) -> Option<&Registration> {
+        self.components.iter().find(|registration| registration.name == name)
+    }
+
+    /// Gets the registration of the resource type saved under the given name.
+    pub(crate) fn get_resource(&self, name: &str) -> Option<&Registration> {
+        self.resources.iter().find(|registration| registration.name == name)
+    }
+
+    /// Adds the registration of `T` to the given list, replacing the ones with the same type or name.
+    fn register<T: Any + MaybeSendSync + Serialize + DeserializeOwned>(registrations: &mut Vec<Registration>, name: &str) {
+        let type_id = TypeId::of::<T>();
+        registrations.retain(|registration| registration.type_id != type_id && registration.name != name);
+
+        registrations.push(Registration {
+            name: name.to_owned(),
+            type_id,
+            serialize: serialize::<T>,
+            deserialize: deserialize::<T>,
+            add_component: add_component::<T>,
+        });
+    }
+}
+
+/// Serializes data that is known to be of type `T`.
+#[cfg(feature = "serde")]
+fn serialize<T: Any + Serialize>(data: &dyn Any) -> Result<Value> {
+    let data = data.downcast_ref::<T>().ok_or(CustomErrors::DowncastToWrongType)?;
+
+    Ok(serde_json::to_value(data)?)
+}
+
+/// Deserializes data of type `T` and boxes it.
+#[cfg(feature = "serde")]
+fn deserialize<T: Any + MaybeSendSync + DeserializeOwned>(value: &Value) -> Result<AnyBox> {
+    Ok(Box::new(T::deserialize(value)?))
+}
+
+/// Adds boxed data of type `T` as a component of an entity.
+#[cfg(feature = "serde")]
+fn add_component<T: Any + MaybeSendSync>(entities: &mut Entities, entity: Entity, data: AnyBox) -> Result<()> {
+    let data = data.downcast::<T>().map_err(|_| CustomErrors::DowncastToWrongType)?;
+
+    entities.add_component_by_entity_id(*data, entity)
+}
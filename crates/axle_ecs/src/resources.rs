@@ -3,12 +3,14 @@ use std::{
     collections::HashMap,
 };
 
+use crate::sync::{AnyBox, MaybeSendSync};
+
 /// A structure to store resources in the game world.
 #[derive(Default)]
 pub struct Resources {
     /// The hashmap of resources, where the key is the type id of the resource
     /// and the value is the actual resource.
-    data: HashMap<TypeId, Box<dyn Any>>,
+    data: HashMap<TypeId, AnyBox>,
 }
 
 impl Resources {
@@ -30,7 +32,7 @@ impl Resources {
     ///
     /// resources.add(world_width);
     /// ```
-    pub fn add(&mut self, data: impl Any) {
+    pub fn add(&mut self, data: impl Any + MaybeSendSync) {
         let type_id = data.type_id();
         self.data.insert(type_id, Box::new(data));
     }
@@ -122,10 +124,129 @@ impl Resources {
         let type_id = TypeId::of::<T>();
         self.data.remove(&type_id);
     }
+
+    /// Takes the resource with the given type id out of the resources, so that it can be lent to
+    /// a system running on another thread.
+    pub(crate) fn take_by_type_id(&mut self, type_id: &TypeId) -> Option<AnyBox> {
+        self.data.remove(type_id)
+    }
+
+    /// Moves every resource of `other` into these resources, replacing the ones with the same type.
+    pub(crate) fn extend(&mut self, other: Resources) {
+        self.data.extend(other.data);
+    }
+
+    /// Gets a type-erased reference to the resource with the given type id.
+    pub(crate) fn get_by_type_id(&self, type_id: &TypeId) -> Option<&dyn Any> {
+        self.data.get(type_id).map(|data| &**data as &dyn Any)
+    }
+
+    /// Gets a type-erased mutable reference to the resource with the given type id.
+    pub(crate) fn get_mut_by_type_id(&mut self, type_id: &TypeId) -> Option<&mut dyn Any> {
+        self.data.get_mut(type_id).map(|data| &mut **data as &mut dyn Any)
+    }
+
+    /// Stores a boxed resource under the given type id.
+    pub(crate) fn insert_by_type_id(&mut self, type_id: TypeId, data: AnyBox) {
+        self.data.insert(type_id, data);
+    }
+}
+
+/// A structure to store the resources that can't be sent to other threads.
+///
+/// Handles like the window or the graphics context of a platform library must stay on the thread
+/// that created them, so they can't be regular resources when the `parallel` feature is enabled.
+/// Systems reach them through the `NonSend` and `NonSendMut` parameters, which make them always
+/// run on the thread that runs the world.
+#[derive(Default)]
+pub struct NonSendResources {
+    /// The hashmap of resources, where the key is the type id of the resource
+    /// and the value is the actual resource.
+    data: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl NonSendResources {
+    /// Add a resource that must stay on the current thread.
+    ///
+    /// Resources are stored based on their type id.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data you want to save as a resource.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    /// use axle_ecs::resources::NonSendResources;
+    ///
+    /// let mut resources = NonSendResources::default();
+    /// resources.add(Rc::new(10_u32));
+    ///
+    /// assert_eq!(**resources.get_ref::<Rc<u32>>().unwrap(), 10);
+    /// ```
+    pub fn add(&mut self, data: impl Any) {
+        let type_id = data.type_id();
+        self.data.insert(type_id, Box::new(data));
+    }
+
+    /// Get a reference to a resource.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the resource you want to get a reference to.
+    ///
+    /// # Returns
+    ///
+    /// An option containing a reference to the resource. If the resource doesn't exist, the option is `None`.
+    pub fn get_ref<T: Any>(&self) -> Option<&T> {
+        self.data.get(&TypeId::of::<T>()).and_then(|data| data.downcast_ref())
+    }
+
+    /// Get a mutable reference to a resource.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the resource you want to get a mutable reference to.
+    ///
+    /// # Returns
+    ///
+    /// An option containing a mutable reference to the resource. If the resource doesn't exist, the option is `None`.
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.data.get_mut(&TypeId::of::<T>()).and_then(|data| data.downcast_mut())
+    }
+
+    /// Remove a resource by its type.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the resource you want to remove.
+    pub fn remove<T: Any>(&mut self) {
+        self.data.remove(&TypeId::of::<T>());
+    }
+
+    /// Gets a type-erased reference to the resource with the given type id.
+    pub(crate) fn get_by_type_id(&self, type_id: &TypeId) -> Option<&dyn Any> {
+        self.data.get(type_id).map(|data| &**data)
+    }
+
+    /// Takes the resource with the given type id out of the resources, so that it can be lent to
+    /// a system running on the thread that runs the world.
+    pub(crate) fn take_by_type_id(&mut self, type_id: &TypeId) -> Option<Box<dyn Any>> {
+        self.data.remove(type_id)
+    }
+
+    /// Inserts the data of a resource under the given type id, usually to give back a resource
+    /// taken with `take_by_type_id`.
+    pub(crate) fn insert_by_type_id(&mut self, type_id: TypeId, data: Box<dyn Any>) {
+        self.data.insert(type_id, data);
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{cell::Cell, rc::Rc};
+
     use super::*;
 
     #[test]
@@ -169,6 +290,25 @@ mod tests {
         assert!(!resources.data.contains_key(&world_width_type_id));
     }
 
+    #[test]
+    fn non_send_resources_are_kept_apart() {
+        let resources = initialize_resource();
+        let mut non_send = NonSendResources::default();
+        non_send.add(Rc::new(Cell::new(50.0_f32)));
+
+        {
+            let width = non_send.get_mut::<Rc<Cell<f32>>>().unwrap();
+            width.set(width.get() + 1.0);
+        }
+
+        assert_eq!(non_send.get_ref::<Rc<Cell<f32>>>().unwrap().get(), 51.0);
+        assert!(resources.get_ref::<Rc<Cell<f32>>>().is_none());
+        assert!(non_send.get_ref::<WorldWidth>().is_none());
+
+        non_send.remove::<Rc<Cell<f32>>>();
+        assert!(non_send.get_ref::<Rc<Cell<f32>>>().is_none());
+    }
+
     fn initialize_resource() -> Resources {
         let mut resources = Resources::default();
         let world_width = WorldWidth(100.0);
@@ -0,0 +1,419 @@
+use std::{any::{Any, TypeId}, collections::{BTreeMap, HashMap}, fs, path::Path};
+use eyre::Result;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::prelude::*;
+use crate::entities::Entities;
+use crate::sync::{AnyBox, MaybeSendSync};
+
+pub mod prefab;
+
+/// The deserialized components of an entity, with the functions that add them to it.
+pub(crate) type SceneComponents = Vec<(fn(&mut Entities, Entity, AnyBox) -> Result<()>, AnyBox)>;
+
+/// A snapshot of the serializable entities and resources of a world.
+///
+/// Only the components and resources registered with `World::register_serializable_component` and
+/// `World::register_serializable_resource` are stored, under the name they were registered with.
+/// Scenes are written as JSON, so they can be used both for level files and for save games.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    /// The saved entities.
+    #[serde(default)]
+    pub entities: Vec<SceneEntity>,
+    /// The saved resources, keyed by their registered name.
+    #[serde(default)]
+    pub resources: BTreeMap<String, Value>,
+}
+
+/// An entity stored in a `Scene`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneEntity {
+    /// The position of the parent of the entity in the entities of the scene, if it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<usize>,
+    /// The saved components, keyed by their registered name.
+    #[serde(default)]
+    pub components: BTreeMap<String, Value>,
+}
+
+impl Scene {
+    /// Writes the scene as a JSON string.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the JSON string if succeeds or an error if it fails.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Reads a scene from a JSON string.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - The JSON string to read.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the scene if succeeds or an error if it fails.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Writes the scene to a file as JSON, replacing the file if it already exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, self.to_json()?)?;
+
+        Ok(())
+    }
+
+    /// Reads a scene from a JSON file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the scene if succeeds or an error if it fails.
+    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+impl World {
+    /// Registers a component type and opts it into serialization, so that it is saved in scenes.
+    ///
+    /// The component is registered in the entities too if it wasn't yet, keeping its existing data
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component.
+    /// * `name` - The name the component is saved under. It must not change if old scenes have to
+    ///   keep loading.
+    pub fn register_serializable_component<T>(&mut self, name: &str)
+    where
+        T: Any + MaybeSendSync + Serialize + DeserializeOwned,
+    {
+        self.entities.register_component::<T>();
+
+        self.registry.register_component::<T>(name);
+    }
+
+    /// Opts a resource type into serialization, so that it is saved in scenes.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the resource.
+    /// * `name` - The name the resource is saved under. It must not change if old scenes have to
+    ///   keep loading.
+    pub fn register_serializable_resource<T>(&mut self, name: &str)
+    where
+        T: Any + MaybeSendSync + Serialize + DeserializeOwned,
+    {
+        self.registry.register_resource::<T>(name);
+    }
+
+    /// Saves the serializable entities and resources of the world in a scene.
+    ///
+    /// An entity is saved if it has at least one serializable component, and only those components
+    /// are saved. The parent of an entity is kept if the parent is saved too.
+    ///
+    /// Components that store `Entity` handles can't be saved meaningfully, because the entities get
+    /// new handles when the scene is loaded.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the scene if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If a component or a resource fails to serialize, an error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::World;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    /// world.register_serializable_component::<Health>("Health");
+    /// world.create_entity().with_component(Health(100)).unwrap();
+    ///
+    /// let json = world.save_scene().unwrap().to_json().unwrap();
+    ///
+    /// let mut loaded = World::new();
+    /// loaded.register_serializable_component::<Health>("Health");
+    /// loaded.load_scene(&axle_ecs::scene::Scene::from_json(&json).unwrap()).unwrap();
+    ///
+    /// assert_eq!(loaded.query::<&Health>().unwrap().iter().next().unwrap().0, 100);
+    /// ```
+    pub fn save_scene(&self) -> Result<Scene> {
+        let mut scene = Scene::default();
+        let mut saved: Vec<Entity> = vec![];
+
+        for entity in self.entities.alive_entities() {
+            let components = self.serialize_components(entity)?;
+
+            if !components.is_empty() {
+                saved.push(entity);
+                scene.entities.push(SceneEntity { parent: None, components });
+            }
+        }
+
+        let positions: HashMap<Entity, usize> = saved.iter().enumerate().map(|(position, entity)| (*entity, position)).collect();
+
+        for (scene_entity, entity) in scene.entities.iter_mut().zip(&saved) {
+            scene_entity.parent = self
+                .entities
+                .get_parent(*entity)
+                .and_then(|parent| positions.get(&parent).copied());
+        }
+
+        for registration in self.registry.resources() {
+            if let Some(data) = self.resources.get_by_type_id(&registration.type_id) {
+                scene.resources.insert(registration.name.clone(), (registration.serialize)(data)?);
+            }
+        }
+
+        Ok(scene)
+    }
+
+    /// Loads a scene into the world.
+    ///
+    /// The entities of the scene are created as new entities, next to the ones that already exist,
+    /// and the resources of the scene replace the ones of the same type.
+    ///
+    /// # Arguments
+    ///
+    /// * `scene` - The scene to load.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the handles of the created entities, in the order of the scene, if
+    /// succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the scene contains a type that wasn't registered for serialization, data that fails to
+    /// deserialize, or an invalid parent, an error is returned and the world is left unchanged.
+    pub fn load_scene(&mut self, scene: &Scene) -> Result<Vec<Entity>> {
+        // Everything is deserialized first, so that invalid scenes don't leave half-loaded entities
+        let mut entities_data = Vec::with_capacity(scene.entities.len());
+
+        for scene_entity in &scene.entities {
+            if scene_entity.parent.is_some_and(|parent| parent >= scene.entities.len()) {
+                return Err(CustomErrors::EntityDoesNotExist.into());
+            }
+
+            entities_data.push(self.deserialize_components(&scene_entity.components)?);
+        }
+
+        let mut resources: Vec<(TypeId, AnyBox)> = vec![];
+
+        for (name, value) in &scene.resources {
+            let registration = self
+                .registry
+                .get_resource(name)
+                .ok_or_else(|| CustomErrors::SerializableTypeNotRegistered(name.clone()))?;
+
+            resources.push((registration.type_id, (registration.deserialize)(value)?));
+        }
+
+        let created: Vec<Entity> = scene.entities.iter().map(|_| self.entities.create_entity().entity()).collect();
+
+        if let Err(error) = self.fill_scene_entities(scene, &created, entities_data) {
+            for entity in created {
+                self.entities.delete_entity_by_id(entity).ok();
+            }
+
+            return Err(error);
+        }
+
+        for (type_id, data) in resources {
+            self.resources.insert_by_type_id(type_id, data);
+        }
+
+        Ok(created)
+    }
+
+    /// Serializes the components of an entity whose type was registered for serialization.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the serialized components, keyed by their registered name, if
+    /// succeeds or an error if it fails.
+    pub(crate) fn serialize_components(&self, entity: Entity) -> Result<BTreeMap<String, Value>> {
+        let mut components = BTreeMap::new();
+
+        for registration in self.registry.components() {
+            let Some(cell) = self
+                .entities
+                .get_storage_by_type_id(&registration.type_id)
+                .and_then(|storage| storage.get_cell(entity.index()))
+            else {
+                continue;
+            };
+
+            let value = (registration.serialize)(&*cell.borrow())?;
+            components.insert(registration.name.clone(), value);
+        }
+
+        Ok(components)
+    }
+
+    /// Deserializes components keyed by their registered name.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the deserialized components, with the functions that add them to an
+    /// entity, if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If a component type wasn't registered for serialization or its data fails to deserialize,
+    /// an error is returned.
+    pub(crate) fn deserialize_components(&self, components: &BTreeMap<String, Value>) -> Result<SceneComponents> {
+        let mut deserialized = vec![];
+
+        for (name, value) in components {
+            let registration = self
+                .registry
+                .get_component(name)
+                .ok_or_else(|| CustomErrors::SerializableTypeNotRegistered(name.clone()))?;
+
+            deserialized.push((registration.add_component, (registration.deserialize)(value)?));
+        }
+
+        Ok(deserialized)
+    }
+
+    /// Adds the deserialized components to the entities created for a scene and links their parents.
+    fn fill_scene_entities(
+        &mut self,
+        scene: &Scene,
+        created: &[Entity],
+        entities_data: Vec<SceneComponents>,
+    ) -> Result<()> {
+        for (entity, components) in created.iter().zip(entities_data) {
+            for (add_component, data) in components {
+                add_component(&mut self.entities, *entity, data)?;
+            }
+        }
+
+        for (entity, scene_entity) in created.iter().zip(&scene.entities) {
+            if let Some(parent) = scene_entity.parent {
+                self.entities.set_parent(*entity, created[parent])?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load_round_trip() -> Result<()> {
+        let mut world = initialize_world();
+        world.add_resource(Score(12));
+
+        let root = world.create_entity().with_component(Position(1.0, 2.0))?.entity();
+        let child = world.create_entity().with_component(Position(3.0, 4.0))?.with_component(Health(50))?.entity();
+        // Entities without serializable components are not saved
+        world.create_entity().with_component(Unsaved)?;
+        world.set_parent(child, root)?;
+
+        let scene = Scene::from_json(&world.save_scene()?.to_json()?)?;
+        assert_eq!(scene.entities.len(), 2);
+
+        let mut loaded = initialize_world();
+        let created = loaded.load_scene(&scene)?;
+
+        assert_eq!(created.len(), 2);
+        assert_eq!(loaded.entities.get_parent(created[1]), Some(created[0]));
+        assert_eq!(loaded.get_resource::<Score>(), Some(&Score(12)));
+
+        let healths: Vec<u32> = loaded.query::<&Health>()?.iter().map(|health| health.0).collect();
+        assert_eq!(healths, vec![50]);
+
+        let mut positions: Vec<(f32, f32)> = loaded.query::<&Position>()?.iter().map(|position| (position.0, position.1)).collect();
+        positions.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(positions, vec![(1.0, 2.0), (3.0, 4.0)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_scenes_leave_the_world_unchanged() -> Result<()> {
+        let mut world = initialize_world();
+        let existing = world.create_entity().with_component(Health(10))?.entity();
+
+        let unknown = Scene::from_json(r#"{ "entities": [ { "components": { "Mana": 3 } } ] }"#)?;
+        assert!(world.load_scene(&unknown).is_err());
+
+        let wrong_data = Scene::from_json(r#"{ "entities": [ { "components": { "Health": "full" } } ] }"#)?;
+        assert!(world.load_scene(&wrong_data).is_err());
+
+        let cycle = Scene::from_json(
+            r#"{ "entities": [ { "parent": 1, "components": {} }, { "parent": 0, "components": {} } ] }"#,
+        )?;
+        assert!(world.load_scene(&cycle).is_err());
+
+        assert_eq!(world.entities.alive_entities().collect::<Vec<Entity>>(), vec![existing]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn scenes_can_be_saved_to_files() -> Result<()> {
+        let mut world = initialize_world();
+        world.create_entity().with_component(Health(7))?;
+
+        let path = std::env::temp_dir().join(format!("axle_ecs_scene_{}.json", std::process::id()));
+        world.save_scene()?.save_to_file(&path)?;
+
+        let scene = Scene::load_from_file(&path);
+        fs::remove_file(&path)?;
+
+        assert_eq!(scene?, world.save_scene()?);
+
+        Ok(())
+    }
+
+    fn initialize_world() -> World {
+        let mut world = World::new();
+        world.register_component::<Unsaved>();
+        world.register_serializable_component::<Health>("Health");
+        world.register_serializable_component::<Position>("Position");
+        world.register_serializable_resource::<Score>("Score");
+
+        world
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Health(u32);
+
+    #[derive(Serialize, Deserialize)]
+    struct Position(f32, f32);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Score(u32);
+
+    struct Unsaved;
+}
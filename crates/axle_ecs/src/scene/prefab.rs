@@ -0,0 +1,298 @@
+use std::{collections::BTreeMap, fs, path::Path};
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::bundle::Bundle;
+use crate::prelude::*;
+
+/// A template of an entity, made of serialized components, that can be spawned many times.
+///
+/// Like scenes, prefabs only contain the components registered with
+/// `World::register_serializable_component`, keyed by their registered name. They are registered in
+/// the world under a name, either built in code, taken from an existing entity or loaded from a
+/// JSON file that maps the names of the prefabs to their components:
+///
+/// ```json
+/// {
+///     "goblin": { "components": { "Health": 30, "Speed": 1.5 } },
+///     "orc": { "components": { "Health": 80, "Speed": 1.0 } }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Prefab {
+    /// The serialized components, keyed by their registered name.
+    #[serde(default)]
+    pub components: BTreeMap<String, Value>,
+}
+
+impl Prefab {
+    /// Creates an empty prefab.
+    ///
+    /// # Returns
+    ///
+    /// A new `Prefab` struct.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a component to the prefab, replacing the one with the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the component type was registered for serialization with.
+    /// * `component` - The component's data.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the prefab if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the component fails to serialize, an error is returned.
+    pub fn with_component<T: Serialize>(mut self, name: &str, component: &T) -> Result<Self> {
+        self.components.insert(name.to_owned(), serde_json::to_value(component)?);
+
+        Ok(self)
+    }
+}
+
+impl World {
+    /// Registers a prefab under a name, replacing the prefab that had the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the prefab is spawned by.
+    /// * `prefab` - The prefab.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the prefab contains a type that wasn't registered for serialization or data that fails
+    /// to deserialize, an error is returned and the prefab isn't registered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use axle_ecs::{scene::prefab::Prefab, World};
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    /// world.register_serializable_component::<Health>("Health");
+    ///
+    /// world.register_prefab("goblin", Prefab::new().with_component("Health", &Health(30)).unwrap()).unwrap();
+    ///
+    /// world.spawn_prefab("goblin").unwrap();
+    /// world.spawn_prefab_with("goblin", (Health(60),)).unwrap();
+    ///
+    /// let mut healths: Vec<u32> = world.query::<&Health>().unwrap().iter().map(|health| health.0).collect();
+    /// healths.sort();
+    /// assert_eq!(healths, vec![30, 60]);
+    /// ```
+    pub fn register_prefab(&mut self, name: &str, prefab: Prefab) -> Result<()> {
+        // Invalid prefabs are rejected now instead of every time they are spawned
+        self.deserialize_components(&prefab.components)?;
+
+        self.prefabs.insert(name.to_owned(), prefab);
+
+        Ok(())
+    }
+
+    /// Gets the prefab registered under the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the prefab.
+    ///
+    /// # Returns
+    ///
+    /// The prefab if it was registered, otherwise `None`.
+    pub fn get_prefab(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    /// Creates a prefab from the serializable components of an entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the prefab if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the entity doesn't exist or one of its components fails to serialize, an error is returned.
+    pub fn prefab_from_entity(&self, entity: Entity) -> Result<Prefab> {
+        self.entities.validate_entity(entity)?;
+
+        Ok(Prefab { components: self.serialize_components(entity)? })
+    }
+
+    /// Registers every prefab of a JSON object that maps the names of the prefabs to them.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - The JSON text.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the names of the registered prefabs if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the JSON is not valid or one of the prefabs is invalid, an error is returned and none of
+    /// the prefabs are registered.
+    pub fn load_prefabs_from_json(&mut self, json: &str) -> Result<Vec<String>> {
+        let prefabs: BTreeMap<String, Prefab> = serde_json::from_str(json)?;
+
+        for prefab in prefabs.values() {
+            self.deserialize_components(&prefab.components)?;
+        }
+
+        let names = prefabs.keys().cloned().collect();
+        self.prefabs.extend(prefabs);
+
+        Ok(names)
+    }
+
+    /// Registers every prefab of a JSON file that maps the names of the prefabs to them.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the names of the registered prefabs if succeeds or an error if it fails.
+    pub fn load_prefabs_from_file(&mut self, path: impl AsRef<Path>) -> Result<Vec<String>> {
+        self.load_prefabs_from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Creates a new entity with the components of a prefab.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the prefab.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the handle of the new entity if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If there is no prefab with the given name, an error is returned.
+    pub fn spawn_prefab(&mut self, name: &str) -> Result<Entity> {
+        self.spawn_prefab_with(name, ())
+    }
+
+    /// Creates a new entity with the components of a prefab, replacing or adding the components of
+    /// the given bundle.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the prefab.
+    /// * `overrides` - The components that replace the ones of the prefab, or are added to them.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the handle of the new entity if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If there is no prefab with the given name, an error is returned.
+    pub fn spawn_prefab_with(&mut self, name: &str, overrides: impl Bundle) -> Result<Entity> {
+        let prefab = self
+            .prefabs
+            .get(name)
+            .ok_or_else(|| CustomErrors::PrefabNotRegistered(name.to_owned()))?;
+        let components = self.deserialize_components(&prefab.components)?;
+
+        let entity = self.entities.create_entity().entity();
+
+        for (add_component, data) in components {
+            add_component(&mut self.entities, entity, data)?;
+        }
+
+        overrides.add_to_entity(&mut self.entities, entity)?;
+
+        Ok(entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefabs_are_spawned_with_overrides() -> Result<()> {
+        let mut world = initialize_world();
+
+        let template = world.create_entity().with_component(Health(30))?.with_component(Speed(1.5))?.entity();
+        world.register_prefab("goblin", world.prefab_from_entity(template)?)?;
+        world.delete_entity_by_id(template)?;
+
+        let goblins = [
+            world.spawn_prefab("goblin")?,
+            world.spawn_prefab("goblin")?,
+            world.spawn_prefab_with("goblin", (Health(90), Boss))?,
+        ];
+
+        let spawned: Vec<(u32, f32, bool)> = world
+            .query::<(&Health, &Speed, Option<&Boss>)>()?
+            .iter()
+            .map(|(health, speed, boss)| (health.0, speed.0, boss.is_some()))
+            .collect();
+
+        assert_eq!(spawned, vec![(30, 1.5, false), (30, 1.5, false), (90, 1.5, true)]);
+        assert!(goblins.iter().all(|goblin| world.entities.is_alive(*goblin)));
+        assert!(world.spawn_prefab("orc").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn prefabs_are_loaded_from_files() -> Result<()> {
+        let mut world = initialize_world();
+
+        let path = std::env::temp_dir().join(format!("axle_ecs_prefabs_{}.json", std::process::id()));
+        fs::write(&path, r#"{ "orc": { "components": { "Health": 80 } }, "bat": { "components": { "Speed": 4.0 } } }"#)?;
+
+        let names = world.load_prefabs_from_file(&path);
+        fs::remove_file(&path)?;
+
+        assert_eq!(names?, vec!["bat", "orc"]);
+        world.spawn_prefab("orc")?;
+        assert_eq!(world.query::<&Health>()?.iter().next().map(|health| health.0), Some(80));
+
+        // Invalid prefabs are rejected without registering any of them
+        assert!(world.load_prefabs_from_json(r#"{ "ghost": { "components": { "Mana": 3 } } }"#).is_err());
+        assert!(world.register_prefab("slow", Prefab::new().with_component("Speed", &"slow")?).is_err());
+        assert!(world.get_prefab("ghost").is_none() && world.get_prefab("slow").is_none());
+
+        Ok(())
+    }
+
+    fn initialize_world() -> World {
+        let mut world = World::new();
+        world.register_serializable_component::<Health>("Health");
+        world.register_serializable_component::<Speed>("Speed");
+
+        world
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Health(u32);
+
+    #[derive(Serialize, Deserialize)]
+    struct Speed(f32);
+
+    struct Boss;
+}
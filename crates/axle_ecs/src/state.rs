@@ -0,0 +1,396 @@
+use std::{any::{Any, TypeId}, fmt::Debug};
+use eyre::Result;
+
+use crate::entities::Entities;
+use crate::prelude::*;
+use crate::resources::Resources;
+use crate::sync::MaybeSendSync;
+use crate::world::StateUpdater;
+
+/// The types that can be used as the states of a `State`, usually a fieldless enum.
+pub trait States: Any + MaybeSendSync + Clone + PartialEq + Debug {}
+
+impl<T: Any + MaybeSendSync + Clone + PartialEq + Debug> States for T {}
+
+/// The current state of the game, like being in the menu, loading or playing, stored as a resource.
+///
+/// Systems queue transitions with `set`, and the world applies the last queued one at the start of
+/// the next run of the systems. The systems are tied to the states with run conditions:
+///
+/// * `in_state` runs a system every frame while the state is the given one.
+/// * `on_enter` runs a system once, in the frame the state is entered.
+/// * `on_exit` runs a system once, in the frame the state is left.
+///
+/// The entities with a `StateScoped` component are deleted, together with their descendants, at
+/// the end of the run of the systems in which their state is left, so the `on_exit` systems still
+/// see them.
+///
+/// # Example
+///
+/// ```
+/// use axle_ecs::{entities::query_entity::QueryEntity, resources::Resources, World};
+/// use axle_ecs::state::{in_state, on_enter, State};
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// enum GameState {
+///     Menu,
+///     Playing,
+/// }
+///
+/// let mut world = World::new();
+/// world.add_state(GameState::Menu);
+/// world.add_resource(0_u32);
+///
+/// world
+///     .create_system(&|_: &Vec<QueryEntity>, resources: &mut Resources| {
+///         resources.get_mut::<State<GameState>>().unwrap().set(GameState::Playing);
+///         Ok(())
+///     })
+///     .run_if(in_state(GameState::Menu)).unwrap();
+/// world
+///     .create_system(&|_: &Vec<QueryEntity>, resources: &mut Resources| {
+///         *resources.get_mut::<u32>().unwrap() += 1;
+///         Ok(())
+///     })
+///     .run_if(on_enter(GameState::Playing)).unwrap();
+///
+/// world.run_all_systems().unwrap();
+/// world.run_all_systems().unwrap();
+/// world.run_all_systems().unwrap();
+///
+/// assert_eq!(*world.get_resource::<State<GameState>>().unwrap().get(), GameState::Playing);
+/// assert_eq!(*world.get_resource::<u32>().unwrap(), 1);
+/// ```
+#[derive(Debug)]
+pub struct State<S> {
+    /// The current state.
+    current: S,
+    /// The state to change to at the start of the next run of the systems.
+    queued: Option<S>,
+    /// Whether the current state was entered at the start of the current run of the systems.
+    entered: bool,
+    /// The state that was left at the start of the current run of the systems, if any.
+    exited: Option<S>,
+    /// Whether the initial state was entered already.
+    started: bool,
+}
+
+impl<S: States> State<S> {
+    /// Creates a state machine that enters the given state in the first run of the systems.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial` - The initial state.
+    ///
+    /// # Returns
+    ///
+    /// A new `State` struct.
+    pub fn new(initial: S) -> Self {
+        Self {
+            current: initial,
+            queued: None,
+            entered: false,
+            exited: None,
+            started: false,
+        }
+    }
+
+    /// Gets the current state.
+    pub fn get(&self) -> &S {
+        &self.current
+    }
+
+    /// Queues a transition to the given state, applied at the start of the next run of the systems.
+    ///
+    /// Only the last queued transition is applied, and changing to the current state does nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `next` - The state to change to.
+    pub fn set(&mut self, next: S) {
+        self.queued = Some(next);
+    }
+
+    /// Gets the state queued to change to, if any.
+    pub fn queued(&self) -> Option<&S> {
+        self.queued.as_ref()
+    }
+
+    /// Checks if the given state was entered at the start of the current run of the systems.
+    pub fn just_entered(&self, state: &S) -> bool {
+        self.entered && self.current == *state
+    }
+
+    /// Checks if the given state was left at the start of the current run of the systems.
+    pub fn just_exited(&self, state: &S) -> bool {
+        self.exited.as_ref() == Some(state)
+    }
+
+    /// Applies the queued transition, if any.
+    ///
+    /// The world calls it at the start of every run of the systems for the states added with
+    /// `World::add_state`, so there is usually no need to call it manually.
+    ///
+    /// # Returns
+    ///
+    /// The state that was left, if the state changed.
+    pub fn update(&mut self) -> Option<S> {
+        self.entered = !self.started;
+        self.exited = None;
+        self.started = true;
+
+        let next = self.queued.take().filter(|next| *next != self.current)?;
+
+        self.exited = Some(std::mem::replace(&mut self.current, next));
+        self.entered = true;
+
+        self.exited.clone()
+    }
+}
+
+/// Ties an entity to a state, so that it is deleted together with its descendants at the end of the
+/// run of the systems in which the state is left.
+///
+/// # Example
+///
+/// ```
+/// use axle_ecs::{state::{State, StateScoped}, World};
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// enum GameState {
+///     Menu,
+///     Playing,
+/// }
+///
+/// let mut world = World::new();
+/// world.add_state(GameState::Menu);
+///
+/// let button = world.create_entity().with_component(StateScoped(GameState::Menu)).unwrap().entity();
+///
+/// world.get_resource_mut::<State<GameState>>().unwrap().set(GameState::Playing);
+/// world.run_all_systems().unwrap();
+///
+/// assert!(!world.entities.is_alive(button));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateScoped<S>(pub S);
+
+/// Creates a run condition that is fulfilled while the state of type `S` is the given one.
+///
+/// # Arguments
+///
+/// * `state` - The state the system runs in.
+///
+/// # Returns
+///
+/// The run condition, to pass to `Systems::run_if`.
+pub fn in_state<S: States>(state: S) -> impl Fn(&Resources) -> bool + MaybeSendSync + 'static {
+    move |resources| resources.get_ref::<State<S>>().is_some_and(|current| *current.get() == state)
+}
+
+/// Creates a run condition that is fulfilled in the run of the systems in which the state of type
+/// `S` changed to the given one, including the first run for the initial state.
+///
+/// # Arguments
+///
+/// * `state` - The state whose entering runs the system.
+///
+/// # Returns
+///
+/// The run condition, to pass to `Systems::run_if`.
+pub fn on_enter<S: States>(state: S) -> impl Fn(&Resources) -> bool + MaybeSendSync + 'static {
+    move |resources| resources.get_ref::<State<S>>().is_some_and(|current| current.just_entered(&state))
+}
+
+/// Creates a run condition that is fulfilled in the run of the systems in which the state of type
+/// `S` changed from the given one.
+///
+/// # Arguments
+///
+/// * `state` - The state whose exit runs the system.
+///
+/// # Returns
+///
+/// The run condition, to pass to `Systems::run_if`.
+pub fn on_exit<S: States>(state: S) -> impl Fn(&Resources) -> bool + MaybeSendSync + 'static {
+    move |resources| resources.get_ref::<State<S>>().is_some_and(|current| current.just_exited(&state))
+}
+
+/// Applies the queued transition of the state of type `S`.
+pub(crate) fn transition_state<S: States>(resources: &mut Resources) {
+    if let Some(state) = resources.get_mut::<State<S>>() {
+        state.update();
+    }
+}
+
+/// Deletes the entities scoped to the state of type `S` that was left in the current run of the
+/// systems, if any.
+pub(crate) fn despawn_state_scoped<S: States>(resources: &Resources, entities: &mut Entities) -> Result<()> {
+    let Some(exited) = resources.get_ref::<State<S>>().and_then(|state| state.exited.clone()) else {
+        return Ok(());
+    };
+
+    let scoped: Vec<Entity> = entities
+        .get_storage::<StateScoped<S>>()
+        .map(|storage| {
+            storage
+                .iter()
+                .filter(|(_, scope)| scope.borrow().0 == exited)
+                .filter_map(|(index, _)| entities.entity_at(index))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for entity in scoped {
+        // The entity may have been deleted already as the descendant of another scoped entity
+        if entities.is_alive(entity) {
+            entities.delete_entity_recursive(entity)?;
+        }
+    }
+
+    Ok(())
+}
+
+impl World {
+    /// Adds a state machine with states of type `S` to the world, stored as the `State<S>` resource.
+    ///
+    /// The queued transitions are applied at the start of every run of the systems, in the order
+    /// the states were added, and the initial state is entered in the first one. Adding the same
+    /// type of state more than once does nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial` - The initial state.
+    pub fn add_state<S: States>(&mut self, initial: S) {
+        if self.resources.get_ref::<State<S>>().is_none() {
+            self.resources.add(State::new(initial));
+        }
+
+        if self.state_updaters.iter().any(|updater| updater.type_id == TypeId::of::<S>()) {
+            return;
+        }
+
+        self.state_updaters.push(StateUpdater {
+            type_id: TypeId::of::<S>(),
+            transition: transition_state::<S>,
+            despawn_scoped: despawn_state_scoped::<S>,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transitions_are_applied_once() {
+        let mut state = State::new(GameState::Menu);
+        assert!(state.update().is_none());
+        assert!(state.just_entered(&GameState::Menu));
+
+        state.set(GameState::Playing);
+        state.set(GameState::Paused);
+        assert_eq!(state.queued(), Some(&GameState::Paused));
+        assert_eq!(*state.get(), GameState::Menu);
+
+        assert_eq!(state.update(), Some(GameState::Menu));
+        assert!(state.just_exited(&GameState::Menu));
+        assert!(state.just_entered(&GameState::Paused));
+        assert!(!state.just_entered(&GameState::Playing));
+
+        // Changing to the current state does nothing
+        state.set(GameState::Paused);
+        assert!(state.update().is_none());
+        assert!(!state.just_entered(&GameState::Paused));
+        assert!(!state.just_exited(&GameState::Menu));
+    }
+
+    #[test]
+    fn systems_follow_the_state() -> Result<()> {
+        let mut world = World::new();
+        world.add_state(GameState::Menu);
+        world.add_resource(Vec::<&'static str>::new());
+
+        world
+            .create_system(|_: &Vec<QueryEntity>, resources: &mut Resources| log(resources, "enter menu"))
+            .run_if(on_enter(GameState::Menu))?;
+        world
+            .create_system(|_: &Vec<QueryEntity>, resources: &mut Resources| log(resources, "menu"))
+            .run_if(in_state(GameState::Menu))?;
+        world
+            .create_system(|_: &Vec<QueryEntity>, resources: &mut Resources| log(resources, "exit menu"))
+            .run_if(on_exit(GameState::Menu))?;
+
+        let button = world.create_entity().with_component(StateScoped(GameState::Menu))?.entity();
+        let label = world.create_entity().entity();
+        let player = world.create_entity().with_component(StateScoped(GameState::Playing))?.entity();
+        world.set_parent(label, button)?;
+
+        world.run_all_systems()?;
+        world.run_all_systems()?;
+        world.get_resource_mut::<State<GameState>>().unwrap().set(GameState::Playing);
+        world.run_all_systems()?;
+        world.run_all_systems()?;
+
+        assert_eq!(*world.get_resource::<Vec<&'static str>>().unwrap(), vec!["enter menu", "menu", "menu", "exit menu"]);
+        assert!(!world.entities.is_alive(button));
+        assert!(!world.entities.is_alive(label));
+        assert!(world.entities.is_alive(player));
+
+        Ok(())
+    }
+
+    #[test]
+    fn scoped_entities_are_seen_by_the_on_exit_systems() -> Result<()> {
+        let mut world = World::new();
+        world.add_state(GameState::Menu);
+        world.add_resource(Vec::<&'static str>::new());
+
+        world
+            .create_system(|entities: &Vec<QueryEntity>, resources: &mut Resources| {
+                for _ in entities {
+                    log(resources, "hide button")?;
+                }
+
+                Ok(())
+            })
+            .with_component::<StateScoped<GameState>>()?
+            .run_if(on_exit(GameState::Menu))?;
+
+        let button = world.create_entity().with_component(StateScoped(GameState::Menu))?.entity();
+
+        world.run_all_systems()?;
+        world.get_resource_mut::<State<GameState>>().unwrap().set(GameState::Playing);
+        world.run_all_systems()?;
+
+        assert_eq!(*world.get_resource::<Vec<&'static str>>().unwrap(), vec!["hide button"]);
+        assert!(!world.entities.is_alive(button));
+
+        Ok(())
+    }
+
+    #[test]
+    fn states_are_updated_in_the_order_they_were_added() {
+        let mut world = World::new();
+        world.add_state(GameState::Menu);
+        world.add_state(0_u32);
+        world.add_state(GameState::Playing);
+        world.add_state('a');
+
+        let order: Vec<TypeId> = world.state_updaters.iter().map(|updater| updater.type_id).collect();
+        assert_eq!(order, vec![TypeId::of::<GameState>(), TypeId::of::<u32>(), TypeId::of::<char>()]);
+        assert_eq!(*world.get_resource::<State<GameState>>().unwrap().get(), GameState::Menu);
+    }
+
+    fn log(resources: &mut Resources, message: &'static str) -> Result<()> {
+        resources.get_mut::<Vec<&'static str>>().unwrap().push(message);
+        Ok(())
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum GameState {
+        Menu,
+        Playing,
+        Paused,
+    }
+}
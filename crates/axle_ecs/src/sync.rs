@@ -0,0 +1,54 @@
+//! Types that switch between their single threaded and thread-safe versions depending on the
+//! `parallel` feature.
+//!
+//! Without the feature, components are stored in `RefCell`s and any type can be used as a
+//! component or a resource. With it, components are stored in `AtomicRefCell`s and components,
+//! resources and systems must be `Send + Sync`, so that the systems can run on several threads.
+
+use std::any::Any;
+
+#[cfg(not(feature = "parallel"))]
+pub use std::cell::{Ref as ComponentRef, RefCell as ComponentCell, RefMut as ComponentRefMut};
+
+#[cfg(feature = "parallel")]
+pub use atomic_refcell::{
+    AtomicRef as ComponentRef,
+    AtomicRefCell as ComponentCell,
+    AtomicRefMut as ComponentRefMut,
+};
+
+/// Implemented by every type without the `parallel` feature, and by the `Send + Sync` types with it.
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSendSync {}
+
+#[cfg(not(feature = "parallel"))]
+impl<T> MaybeSendSync for T {}
+
+/// Implemented by every type without the `parallel` feature, and by the `Send + Sync` types with it.
+#[cfg(feature = "parallel")]
+pub trait MaybeSendSync: Send + Sync {}
+
+#[cfg(feature = "parallel")]
+impl<T: Send + Sync> MaybeSendSync for T {}
+
+/// Implemented by every type without the `parallel` feature, and by the `Send` types with it.
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSend {}
+
+#[cfg(not(feature = "parallel"))]
+impl<T> MaybeSend for T {}
+
+/// Implemented by every type without the `parallel` feature, and by the `Send` types with it.
+#[cfg(feature = "parallel")]
+pub trait MaybeSend: Send {}
+
+#[cfg(feature = "parallel")]
+impl<T: Send> MaybeSend for T {}
+
+/// A boxed value of any type, used to store the resources.
+#[cfg(not(feature = "parallel"))]
+pub type AnyBox = Box<dyn Any>;
+
+/// A boxed value of any type, used to store the resources.
+#[cfg(feature = "parallel")]
+pub type AnyBox = Box<dyn Any + Send + Sync>;
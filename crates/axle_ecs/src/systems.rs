@@ -1,21 +1,53 @@
 use eyre::Result;
-use std::any::{Any, TypeId};
+use std::{any::{Any, TypeId}, sync::Arc};
 
-use crate::entities::{query::Query, Entities};
+use crate::commands::Commands;
+use crate::entities::{filter::{Filter, QueryFilter}, typed_query::{self, QueryData}, Entities};
 use crate::prelude::*;
-use crate::resources::Resources;
-
-pub type SystemFunction = &'static dyn Fn(&Vec<QueryEntity>, &mut Resources) -> Result<()>;
+use crate::resources::{NonSendResources, Resources};
+use crate::sync::MaybeSendSync;
+use access::{ResourceAccess, SystemAccess};
+use executor::{BatchSystem, Executor};
+use schedule::{RunCondition, Stage, SystemSchedule};
+use param::IntoSystem;
+use system::System;
+
+pub mod access;
+pub mod executor;
+pub mod param;
+pub mod schedule;
+pub mod system;
+
+#[cfg(not(feature = "parallel"))]
+pub type SystemFunction = Box<dyn System>;
+#[cfg(feature = "parallel")]
+pub type SystemFunction = Box<dyn System + Send>;
 pub type SystemComponents = Vec<TypeId>;
 pub type SystemResources = Vec<TypeId>;
 
 /// Stores all the systems in the ECS.
 #[derive(Default)]
 pub struct Systems {
-    /// The system functions stored as a vector of options.
+    /// The systems stored as a vector of options.
     funtions: Vec<Option<SystemFunction>>,
     /// The components each system has stored as a vector of vectors of type ids.
     components: Vec<SystemComponents>,
+    /// The filters each system's entities must match.
+    filters: Vec<Vec<Filter>>,
+    /// The tick from which each system considers the components added or changed, that is, the
+    /// tick right after its last run.
+    since_ticks: Vec<u64>,
+    /// When each system runs: its stage, its ordering constraints and its run conditions.
+    schedules: Vec<SystemSchedule>,
+    /// The components and resources each system reads and writes.
+    accesses: Vec<SystemAccess>,
+    /// How the systems that don't conflict with each other are run.
+    executor: Executor,
+    /// The amount of systems created so far, used to keep the systems without ordering
+    /// constraints in the order they were created in, regardless of the slot they use.
+    created_count: u64,
+    /// The ids of the systems in the order they run, computed again when the schedules change.
+    order: Option<Vec<usize>>,
     /// The index of the system to add a component to.
     inserting_into_index: usize,
 }
@@ -25,7 +57,11 @@ impl Systems {
     ///
     /// # Arguments
     ///
-    /// * `system` - The system function to add.
+    /// * `system` - The system to add: a function, a closure that owns its state or a `System` struct.
+    ///
+    /// Systems made from functions that take `Res` and `ResMut` parameters declare those resources
+    /// as their access, so they only get them and can run at the same time as the systems they
+    /// don't conflict with.
     ///
     /// # Returns
     ///
@@ -40,23 +76,49 @@ impl Systems {
     ///
     /// let mut systems = Systems::default();
     /// systems.create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()));
+    ///
+    /// let mut runs = 0;
+    /// systems.create_system(move |_: &Vec<QueryEntity>, _: &mut Resources| {
+    ///     runs += 1;
+    ///     Ok(())
+    /// });
     /// ```
-    pub fn create_system(&mut self, system: SystemFunction) -> &mut Self {
+    pub fn create_system<M>(&mut self, system: impl IntoSystem<M>) -> &mut Self {
+        let system = system.into_system();
+        let main_thread = system.needs_main_thread();
+        let access = match system.declared_resources() {
+            Some(resources) => SystemAccess { declared: true, resources, main_thread, ..Default::default() },
+            None => SystemAccess { main_thread, ..Default::default() },
+        };
+        let system: SystemFunction = Box::new(system);
+
         if let Some(index) = self.funtions.iter().position(|x| x.is_none()) {
             self.inserting_into_index = index;
             self.funtions[index] = Some(system);
+            self.since_ticks[index] = 0;
+            self.schedules[index] = SystemSchedule::new(self.created_count);
+            self.accesses[index] = access;
         } else {
             self.funtions.push(Some(system));
             self.components.push(vec![]);
+            self.filters.push(vec![]);
+            self.since_ticks.push(0);
+            self.schedules.push(SystemSchedule::new(self.created_count));
+            self.accesses.push(access);
             self.inserting_into_index = self.funtions.len() - 1;
         }
 
+        self.created_count += 1;
+        self.order = None;
+
         self
     }
 
     /// Add a component to a creating system.
     /// The component will be added to the current system.
     ///
+    /// The component counts as written by the system, use `with_access` to declare it as read only.
+    ///
     /// # Arguments
     ///
     /// * `T` - The type of the component to add.
@@ -86,10 +148,354 @@ impl Systems {
             .get_mut(self.inserting_into_index)
             .ok_or(CustomErrors::CreateSystemNeverCalled)?;
         components.push(type_id);
+        self.accesses[self.inserting_into_index].add_component(type_id, true, true);
 
         Ok(self)
     }
 
+    /// Declares the components the creating system accesses, and how.
+    ///
+    /// The required components of `Q` are added to the system like with `with_component`. Once a
+    /// system declares its access, it can run at the same time as the systems it doesn't
+    /// conflict with, and it only gets the resources declared with `with_resource` and
+    /// `with_resource_mut`, plus its own `Commands` buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `Q` - The components the system accesses, for example `(&mut Transform, &RigidBody)`.
+    ///
+    /// # Returns
+    ///
+    /// A result containing a mutable reference to this struct.
+    ///
+    /// # Errors
+    ///
+    /// If a component is accessed mutably more than once, or both mutably and immutably, an
+    /// error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::entities::query_entity::QueryEntity;
+    /// use axle_ecs::systems::Systems;
+    /// use axle_ecs::resources::Resources;
+    ///
+    /// let mut systems = Systems::default();
+    /// systems
+    ///     .create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()))
+    ///     .with_access::<(&mut u32, &i32, Option<&f32>)>().unwrap();
+    /// ```
+    pub fn with_access<Q: QueryData>(&mut self) -> Result<&mut Self> {
+        let declared = typed_query::validated_access::<Q>()?;
+        let index = self.inserting_into_index;
+
+        let components = self
+            .components
+            .get_mut(index)
+            .ok_or(CustomErrors::CreateSystemNeverCalled)?;
+        let access = &mut self.accesses[index];
+
+        for component in declared {
+            if component.required && !components.contains(&component.type_id) {
+                components.push(component.type_id);
+            }
+
+            access.add_component(component.type_id, component.mutable, component.required);
+        }
+        access.declared = true;
+
+        Ok(self)
+    }
+
+    /// Declares that the creating system reads the resource of type `T`.
+    ///
+    /// See `with_access` for how systems that declare their access are run.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the resource.
+    ///
+    /// # Returns
+    ///
+    /// A result containing a mutable reference to this struct.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::entities::query_entity::QueryEntity;
+    /// use axle_ecs::systems::Systems;
+    /// use axle_ecs::resources::Resources;
+    ///
+    /// let mut systems = Systems::default();
+    /// systems
+    ///     .create_system(&|_: &Vec<QueryEntity>, resources: &mut Resources| {
+    ///         let _gravity = resources.get_ref::<f32>();
+    ///         Ok(())
+    ///     })
+    ///     .with_resource::<f32>().unwrap();
+    /// ```
+    pub fn with_resource<T: Any>(&mut self) -> Result<&mut Self> {
+        self.declare_resource(TypeId::of::<T>(), false)
+    }
+
+    /// Declares that the creating system writes the resource of type `T`.
+    ///
+    /// See `with_access` for how systems that declare their access are run.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the resource.
+    ///
+    /// # Returns
+    ///
+    /// A result containing a mutable reference to this struct.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::entities::query_entity::QueryEntity;
+    /// use axle_ecs::systems::Systems;
+    /// use axle_ecs::resources::Resources;
+    ///
+    /// let mut systems = Systems::default();
+    /// systems
+    ///     .create_system(&|_: &Vec<QueryEntity>, resources: &mut Resources| {
+    ///         if let Some(score) = resources.get_mut::<u32>() {
+    ///             *score += 1;
+    ///         }
+    ///         Ok(())
+    ///     })
+    ///     .with_resource_mut::<u32>().unwrap();
+    /// ```
+    pub fn with_resource_mut<T: Any>(&mut self) -> Result<&mut Self> {
+        self.declare_resource(TypeId::of::<T>(), true)
+    }
+
+    /// Sets how the systems that don't conflict with each other are run.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - The executor to use.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::systems::{executor::Executor, Systems};
+    ///
+    /// let mut systems = Systems::default();
+    /// systems.set_executor(Executor::SingleThreaded);
+    /// ```
+    pub fn set_executor(&mut self, executor: Executor) {
+        self.executor = executor;
+    }
+
+    /// Add a filter to a creating system.
+    /// The filter will be added to the current system, and only the entities that match it will
+    /// be passed to the system.
+    ///
+    /// The `Added` and `Changed` filters match the components added or changed since the last
+    /// time the system ran.
+    ///
+    /// # Arguments
+    ///
+    /// * `F` - The filter to add, for example `Without<Static>` or `Changed<Transform>`.
+    ///
+    /// # Returns
+    ///
+    /// A result containing a mutable reference to this struct.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::entities::{filter::{Changed, Without}, query_entity::QueryEntity};
+    /// use axle_ecs::systems::Systems;
+    /// use axle_ecs::resources::Resources;
+    ///
+    /// let mut systems = Systems::default();
+    /// systems
+    ///     .create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()))
+    ///     .with_component::<u32>().unwrap()
+    ///     .with_filter::<(Changed<u32>, Without<i32>)>().unwrap();
+    /// ```
+    pub fn with_filter<F: QueryFilter>(&mut self) -> Result<&mut Self> {
+        let filters = self
+            .filters
+            .get_mut(self.inserting_into_index)
+            .ok_or(CustomErrors::CreateSystemNeverCalled)?;
+        F::filters(filters);
+
+        Ok(self)
+    }
+
+    /// Sets the stage the creating system runs in. By default systems run in the `Update` stage.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The stage the system runs in.
+    ///
+    /// # Returns
+    ///
+    /// A result containing a mutable reference to this struct.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::entities::query_entity::QueryEntity;
+    /// use axle_ecs::systems::{schedule::Stage, Systems};
+    /// use axle_ecs::resources::Resources;
+    ///
+    /// let mut systems = Systems::default();
+    /// systems
+    ///     .create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()))
+    ///     .in_stage(Stage::Render).unwrap();
+    /// ```
+    pub fn in_stage(&mut self, stage: Stage) -> Result<&mut Self> {
+        self.inserting_schedule()?.stage = stage;
+        Ok(self)
+    }
+
+    /// Names the creating system, so that other systems can be ordered relative to it.
+    ///
+    /// Several systems can share the same name, in which case the ordering constraints apply to
+    /// all of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the system.
+    ///
+    /// # Returns
+    ///
+    /// A result containing a mutable reference to this struct.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::entities::query_entity::QueryEntity;
+    /// use axle_ecs::systems::Systems;
+    /// use axle_ecs::resources::Resources;
+    ///
+    /// let mut systems = Systems::default();
+    /// systems
+    ///     .create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()))
+    ///     .with_name("physics").unwrap();
+    /// ```
+    pub fn with_name(&mut self, name: &'static str) -> Result<&mut Self> {
+        self.inserting_schedule()?.name = Some(name);
+        Ok(self)
+    }
+
+    /// Makes the creating system run before the systems with the given name.
+    ///
+    /// The constraint only applies if both systems run in the same stage.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the systems to run before.
+    ///
+    /// # Returns
+    ///
+    /// A result containing a mutable reference to this struct.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::entities::query_entity::QueryEntity;
+    /// use axle_ecs::systems::Systems;
+    /// use axle_ecs::resources::Resources;
+    ///
+    /// let mut systems = Systems::default();
+    /// systems
+    ///     .create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()))
+    ///     .with_name("physics").unwrap();
+    /// systems
+    ///     .create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()))
+    ///     .before("physics").unwrap();
+    ///
+    /// assert_eq!(systems.execution_order().unwrap(), vec![1, 0]);
+    /// ```
+    pub fn before(&mut self, name: &'static str) -> Result<&mut Self> {
+        self.inserting_schedule()?.before.push(name);
+        Ok(self)
+    }
+
+    /// Makes the creating system run after the systems with the given name.
+    ///
+    /// The constraint only applies if both systems run in the same stage.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the systems to run after.
+    ///
+    /// # Returns
+    ///
+    /// A result containing a mutable reference to this struct.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::entities::query_entity::QueryEntity;
+    /// use axle_ecs::systems::Systems;
+    /// use axle_ecs::resources::Resources;
+    ///
+    /// let mut systems = Systems::default();
+    /// systems
+    ///     .create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()))
+    ///     .after("input").unwrap();
+    /// systems
+    ///     .create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()))
+    ///     .with_name("input").unwrap();
+    ///
+    /// assert_eq!(systems.execution_order().unwrap(), vec![1, 0]);
+    /// ```
+    pub fn after(&mut self, name: &'static str) -> Result<&mut Self> {
+        self.inserting_schedule()?.after.push(name);
+        Ok(self)
+    }
+
+    /// Adds a run condition to the creating system. The system only runs when all its conditions
+    /// are fulfilled.
+    ///
+    /// # Arguments
+    ///
+    /// * `condition` - A function or closure that checks the resources and returns whether the system
+    ///   should run, like the ones returned by `state::in_state`, `state::on_enter` and `state::on_exit`.
+    ///
+    /// # Returns
+    ///
+    /// A result containing a mutable reference to this struct.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::entities::query_entity::QueryEntity;
+    /// use axle_ecs::systems::{schedule::resource_exists, Systems};
+    /// use axle_ecs::resources::Resources;
+    ///
+    /// let mut systems = Systems::default();
+    /// systems
+    ///     .create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()))
+    ///     .run_if(resource_exists::<u32>).unwrap()
+    ///     .run_if(|resources: &Resources| resources.get_ref::<bool>().is_some_and(|paused| !*paused)).unwrap();
+    /// ```
+    pub fn run_if(&mut self, condition: impl Fn(&Resources) -> bool + MaybeSendSync + 'static) -> Result<&mut Self> {
+        let condition: RunCondition = Arc::new(condition);
+        self.inserting_schedule()?.conditions.push(condition);
+        Ok(self)
+    }
+
+    /// Gets the ids of the systems in the order they run.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains the ids of the systems if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If the ordering constraints of the systems form a cycle, an error is returned.
+    pub fn execution_order(&mut self) -> Result<Vec<usize>> {
+        Ok(self.cached_order()?.clone())
+    }
+
     /// Deletes a component from a system by the given system id and component type.
     ///
     /// # Arguments
@@ -118,12 +524,17 @@ impl Systems {
     /// ```
     pub fn delete_component_by_system_id<T: Any>(&mut self, system_id: usize) -> Result<()> {
         let type_id = TypeId::of::<T>();
-        let index = self.components[system_id]
+        let components = self
+            .components
+            .get_mut(system_id)
+            .ok_or(CustomErrors::SystemDoesNotExist)?;
+        let index = components
             .iter()
             .position(|id| *id == type_id)
             .ok_or(CustomErrors::ComponentInSystemDoesNotExist)?;
 
-        self.components[system_id].remove(index);
+        components.remove(index);
+        self.accesses[system_id].components.retain(|component| component.type_id != type_id);
 
         Ok(())
     }
@@ -161,6 +572,7 @@ impl Systems {
             .ok_or(CustomErrors::SystemDoesNotExist)?;
 
         components.push(type_id);
+        self.accesses[system_id].add_component(type_id, true, true);
 
         Ok(())
     }
@@ -202,15 +614,120 @@ impl Systems {
             .get_mut(system_id)
             .ok_or(CustomErrors::SystemDoesNotExist)?;
         components.clear();
+        self.filters[system_id].clear();
+        self.accesses[system_id] = SystemAccess::default();
+        self.order = None;
+
+        Ok(())
+    }
+
+    /// Runs all the systems created, except the ones in the `FixedUpdate` stage.
+    ///
+    /// The systems run stage by stage, respecting their ordering constraints. The systems without
+    /// constraints between them run in the order they were created in. The systems whose run
+    /// conditions aren't fulfilled are skipped.
+    ///
+    /// Consecutive systems that declared their access and don't conflict with each other are run
+    /// as a batch, at the same time if the executor is parallel. Every other system runs alone.
+    ///
+    /// The commands queued by every system in the `Commands` resource are applied right after
+    /// it, or its batch, finishes, so the next systems already see the changes.
+    ///
+    /// The change tick is increased after every system or batch, so that the `Changed` and `Added`
+    /// filters of a system see the changes made by the other systems since it last ran.
+    ///
+    /// There are no non-send resources here, so the systems that borrow one fail. Use `run_frame`
+    /// with the non-send resources of the world to run them.
+    ///
+    /// # Arguments
+    ///
+    /// * `entities` - A refence to the entities.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::entities::{query_entity::QueryEntity, Entities};
+    /// use axle_ecs::systems::Systems;
+    /// use axle_ecs::resources::Resources;
+    ///
+    /// let mut entities = Entities::default();
+    /// let mut resources = Resources::default();
+    ///
+    /// entities.register_component::<u32>();
+    /// entities.register_component::<i32>();
+    ///
+    /// let mut systems = Systems::default();
+    /// systems
+    ///     .create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()))
+    ///     .with_component::<i32>().unwrap();
+    ///
+    /// entities
+    ///     .create_entity()
+    ///     .with_component(100_i32).unwrap()
+    ///     .with_component(100_u32).unwrap();
+    ///
+    /// systems.run_all(&mut entities, &mut resources).unwrap();
+    /// ```
+    pub fn run_all(&mut self, entities: &mut Entities, resources: &mut Resources) -> Result<()> {
+        self.run_frame(0, entities, resources, &mut NonSendResources::default())
+    }
+
+    /// Runs a whole frame: the `PreUpdate` stage, then the `FixedUpdate` stage the given number of
+    /// times, and then the rest of the stages.
+    ///
+    /// The systems run the same way `run_all` runs them. The change tick is also increased after
+    /// every fixed step, so each step only sees the changes made since the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `fixed_steps` - How many times the `FixedUpdate` stage runs.
+    /// * `entities` - A refence to the entities.
+    /// * `resources` - A reference to the resources.
+    /// * `non_send_resources` - A reference to the non-send resources.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    pub fn run_frame(
+        &mut self,
+        fixed_steps: u32,
+        entities: &mut Entities,
+        resources: &mut Resources,
+        non_send_resources: &mut NonSendResources,
+    ) -> Result<()> {
+        entities.start_frame();
+
+        let order = self.cached_order()?.clone();
+        let (pre_update, rest): (Vec<usize>, Vec<usize>) =
+            order.into_iter().partition(|index| self.schedules[*index].stage == Stage::PreUpdate);
+        let (fixed_update, rest): (Vec<usize>, Vec<usize>) =
+            rest.into_iter().partition(|index| self.schedules[*index].stage == Stage::FixedUpdate);
+
+        self.run_ordered(&pre_update, entities, resources, non_send_resources)?;
+
+        for _ in 0..fixed_steps {
+            self.run_ordered(&fixed_update, entities, resources, non_send_resources)?;
+            entities.increment_change_tick();
+        }
 
-        Ok(())
+        self.run_ordered(&rest, entities, resources, non_send_resources)
     }
 
-    /// Runs all the systems created.
+    /// Runs the systems of a single stage, the same way `run_all` runs them.
+    ///
+    /// It is mainly used to run the `FixedUpdate` stage, which `run_all` skips, on its own. To run
+    /// it in the middle of a frame, use `run_frame` instead.
     ///
     /// # Arguments
     ///
+    /// * `stage` - The stage whose systems run.
     /// * `entities` - A refence to the entities.
+    /// * `resources` - A reference to the resources.
+    /// * `non_send_resources` - A reference to the non-send resources.
     ///
     /// # Returns
     ///
@@ -220,55 +737,237 @@ impl Systems {
     ///
     /// ```
     /// use axle_ecs::entities::{query_entity::QueryEntity, Entities};
-    /// use axle_ecs::systems::Systems;
-    /// use axle_ecs::resources::Resources;
+    /// use axle_ecs::systems::{schedule::Stage, Systems};
+    /// use axle_ecs::resources::{NonSendResources, Resources};
     ///
     /// let mut entities = Entities::default();
     /// let mut resources = Resources::default();
-    ///
-    /// entities.register_component::<u32>();
-    /// entities.register_component::<i32>();
+    /// let mut non_send_resources = NonSendResources::default();
+    /// resources.add(0_u32);
     ///
     /// let mut systems = Systems::default();
     /// systems
-    ///     .create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()))
-    ///     .with_component::<i32>().unwrap();
+    ///     .create_system(&|_: &Vec<QueryEntity>, resources: &mut Resources| {
+    ///         *resources.get_mut::<u32>().unwrap() += 1;
+    ///         Ok(())
+    ///     })
+    ///     .in_stage(Stage::FixedUpdate).unwrap();
     ///
-    /// entities
-    ///     .create_entity()
-    ///     .with_component(100_i32).unwrap()
-    ///     .with_component(100_u32).unwrap();
+    /// systems.run_all(&mut entities, &mut resources).unwrap();
+    /// systems.run_stage(Stage::FixedUpdate, &mut entities, &mut resources, &mut non_send_resources).unwrap();
+    /// systems.run_stage(Stage::FixedUpdate, &mut entities, &mut resources, &mut non_send_resources).unwrap();
     ///
-    /// systems.run_all(&entities, &mut resources).unwrap();
+    /// assert_eq!(*resources.get_ref::<u32>().unwrap(), 2);
     /// ```
-    pub fn run_all(&self, entities: &Entities, resources: &mut Resources) -> Result<()> {
-        for index in 0..self.funtions.len() {
-            if let Some(function) = self.funtions[index] {
-                let mut query = Query::new(entities);
+    pub fn run_stage(
+        &mut self,
+        stage: Stage,
+        entities: &mut Entities,
+        resources: &mut Resources,
+        non_send_resources: &mut NonSendResources,
+    ) -> Result<()> {
+        entities.start_frame();
+
+        let mut order = self.cached_order()?.clone();
+        order.retain(|index| self.schedules[*index].stage == stage);
+
+        self.run_ordered(&order, entities, resources, non_send_resources)
+    }
 
-                let components = &self.components[index];
-                components.iter().for_each(|type_id| {
-                    query.with_component_by_type_id(*type_id).unwrap();
-                });
+    /// Runs the given systems in order, batching the ones that can run at the same time.
+    fn run_ordered(
+        &mut self,
+        order: &[usize],
+        entities: &mut Entities,
+        resources: &mut Resources,
+        non_send_resources: &mut NonSendResources,
+    ) -> Result<()> {
+        let mut start = 0;
+
+        while start < order.len() {
+            let batch_len = self.batch_len(&order[start..]);
+            let batch: Vec<usize> = order[start..start + batch_len]
+                .iter()
+                .copied()
+                .filter(|index| self.schedules[*index].should_run(resources))
+                .collect();
+            start += batch_len;
+
+            if batch.is_empty() {
+                continue;
+            }
+
+            let batch_tick = entities.change_tick();
+
+            if self.accesses[batch[0]].runs_in_batches() {
+                self.run_batch(&batch, entities, resources)?;
+            } else {
+                self.run_exclusive(batch[0], entities, resources, non_send_resources)?;
+            }
 
-                (function)(&query.run_entity(), resources)?;
+            // Next time, the systems only see the changes made after their own ones. The commands
+            // of a batch are applied one tick later, so the whole batch sees them
+            for index in &batch {
+                self.since_ticks[*index] = batch_tick + 1;
             }
+            entities.increment_change_tick();
         }
 
         Ok(())
     }
+
+    /// Runs a system that didn't declare its access, or borrows non-send resources, alone on the
+    /// current thread, with every resource of the world.
+    fn run_exclusive(
+        &mut self,
+        index: usize,
+        entities: &mut Entities,
+        resources: &mut Resources,
+        non_send_resources: &mut NonSendResources,
+    ) -> Result<()> {
+        let query = executor::system_query(entities, &self.components[index], &self.filters[index], self.since_ticks[index])?;
+
+        if let Some(function) = self.funtions[index].as_mut() {
+            let entities = executor::system_entities(&query, &self.accesses[index]);
+            function.run_with_non_send(&entities, resources, non_send_resources)?;
+        }
+
+        // Apply the structural changes queued by the system
+        if let Some(commands) = resources.get_mut::<Commands>() {
+            commands.apply(entities)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs a batch of systems that declared their access and don't conflict with each other.
+    ///
+    /// Every system is lent the resources it declared and gets its own commands buffer. Once
+    /// the batch finishes, the resources are given back and the commands are applied in the
+    /// execution order of the systems, up to the first system that failed.
+    ///
+    /// The change tick is increased before applying the commands, so that the `Added` and
+    /// `Changed` filters of the systems of the batch see the changes they made.
+    fn run_batch(&mut self, batch: &[usize], entities: &mut Entities, resources: &mut Resources) -> Result<()> {
+        let mut functions: Vec<Option<&mut SystemFunction>> = self.funtions
+            .iter_mut()
+            .map(Option::as_mut)
+            .collect();
+
+        let mut systems: Vec<BatchSystem> = batch
+            .iter()
+            .map(|index| BatchSystem {
+                function: functions[*index].take().unwrap(),
+                components: &self.components[*index],
+                filters: &self.filters[*index],
+                access: &self.accesses[*index],
+                since_tick: self.since_ticks[*index],
+                resources: executor::lend_resources(&self.accesses[*index], resources, entities),
+            })
+            .collect();
+
+        let results = executor::run_batch(self.executor, &mut systems, entities);
+
+        // The systems of the batch didn't see the commands of the others yet
+        entities.increment_change_tick();
+
+        let mut result = Ok(());
+
+        for (system, system_result) in systems.into_iter().zip(results) {
+            let mut lent = system.resources;
+
+            if result.is_ok() {
+                result = system_result;
+
+                if let (Ok(()), Some(commands)) = (&result, lent.get_mut::<Commands>()) {
+                    result = commands.apply(entities);
+                }
+            }
+
+            lent.remove::<Commands>();
+            resources.extend(lent);
+        }
+
+        result
+    }
+
+    /// Counts how many systems at the start of the given order can run as a single batch.
+    ///
+    /// A batch is made of consecutive systems of the same stage that declared their access,
+    /// don't conflict with each other and have no ordering constraints between them.
+    fn batch_len(&self, order: &[usize]) -> usize {
+        let first = order[0];
+        let mut len = 1;
+
+        if !self.accesses[first].runs_in_batches() {
+            return len;
+        }
+
+        for index in &order[1..] {
+            let joins = self.schedules[*index].stage == self.schedules[first].stage
+                && order[..len].iter().all(|other| {
+                    !self.accesses[*other].conflicts_with(&self.accesses[*index])
+                        && !self.schedules[*other].is_ordered_with(&self.schedules[*index])
+                });
+
+            if !joins {
+                break;
+            }
+
+            len += 1;
+        }
+
+        len
+    }
+
+    /// Declares that the creating system accesses the resource with the given type id.
+    fn declare_resource(&mut self, type_id: TypeId, mutable: bool) -> Result<&mut Self> {
+        let access = self
+            .accesses
+            .get_mut(self.inserting_into_index)
+            .ok_or(CustomErrors::CreateSystemNeverCalled)?;
+
+        access.resources.push(ResourceAccess { type_id, mutable });
+        access.declared = true;
+
+        Ok(self)
+    }
+
+    /// Gets the schedule of the creating system and invalidates the execution order.
+    fn inserting_schedule(&mut self) -> Result<&mut SystemSchedule> {
+        self.order = None;
+
+        self.schedules
+            .get_mut(self.inserting_into_index)
+            .ok_or_else(|| CustomErrors::CreateSystemNeverCalled.into())
+    }
+
+    /// Gets the ids of the systems in the order they run, computing it if the schedules changed.
+    fn cached_order(&mut self) -> Result<&Vec<usize>> {
+        if self.order.is_none() {
+            let systems: Vec<(usize, &SystemSchedule)> = self.schedules
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| self.funtions[*index].is_some())
+                .collect();
+
+            self.order = Some(schedule::execution_order(&systems)?);
+        }
+
+        Ok(self.order.as_ref().unwrap())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::entities::{query_entity::QueryEntity, Entities};
+    use crate::entities::{filter::{Added, Changed, Without}, query::Query, query_entity::QueryEntity, typed_query::TypedQuery, Entities};
     use crate::resources::Resources;
 
     #[test]
     fn create_system() {
         let mut systems = Systems::default();
-        systems.create_system(&damage_health);
+        systems.create_system(damage_health);
 
         assert_eq!(systems.funtions.len(), 1);
         assert_eq!(systems.components.len(), 1);
@@ -278,7 +977,7 @@ mod tests {
     fn create_system_with_components() -> Result<()> {
         let mut systems = Systems::default();
         systems
-            .create_system(&damage_health)
+            .create_system(damage_health)
             .with_component::<Health>()?
             .with_component::<Speed>()?;
 
@@ -301,7 +1000,7 @@ mod tests {
 
         let mut systems = Systems::default();
         systems
-            .create_system(&damage_health)
+            .create_system(damage_health)
             .with_component::<Health>()?;
 
         entities
@@ -309,7 +1008,7 @@ mod tests {
             .with_component(Health(100))?
             .with_component(Speed(100))?;
 
-        systems.run_all(&entities, &mut resources)?;
+        systems.run_all(&mut entities, &mut resources)?;
 
         let mut query = Query::new(&entities);
         let query_result = query.with_component::<Health>()?.run();
@@ -333,11 +1032,11 @@ mod tests {
 
         let mut systems = Systems::default();
         systems
-            .create_system(&damage_health)
+            .create_system(damage_health)
             .with_component::<Health>()?;
 
         systems
-            .create_system(&increase_speed)
+            .create_system(increase_speed)
             .with_component::<Speed>()?;
 
         entities
@@ -351,7 +1050,7 @@ mod tests {
 
         entities.create_entity().with_component(Health(300))?;
 
-        systems.run_all(&entities, &mut resources)?;
+        systems.run_all(&mut entities, &mut resources)?;
 
         // See health stats
         let mut query = Query::new(&entities);
@@ -392,7 +1091,7 @@ mod tests {
     fn delete_component_by_system_id() -> Result<()> {
         let mut systems = Systems::default();
         systems
-            .create_system(&damage_health)
+            .create_system(damage_health)
             .with_component::<Speed>()?
             .with_component::<Health>()?;
 
@@ -409,7 +1108,7 @@ mod tests {
     fn add_component_by_entity_id() -> Result<()> {
         let mut systems = Systems::default();
         systems
-            .create_system(&damage_health)
+            .create_system(damage_health)
             .with_component::<Health>()?;
 
         systems.add_component_by_system_id::<Speed>(0)?;
@@ -425,7 +1124,7 @@ mod tests {
     fn delete_system_by_id() -> Result<()> {
         let mut systems = Systems::default();
         systems
-            .create_system(&damage_health)
+            .create_system(damage_health)
             .with_component::<Health>()?;
 
         systems.delete_system_by_id(0)?;
@@ -441,22 +1140,22 @@ mod tests {
     fn created_systems_are_inserted_into_deleted_systems_columns() -> Result<()> {
         let mut systems = Systems::default();
         systems
-            .create_system(&damage_health)
+            .create_system(damage_health)
             .with_component::<Health>()?;
 
         systems
-            .create_system(&increase_speed)
+            .create_system(increase_speed)
             .with_component::<Speed>()?;
 
         systems.delete_system_by_id(0)?;
 
         systems
-            .create_system(&both)
+            .create_system(both)
             .with_component::<Health>()?
             .with_component::<Speed>()?;
 
         systems
-            .create_system(&damage_health)
+            .create_system(damage_health)
             .with_component::<Health>()?;
 
         assert_eq!(systems.funtions.len(), 3);
@@ -477,11 +1176,11 @@ mod tests {
     fn should_happen_nothing_after_deleting_multiple_times_the_same_id() -> Result<()> {
         let mut systems = Systems::default();
         systems
-            .create_system(&damage_health)
+            .create_system(damage_health)
             .with_component::<Health>()?;
 
         systems
-            .create_system(&increase_speed)
+            .create_system(increase_speed)
             .with_component::<Speed>()?;
 
         systems.delete_system_by_id(0)?;
@@ -489,12 +1188,12 @@ mod tests {
         systems.delete_system_by_id(0)?;
 
         systems
-            .create_system(&both)
+            .create_system(both)
             .with_component::<Health>()?
             .with_component::<Speed>()?;
 
         systems
-            .create_system(&damage_health)
+            .create_system(damage_health)
             .with_component::<Health>()?;
 
         assert_eq!(systems.funtions.len(), 3);
@@ -511,7 +1210,431 @@ mod tests {
         Ok(())
     }
 
-    fn damage_health(entities: &Vec<QueryEntity>, resources: &mut Resources) -> Result<()> {
+    #[test]
+    fn filtered_systems() -> Result<()> {
+        let mut entities = Entities::default();
+        let mut resources = Resources::default();
+
+        entities.register_component::<Health>();
+        entities.register_component::<Speed>();
+
+        let mut systems = Systems::default();
+        systems
+            .create_system(damage_health)
+            .with_component::<Health>()?
+            .with_filter::<Without<Speed>>()?;
+        systems
+            .create_system(increase_speed)
+            .with_component::<Speed>()?
+            .with_filter::<Changed<Health>>()?;
+
+        let moving = entities
+            .create_entity()
+            .with_component(Health(100))?
+            .with_component(Speed(0))?
+            .entity();
+        let still = entities
+            .create_entity()
+            .with_component(Health(100))?
+            .entity();
+
+        // Every component is new the first time the systems run
+        systems.run_all(&mut entities, &mut resources)?;
+        systems.run_all(&mut entities, &mut resources)?;
+
+        {
+            let query = TypedQuery::<(&Health, Option<&Speed>)>::new(&entities)?;
+            let (health, speed) = query.get(moving).unwrap();
+            assert_eq!((health.0, speed.unwrap().0), (100, 10));
+            let (health, _) = query.get(still).unwrap();
+            assert_eq!(health.0, 80);
+        }
+
+        entities.add_component_by_entity_id(Health(50), moving)?;
+        systems.run_all(&mut entities, &mut resources)?;
+
+        let query = TypedQuery::<&Speed>::new(&entities)?;
+        assert_eq!(query.get(moving).unwrap().0, 20);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reused_slots_keep_the_creation_order() -> Result<()> {
+        let mut systems = Systems::default();
+        systems.create_system(damage_health);
+        systems.create_system(increase_speed);
+
+        systems.delete_system_by_id(0)?;
+        systems.create_system(both);
+
+        assert_eq!(systems.execution_order()?, vec![1, 0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn systems_with_unfulfilled_conditions_are_skipped() -> Result<()> {
+        let mut entities = Entities::default();
+        let mut resources = Resources::default();
+
+        entities.register_component::<Health>();
+        entities.create_entity().with_component(Health(100))?;
+
+        let mut systems = Systems::default();
+        systems
+            .create_system(damage_health)
+            .with_component::<Health>()?
+            .run_if(schedule::resource_exists::<Speed>)?;
+
+        systems.run_all(&mut entities, &mut resources)?;
+        assert_eq!(TypedQuery::<&Health>::new(&entities)?.iter().next().unwrap().0, 100);
+
+        resources.add(Speed(0));
+        systems.run_all(&mut entities, &mut resources)?;
+        assert_eq!(TypedQuery::<&Health>::new(&entities)?.iter().next().unwrap().0, 90);
+
+        Ok(())
+    }
+
+    #[test]
+    fn systems_with_unregistered_components_see_no_entities() -> Result<()> {
+        let mut entities = Entities::default();
+        let mut resources = Resources::default();
+        entities.create_entity().with_component(Health(100))?;
+
+        let mut systems = Systems::default();
+        systems
+            .create_system(|entities: &Vec<QueryEntity>, _: &mut Resources| {
+                assert!(entities.is_empty());
+                Ok(())
+            })
+            .with_component::<Health>()?
+            .with_component::<Speed>()?;
+
+        systems.run_all(&mut entities, &mut resources)?;
+
+        assert!(systems.delete_component_by_system_id::<Health>(5).is_err());
+        assert!(systems.add_component_by_system_id::<Health>(5).is_err());
+        assert!(systems.delete_system_by_id(5).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn ordering_cycles_are_reported() -> Result<()> {
+        let mut entities = Entities::default();
+        let mut resources = Resources::default();
+
+        let mut systems = Systems::default();
+        systems
+            .create_system(damage_health)
+            .with_name("damage")?
+            .after("speed")?;
+        systems
+            .create_system(increase_speed)
+            .with_name("speed")?
+            .after("damage")?;
+
+        assert!(systems.run_all(&mut entities, &mut resources).is_err());
+
+        systems.delete_system_by_id(1)?;
+        assert!(systems.run_all(&mut entities, &mut resources).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn fixed_steps_run_between_pre_update_and_update() -> Result<()> {
+        let mut entities = Entities::default();
+        let mut resources = Resources::default();
+        resources.add(Vec::<Stage>::new());
+
+        let mut systems = Systems::default();
+        for stage in [Stage::Render, Stage::Update, Stage::FixedUpdate, Stage::PreUpdate] {
+            systems
+                .create_system(move |_: &Vec<QueryEntity>, resources: &mut Resources| {
+                    resources.get_mut::<Vec<Stage>>().unwrap().push(stage);
+                    Ok(())
+                })
+                .in_stage(stage)?;
+        }
+
+        let tick = entities.change_tick();
+        systems.run_frame(2, &mut entities, &mut resources, &mut NonSendResources::default())?;
+
+        assert_eq!(
+            *resources.get_ref::<Vec<Stage>>().unwrap(),
+            vec![Stage::PreUpdate, Stage::FixedUpdate, Stage::FixedUpdate, Stage::Update, Stage::Render]
+        );
+        // Every fixed step advances the change tick, on top of the one of every system
+        assert_eq!(entities.change_tick(), tick + 7);
+
+        Ok(())
+    }
+
+    #[test]
+    fn systems_keep_their_own_state() -> Result<()> {
+        let mut entities = Entities::default();
+        let mut resources = Resources::default();
+
+        entities.register_component::<Health>();
+        entities.create_entity().with_component(Health(100))?;
+
+        let mut systems = Systems::default();
+        systems
+            .create_system(Regeneration { amount: 1 })
+            .with_component::<Health>()?;
+
+        let mut runs = 0;
+        systems.create_system(move |_: &Vec<QueryEntity>, resources: &mut Resources| {
+            runs += 1;
+            resources.add(Speed(runs));
+            Ok(())
+        });
+
+        systems.run_all(&mut entities, &mut resources)?;
+        systems.run_all(&mut entities, &mut resources)?;
+        systems.run_all(&mut entities, &mut resources)?;
+
+        // Every run regenerates one more point than the previous one
+        assert_eq!(TypedQuery::<&Health>::new(&entities)?.iter().next().unwrap().0, 106);
+        assert_eq!(resources.get_ref::<Speed>().unwrap().0, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn declared_systems_run_in_batches() -> Result<()> {
+        let mut systems = Systems::default();
+        systems
+            .create_system(damage_health)
+            .with_access::<&mut Health>()?;
+        systems
+            .create_system(increase_speed)
+            .with_access::<&Speed>()?;
+        systems
+            .create_system(increase_speed)
+            .with_access::<&mut Speed>()?;
+        systems
+            .create_system(damage_health)
+            .with_access::<&Health>()?
+            .with_resource::<Gravity>()?;
+        systems
+            .create_system(damage_health)
+            .with_access::<&Health>()?
+            .with_resource_mut::<Gravity>()?;
+        systems
+            .create_system(increase_speed)
+            .with_component::<Speed>()?;
+        systems
+            .create_system(damage_health)
+            .with_access::<&Health>()?
+            .with_name("first")?;
+        systems
+            .create_system(damage_health)
+            .with_access::<&Health>()?
+            .after("first")?;
+
+        let order = systems.execution_order()?;
+        let mut batches = vec![];
+        let mut start = 0;
+
+        while start < order.len() {
+            let len = systems.batch_len(&order[start..]);
+            batches.push(order[start..start + len].to_vec());
+            start += len;
+        }
+
+        // Conflicting components or resources, undeclared access and ordering constraints split
+        // the batches
+        assert_eq!(batches, vec![vec![0, 1], vec![2, 3], vec![4], vec![5], vec![6], vec![7]]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn batches_lend_the_declared_resources() -> Result<()> {
+        let mut entities = Entities::default();
+        let mut resources = Resources::default();
+        resources.add(Gravity(10));
+        resources.add(Speed(0));
+
+        entities.register_component::<Health>();
+        entities.register_component::<Speed>();
+        entities.create_entity().with_component(Health(100))?;
+
+        let mut systems = Systems::default();
+        systems.set_executor(Executor::SingleThreaded);
+        systems
+            .create_system(|entities: &Vec<QueryEntity>, resources: &mut Resources| {
+                let gravity = resources.get_ref::<Gravity>().unwrap().0;
+                for entity in entities {
+                    entity.get_component_mut::<Health>()?.0 -= gravity;
+                }
+
+                // Only the declared resources are lent to the system
+                assert!(resources.get_ref::<Speed>().is_none());
+                resources
+                    .get_mut::<Commands>()
+                    .unwrap()
+                    .create_entity()
+                    .with_component(Speed(5))?;
+
+                Ok(())
+            })
+            .with_access::<&mut Health>()?
+            .with_resource::<Gravity>()?;
+        systems
+            .create_system(|entities: &Vec<QueryEntity>, _resources: &mut Resources| {
+                // The commands of the batch are applied once the whole batch finishes
+                assert!(entities.is_empty());
+                Ok(())
+            })
+            .with_access::<&Speed>()?;
+
+        systems.run_all(&mut entities, &mut resources)?;
+
+        let order = systems.execution_order()?;
+        assert_eq!(systems.batch_len(&order), 2);
+        assert_eq!(TypedQuery::<&Health>::new(&entities)?.iter().next().unwrap().0, 90);
+        assert_eq!(TypedQuery::<&Speed>::new(&entities)?.count(), 1);
+        assert_eq!(resources.get_ref::<Gravity>().unwrap().0, 10);
+        assert!(resources.get_ref::<Speed>().is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn batches_see_the_entities_spawned_by_their_commands() -> Result<()> {
+        let mut entities = Entities::default();
+        let mut resources = Resources::default();
+        resources.add(Gravity(0));
+        entities.register_component::<Health>();
+
+        let mut systems = Systems::default();
+        let mut spawned = false;
+        systems
+            .create_system(move |_: &Vec<QueryEntity>, resources: &mut Resources| {
+                if !spawned {
+                    resources.get_mut::<Commands>().unwrap().create_entity().with_component(Health(100))?;
+                    spawned = true;
+                }
+                Ok(())
+            })
+            .with_access::<&Speed>()?;
+        systems
+            .create_system(|entities: &Vec<QueryEntity>, resources: &mut Resources| {
+                resources.get_mut::<Gravity>().unwrap().0 += entities.len() as u32;
+                Ok(())
+            })
+            .with_access::<&Health>()?
+            .with_filter::<Added<Health>>()?
+            .with_resource_mut::<Gravity>()?;
+
+        let order = systems.execution_order()?;
+        assert_eq!(systems.batch_len(&order), 2);
+
+        for _ in 0..3 {
+            systems.run_all(&mut entities, &mut resources)?;
+        }
+
+        // The entity is seen as added exactly once, on the frame after it was spawned
+        assert_eq!(resources.get_ref::<Gravity>().unwrap().0, 1);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn batched_systems_only_access_their_declared_components() -> Result<()> {
+        let mut entities = Entities::default();
+        let mut resources = Resources::default();
+        entities.create_entity().with_component(Health(100))?.with_component(Speed(1))?;
+
+        let mut systems = Systems::default();
+        systems
+            .create_system(|entities: &Vec<QueryEntity>, _: &mut Resources| {
+                for entity in entities {
+                    let mut health = entity.get_component_mut::<Health>()?;
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                    health.0 -= 10;
+                }
+                Ok(())
+            })
+            .with_access::<&mut Health>()?;
+        systems
+            .create_system(|entities: &Vec<QueryEntity>, _: &mut Resources| {
+                for entity in entities {
+                    assert_eq!(entity.get_components::<&Speed>()?.0, 1);
+
+                    // Neither the components of the other system nor writing its own read only one
+                    let error = entity.get_component::<Health>().err().unwrap();
+                    assert!(matches!(error.downcast_ref(), Some(CustomErrors::UndeclaredComponentAccess)));
+                    assert!(entity.get_component_mut::<Speed>().is_err());
+                    assert!(entity.get_components::<(&Speed, &Health)>().is_err());
+                }
+                Ok(())
+            })
+            .with_access::<&Speed>()?;
+
+        let order = systems.execution_order()?;
+        assert_eq!(systems.batch_len(&order), 2);
+
+        systems.run_all(&mut entities, &mut resources)?;
+        assert_eq!(TypedQuery::<&Health>::new(&entities)?.iter().next().unwrap().0, 90);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn batches_run_on_several_threads() -> Result<()> {
+        use std::thread::{self, ThreadId};
+
+        let mut entities = Entities::default();
+        let mut resources = Resources::default();
+
+        let mut systems = Systems::default();
+        systems
+            .create_system(|_: &Vec<QueryEntity>, resources: &mut Resources| {
+                resources.add(thread::current().id());
+                Ok(())
+            })
+            .with_resource_mut::<ThreadId>()?;
+        systems
+            .create_system(|_: &Vec<QueryEntity>, resources: &mut Resources| {
+                resources.add(Some(thread::current().id()));
+                Ok(())
+            })
+            .with_resource_mut::<Option<ThreadId>>()?;
+
+        systems.run_all(&mut entities, &mut resources)?;
+
+        let first = *resources.get_ref::<ThreadId>().unwrap();
+        let second = resources.get_ref::<Option<ThreadId>>().unwrap().unwrap();
+        assert_ne!(first, second);
+
+        Ok(())
+    }
+
+    struct Regeneration {
+        amount: u32,
+    }
+
+    impl System for Regeneration {
+        fn run(&mut self, entities: &Vec<QueryEntity>, _resources: &mut Resources) -> Result<()> {
+            for entity in entities {
+                entity.get_component_mut::<Health>()?.0 += self.amount;
+            }
+
+            self.amount += 1;
+
+            Ok(())
+        }
+    }
+
+    fn damage_health(entities: &Vec<QueryEntity>, _resources: &mut Resources) -> Result<()> {
         for entity in entities {
             let mut health_mut = entity.get_component_mut::<Health>()?;
             health_mut.0 -= 10;
@@ -520,7 +1643,7 @@ mod tests {
         Ok(())
     }
 
-    fn increase_speed(entities: &Vec<QueryEntity>, resources: &mut Resources) -> Result<()> {
+    fn increase_speed(entities: &Vec<QueryEntity>, _resources: &mut Resources) -> Result<()> {
         for entity in entities {
             let mut speed_mut = entity.get_component_mut::<Speed>()?;
             speed_mut.0 += 10;
@@ -529,10 +1652,9 @@ mod tests {
         Ok(())
     }
 
-    fn both(entities: &Vec<QueryEntity>, resources: &mut Resources) -> Result<()> {
+    fn both(entities: &Vec<QueryEntity>, _resources: &mut Resources) -> Result<()> {
         for entity in entities {
-            let mut health_mut = entity.get_component_mut::<Health>()?;
-            let mut speed_mut = entity.get_component_mut::<Speed>()?;
+            let (mut health_mut, mut speed_mut) = entity.get_components::<(&mut Health, &mut Speed)>()?;
 
             health_mut.0 -= 10;
             speed_mut.0 += 10;
@@ -543,5 +1665,5 @@ mod tests {
 
     struct Health(pub u32);
     struct Speed(pub u32);
-    struct Score(pub u32);
+    struct Gravity(pub u32);
 }
@@ -0,0 +1,123 @@
+use std::any::TypeId;
+
+use crate::commands::Commands;
+use crate::entities::typed_query::ComponentAccess;
+
+/// Describes how a system accesses a resource type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceAccess {
+    /// The type id of the resource.
+    pub type_id: TypeId,
+    /// Whether the resource is accessed mutably or not.
+    pub mutable: bool,
+}
+
+/// The components and resources a system reads and writes.
+///
+/// Systems that declared their access can run at the same time as the other systems they don't
+/// conflict with. Systems that didn't declare it always run alone, with every resource of the
+/// world.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SystemAccess {
+    /// Whether the system declared its access or not.
+    pub declared: bool,
+    /// The components the system accesses.
+    pub components: Vec<ComponentAccess>,
+    /// The resources the system accesses.
+    pub resources: Vec<ResourceAccess>,
+    /// Whether the system borrows non-send resources, so it must run alone on the thread that
+    /// runs the world.
+    pub main_thread: bool,
+}
+
+impl SystemAccess {
+    /// Checks if this system can't run at the same time as the other one.
+    ///
+    /// Two systems conflict if one of them didn't declare its access or must run on the main
+    /// thread, if one of them writes a component the other one accesses, or if both access the
+    /// same resource.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The access of the other system.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the systems conflict, `false` otherwise.
+    pub fn conflicts_with(&self, other: &SystemAccess) -> bool {
+        if !self.runs_in_batches() || !other.runs_in_batches() {
+            return true;
+        }
+
+        let components = self.components.iter().any(|component| {
+            other.components.iter().any(|other_component| {
+                component.type_id == other_component.type_id && (component.mutable || other_component.mutable)
+            })
+        });
+
+        // The resources are lent to the systems, so even reading the same one is a conflict. The
+        // commands are the exception, as every system gets its own buffer
+        let resources = self.resources.iter().any(|resource| {
+            resource.type_id != TypeId::of::<Commands>()
+                && other.resources.iter().any(|other_resource| resource.type_id == other_resource.type_id)
+        });
+
+        components || resources
+    }
+
+    /// Checks if the system can run in a batch, with the resources it declared lent to it.
+    pub fn runs_in_batches(&self) -> bool {
+        self.declared && !self.main_thread
+    }
+
+    /// Records a component access, required or not.
+    pub fn add_component(&mut self, type_id: TypeId, mutable: bool, required: bool) {
+        self.components.push(ComponentAccess { type_id, mutable, required });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undeclared_access_always_conflicts() {
+        let undeclared = SystemAccess::default();
+        let declared = SystemAccess { declared: true, ..Default::default() };
+
+        assert!(undeclared.conflicts_with(&declared));
+        assert!(declared.conflicts_with(&undeclared));
+        assert!(!declared.conflicts_with(&declared));
+
+        let main_thread = SystemAccess { declared: true, main_thread: true, ..Default::default() };
+        assert!(main_thread.conflicts_with(&declared));
+        assert!(declared.conflicts_with(&main_thread));
+    }
+
+    #[test]
+    fn component_and_resource_conflicts() {
+        let mut reads_health = SystemAccess { declared: true, ..Default::default() };
+        reads_health.add_component(TypeId::of::<Health>(), false, true);
+
+        let mut also_reads_health = reads_health.clone();
+        also_reads_health.add_component(TypeId::of::<Speed>(), true, true);
+        assert!(!reads_health.conflicts_with(&also_reads_health));
+
+        let mut writes_health = SystemAccess { declared: true, ..Default::default() };
+        writes_health.add_component(TypeId::of::<Health>(), true, false);
+        assert!(reads_health.conflicts_with(&writes_health));
+        assert!(writes_health.conflicts_with(&reads_health));
+
+        reads_health.resources.push(ResourceAccess { type_id: TypeId::of::<Gravity>(), mutable: false });
+        also_reads_health.resources.push(ResourceAccess { type_id: TypeId::of::<Gravity>(), mutable: false });
+        assert!(reads_health.conflicts_with(&also_reads_health));
+
+        let mut writes_commands = SystemAccess { declared: true, ..Default::default() };
+        writes_commands.resources.push(ResourceAccess { type_id: TypeId::of::<Commands>(), mutable: true });
+        assert!(!writes_commands.conflicts_with(&writes_commands.clone()));
+    }
+
+    struct Health;
+    struct Speed;
+    struct Gravity;
+}
@@ -0,0 +1,172 @@
+use std::any::TypeId;
+use eyre::Result;
+
+use crate::commands::Commands;
+use crate::entities::{filter::Filter, query::Query, query_entity::QueryEntity, Entities};
+use crate::resources::Resources;
+use super::{access::SystemAccess, SystemComponents, SystemFunction};
+
+/// How the systems that don't conflict with each other are run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Executor {
+    /// Runs the systems one after another on the calling thread, in their execution order.
+    ///
+    /// The results don't depend on the scheduling of the threads, which makes it useful for tests.
+    SingleThreaded,
+    /// Runs the systems that don't conflict with each other at the same time, each on its own
+    /// thread.
+    #[cfg(feature = "parallel")]
+    Parallel,
+}
+
+impl Default for Executor {
+    /// The parallel executor if the `parallel` feature is enabled, the single threaded one otherwise.
+    fn default() -> Self {
+        #[cfg(feature = "parallel")]
+        return Executor::Parallel;
+
+        #[cfg(not(feature = "parallel"))]
+        return Executor::SingleThreaded;
+    }
+}
+
+/// A system ready to run as part of a batch of systems that don't conflict with each other.
+pub(crate) struct BatchSystem<'a> {
+    /// The system itself.
+    pub function: &'a mut SystemFunction,
+    /// The components the entities passed to the system must have.
+    pub components: &'a SystemComponents,
+    /// The filters the entities passed to the system must match.
+    pub filters: &'a [Filter],
+    /// The components and resources the system declared.
+    pub access: &'a SystemAccess,
+    /// The tick from which the system considers the components added or changed.
+    pub since_tick: u64,
+    /// The resources lent to the system: the ones it declared and its own commands buffer.
+    pub resources: Resources,
+}
+
+impl BatchSystem<'_> {
+    /// Runs the system with the resources lent to it.
+    fn run(&mut self, entities: &Entities) -> Result<()> {
+        let query = system_query(entities, self.components, self.filters, self.since_tick)?;
+        self.function.run(&system_entities(&query, self.access), &mut self.resources)
+    }
+}
+
+/// Builds the query of the entities passed to a system.
+///
+/// # Arguments
+///
+/// * `entities` - The entities to query.
+/// * `components` - The components the entities must have.
+/// * `filters` - The filters the entities must match.
+/// * `since_tick` - The tick from which the components are considered added or changed.
+///
+/// # Returns
+///
+/// A result that contains the query if succeeds or an error if it fails.
+pub(crate) fn system_query<'a>(
+    entities: &'a Entities,
+    components: &SystemComponents,
+    filters: &[Filter],
+    since_tick: u64,
+) -> Result<Query<'a>> {
+    let mut query = Query::new(entities);
+    query.changed_since(since_tick);
+
+    for type_id in components {
+        query.with_component_by_type_id(*type_id)?;
+    }
+
+    for filter in filters {
+        query.with_filter(filter.clone())?;
+    }
+
+    Ok(query)
+}
+
+/// Gets the entities of a system's query, restricted to the components the system declared if it
+/// declared its access.
+///
+/// # Arguments
+///
+/// * `query` - The query of the system.
+/// * `access` - The access of the system.
+///
+/// # Returns
+///
+/// The entities passed to the system.
+pub(crate) fn system_entities<'a>(query: &Query<'a>, access: &'a SystemAccess) -> Vec<QueryEntity<'a>> {
+    let entities = query.run_entity();
+
+    if !access.declared {
+        return entities;
+    }
+
+    entities.into_iter().map(|entity| entity.with_access(&access.components)).collect()
+}
+
+/// Moves the resources declared by a system out of the resources of the world, so that they can
+/// be lent to it while it runs alongside other systems.
+///
+/// The system also gets its own `Commands` buffer, applied once the whole batch finishes.
+///
+/// # Arguments
+///
+/// * `access` - The access declared by the system.
+/// * `resources` - The resources of the world.
+/// * `entities` - The entities the system's commands reserve the handles of new entities from.
+///
+/// # Returns
+///
+/// The resources lent to the system.
+pub(crate) fn lend_resources(access: &SystemAccess, resources: &mut Resources, entities: &Entities) -> Resources {
+    let mut lent = Resources::default();
+
+    for resource in &access.resources {
+        if resource.type_id == TypeId::of::<Commands>() {
+            continue;
+        }
+
+        if let Some(data) = resources.take_by_type_id(&resource.type_id) {
+            lent.insert_by_type_id(resource.type_id, data);
+        }
+    }
+
+    lent.add(Commands::new(entities));
+
+    lent
+}
+
+/// Runs a batch of systems that don't conflict with each other.
+///
+/// # Arguments
+///
+/// * `executor` - How to run the systems.
+/// * `systems` - The systems of the batch, in their execution order.
+/// * `entities` - The entities of the world.
+///
+/// # Returns
+///
+/// The result of every system, in the same order as the systems.
+pub(crate) fn run_batch(executor: Executor, systems: &mut [BatchSystem], entities: &Entities) -> Vec<Result<()>> {
+    match executor {
+        Executor::SingleThreaded => systems
+            .iter_mut()
+            .map(|system| system.run(entities))
+            .collect(),
+        #[cfg(feature = "parallel")]
+        Executor::Parallel => std::thread::scope(|scope| {
+            let handles: Vec<_> = systems
+                .iter_mut()
+                .map(|system| scope.spawn(move || system.run(entities)))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+                .collect()
+        }),
+    }
+}
@@ -0,0 +1,455 @@
+use std::{any::{Any, TypeId}, marker::PhantomData, ops::{Deref, DerefMut}};
+use eyre::Result;
+
+use crate::prelude::*;
+use crate::resources::{NonSendResources, Resources};
+use crate::sync::{AnyBox, MaybeSend};
+use super::{access::ResourceAccess, system::System};
+
+/// A resource read by a system, declared as a parameter of its function.
+///
+/// The resource must exist when the system runs, otherwise the system fails with
+/// `CustomErrors::ResourceInSystemDoesNotExist` without running.
+///
+/// # Example
+///
+/// ```
+/// use eyre::Result;
+/// use axle_ecs::{entities::query_entity::QueryEntity, systems::param::{Res, ResMut}, World};
+///
+/// struct Gravity(f32);
+/// struct Time(f32);
+///
+/// fn fall(_: &Vec<QueryEntity>, gravity: Res<Gravity>, mut time: ResMut<Time>) -> Result<()> {
+///     time.0 += gravity.0;
+///     Ok(())
+/// }
+///
+/// let mut world = World::new();
+/// world.add_resource(Gravity(9.8));
+/// world.add_resource(Time(0.0));
+/// world.create_system(fall);
+///
+/// world.run_all_systems().unwrap();
+///
+/// assert_eq!(world.get_resource::<Time>().unwrap().0, 9.8);
+/// ```
+pub struct Res<'r, T> {
+    value: &'r T,
+}
+
+impl<T> Deref for Res<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+/// A resource written by a system, declared as a parameter of its function.
+///
+/// The resource must exist when the system runs, otherwise the system fails with
+/// `CustomErrors::ResourceInSystemDoesNotExist` without running.
+pub struct ResMut<'r, T> {
+    value: &'r mut T,
+}
+
+impl<T> Deref for ResMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<T> DerefMut for ResMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.value
+    }
+}
+
+/// A non-send resource read by a system, declared as a parameter of its function.
+///
+/// Non-send resources must stay on the thread that created them, so the systems that borrow one
+/// never run in a batch: they always run alone, on the thread that runs the world. The resource
+/// must exist when the system runs, otherwise the system fails with
+/// `CustomErrors::ResourceInSystemDoesNotExist` without running.
+///
+/// # Example
+///
+/// ```
+/// use std::rc::Rc;
+/// use eyre::Result;
+/// use axle_ecs::{entities::query_entity::QueryEntity, systems::param::{NonSend, ResMut}, World};
+///
+/// struct Title(String);
+///
+/// fn read_window(_: &Vec<QueryEntity>, window: NonSend<Rc<String>>, mut title: ResMut<Title>) -> Result<()> {
+///     title.0 = window.to_string();
+///     Ok(())
+/// }
+///
+/// let mut world = World::new();
+/// world.add_non_send_resource(Rc::new(String::from("Axle")));
+/// world.add_resource(Title(String::new()));
+/// world.create_system(read_window);
+///
+/// world.run_all_systems().unwrap();
+///
+/// assert_eq!(world.get_resource::<Title>().unwrap().0, "Axle");
+/// ```
+pub struct NonSend<'r, T> {
+    value: &'r T,
+}
+
+impl<T> Deref for NonSend<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+/// A non-send resource written by a system, declared as a parameter of its function.
+///
+/// Like with `NonSend`, the system always runs alone, on the thread that runs the world. The
+/// resource must exist when the system runs, otherwise the system fails with
+/// `CustomErrors::ResourceInSystemDoesNotExist` without running.
+pub struct NonSendMut<'r, T> {
+    value: &'r mut T,
+}
+
+impl<T> Deref for NonSendMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<T> DerefMut for NonSendMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.value
+    }
+}
+
+/// A parameter of a system function that borrows a resource of the world.
+pub trait SystemParam {
+    /// The same parameter, borrowing the resource for the given lifetime.
+    type Item<'r>;
+
+    /// Gets the resource the parameter borrows and how.
+    fn access() -> ResourceAccess;
+
+    /// Borrows the parameter from the data of its resource.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data of the resource, which must be of the type given by `access`.
+    fn fetch(data: &mut dyn Any) -> Self::Item<'_>;
+
+    /// Checks if the resource is a non-send resource, which forces the system to run alone on the
+    /// thread that runs the world.
+    fn non_send() -> bool {
+        false
+    }
+}
+
+impl<T: Any> SystemParam for Res<'_, T> {
+    type Item<'r> = Res<'r, T>;
+
+    fn access() -> ResourceAccess {
+        ResourceAccess { type_id: TypeId::of::<T>(), mutable: false }
+    }
+
+    fn fetch(data: &mut dyn Any) -> Self::Item<'_> {
+        Res { value: data.downcast_ref().unwrap() }
+    }
+}
+
+impl<T: Any> SystemParam for ResMut<'_, T> {
+    type Item<'r> = ResMut<'r, T>;
+
+    fn access() -> ResourceAccess {
+        ResourceAccess { type_id: TypeId::of::<T>(), mutable: true }
+    }
+
+    fn fetch(data: &mut dyn Any) -> Self::Item<'_> {
+        ResMut { value: data.downcast_mut().unwrap() }
+    }
+}
+
+impl<T: Any> SystemParam for NonSend<'_, T> {
+    type Item<'r> = NonSend<'r, T>;
+
+    fn access() -> ResourceAccess {
+        ResourceAccess { type_id: TypeId::of::<T>(), mutable: false }
+    }
+
+    fn fetch(data: &mut dyn Any) -> Self::Item<'_> {
+        NonSend { value: data.downcast_ref().unwrap() }
+    }
+
+    fn non_send() -> bool {
+        true
+    }
+}
+
+impl<T: Any> SystemParam for NonSendMut<'_, T> {
+    type Item<'r> = NonSendMut<'r, T>;
+
+    fn access() -> ResourceAccess {
+        ResourceAccess { type_id: TypeId::of::<T>(), mutable: true }
+    }
+
+    fn fetch(data: &mut dyn Any) -> Self::Item<'_> {
+        NonSendMut { value: data.downcast_mut().unwrap() }
+    }
+
+    fn non_send() -> bool {
+        true
+    }
+}
+
+/// Converts a value into a system that can be added to the world.
+///
+/// It is implemented for every `System`, and for the functions and closures that take the
+/// entities followed by up to eight `Res`, `ResMut`, `NonSend` or `NonSendMut` parameters. The `Marker` type only tells
+/// both kinds apart and is inferred by the compiler.
+pub trait IntoSystem<Marker> {
+    /// The system the value is converted into.
+    type System: System + MaybeSend + 'static;
+
+    /// Converts the value into a system.
+    fn into_system(self) -> Self::System;
+}
+
+/// The marker of the values that already are a `System`.
+pub struct IsSystem;
+
+impl<S: System + MaybeSend + 'static> IntoSystem<IsSystem> for S {
+    type System = S;
+
+    fn into_system(self) -> Self::System {
+        self
+    }
+}
+
+/// A system made from a function whose parameters declare the resources it accesses.
+///
+/// Its declared resources are its only access to the resources of the world, so it can run at
+/// the same time as the systems it doesn't conflict with.
+pub struct FunctionSystem<F, P> {
+    /// The function of the system.
+    function: F,
+    /// The parameters of the function.
+    params: PhantomData<fn() -> P>,
+}
+
+/// A resource taken out of the world for a parameter of a system.
+enum TakenResource {
+    /// A resource taken from the regular resources.
+    Shared(AnyBox),
+    /// A resource taken from the non-send resources.
+    NonSend(Box<dyn Any>),
+}
+
+impl TakenResource {
+    /// Gets the data of the resource.
+    fn data(&mut self) -> &mut dyn Any {
+        match self {
+            TakenResource::Shared(data) => &mut **data,
+            TakenResource::NonSend(data) => &mut **data,
+        }
+    }
+}
+
+/// Takes the resources of the parameters of a system out of the resources of the world, so that
+/// every parameter can borrow its own one.
+///
+/// # Arguments
+///
+/// * `accesses` - The accesses of the parameters, in their order, and whether they are non-send.
+/// * `resources` - The resources of the world.
+/// * `non_send_resources` - The non-send resources of the world.
+///
+/// # Returns
+///
+/// A result that contains the data of the resources, in the order of the parameters, if succeeds
+/// or an error if it fails.
+///
+/// # Errors
+///
+/// If the same resource is declared more than once, or one of the resources doesn't exist, an
+/// error is returned and no resource is taken.
+fn take_resources(
+    accesses: &[(ResourceAccess, bool)],
+    resources: &mut Resources,
+    non_send_resources: &mut NonSendResources,
+) -> Result<Vec<TakenResource>> {
+    for (index, (access, non_send)) in accesses.iter().enumerate() {
+        if accesses[..index].iter().any(|(other, _)| other.type_id == access.type_id) {
+            return Err(CustomErrors::ConflictingResourceAccess.into());
+        }
+
+        let exists = if *non_send {
+            non_send_resources.get_by_type_id(&access.type_id).is_some()
+        } else {
+            resources.get_by_type_id(&access.type_id).is_some()
+        };
+
+        if !exists {
+            return Err(CustomErrors::ResourceInSystemDoesNotExist.into());
+        }
+    }
+
+    Ok(accesses
+        .iter()
+        .filter_map(|(access, non_send)| {
+            if *non_send {
+                non_send_resources.take_by_type_id(&access.type_id).map(TakenResource::NonSend)
+            } else {
+                resources.take_by_type_id(&access.type_id).map(TakenResource::Shared)
+            }
+        })
+        .collect())
+}
+
+/// Gives the resources taken by `take_resources` back to the resources of the world.
+fn give_back_resources(
+    accesses: &[(ResourceAccess, bool)],
+    taken: Vec<TakenResource>,
+    resources: &mut Resources,
+    non_send_resources: &mut NonSendResources,
+) {
+    for ((access, _), data) in accesses.iter().zip(taken) {
+        match data {
+            TakenResource::Shared(data) => resources.insert_by_type_id(access.type_id, data),
+            TakenResource::NonSend(data) => non_send_resources.insert_by_type_id(access.type_id, data),
+        }
+    }
+}
+
+/// Implements `System` and `IntoSystem` for the functions with the given parameters.
+macro_rules! impl_function_system {
+    ($($param:ident),*) => {
+        impl<F, $($param: SystemParam),*> System for FunctionSystem<F, fn($($param),*)>
+        where
+            F: FnMut(&Vec<QueryEntity>, $($param),*) -> Result<()>
+                + for<'r> FnMut(&Vec<QueryEntity>, $($param::Item<'r>),*) -> Result<()>,
+        {
+            fn run(&mut self, entities: &Vec<QueryEntity>, resources: &mut Resources) -> Result<()> {
+                self.run_with_non_send(entities, resources, &mut NonSendResources::default())
+            }
+
+            fn run_with_non_send(
+                &mut self,
+                entities: &Vec<QueryEntity>,
+                resources: &mut Resources,
+                non_send_resources: &mut NonSendResources,
+            ) -> Result<()> {
+                let accesses = [$(($param::access(), $param::non_send())),*];
+                let mut taken = take_resources(&accesses, resources, non_send_resources)?;
+
+                #[allow(unused_mut, unused_variables)]
+                let mut data = taken.iter_mut();
+                let result = (self.function)(entities, $($param::fetch(data.next().unwrap().data())),*);
+
+                give_back_resources(&accesses, taken, resources, non_send_resources);
+
+                result
+            }
+
+            fn declared_resources(&self) -> Option<Vec<ResourceAccess>> {
+                let accesses: Vec<(ResourceAccess, bool)> = vec![$(($param::access(), $param::non_send())),*];
+
+                // The non-send resources are never lent to a batch
+                Some(accesses
+                    .into_iter()
+                    .filter(|(_, non_send)| !non_send)
+                    .map(|(access, _)| access)
+                    .collect())
+            }
+
+            fn needs_main_thread(&self) -> bool {
+                false $(|| $param::non_send())*
+            }
+        }
+
+        impl<F, $($param: SystemParam + 'static),*> IntoSystem<fn($($param),*)> for F
+        where
+            F: FnMut(&Vec<QueryEntity>, $($param),*) -> Result<()>
+                + for<'r> FnMut(&Vec<QueryEntity>, $($param::Item<'r>),*) -> Result<()>
+                + MaybeSend
+                + 'static,
+        {
+            type System = FunctionSystem<F, fn($($param),*)>;
+
+            fn into_system(self) -> Self::System {
+                FunctionSystem { function: self, params: PhantomData }
+            }
+        }
+    };
+}
+
+impl_function_system!();
+impl_function_system!(P1);
+impl_function_system!(P1, P2);
+impl_function_system!(P1, P2, P3);
+impl_function_system!(P1, P2, P3, P4);
+impl_function_system!(P1, P2, P3, P4, P5);
+impl_function_system!(P1, P2, P3, P4, P5, P6);
+impl_function_system!(P1, P2, P3, P4, P5, P6, P7);
+impl_function_system!(P1, P2, P3, P4, P5, P6, P7, P8);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn function_systems_borrow_their_resources() -> Result<()> {
+        let mut resources = Resources::default();
+        resources.add(Gravity(2.0));
+        resources.add(Score(1.0));
+
+        let mut system = (|_: &Vec<QueryEntity>, gravity: Res<Gravity>, mut score: ResMut<Score>| {
+            score.0 += gravity.0;
+            Ok(())
+        })
+        .into_system();
+
+        system.run(&vec![], &mut resources)?;
+        system.run(&vec![], &mut resources)?;
+
+        assert_eq!(resources.get_ref::<Score>().unwrap().0, 5.0);
+        assert_eq!(
+            system.declared_resources(),
+            Some(vec![
+                ResourceAccess { type_id: TypeId::of::<Gravity>(), mutable: false },
+                ResourceAccess { type_id: TypeId::of::<Score>(), mutable: true },
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn missing_or_repeated_resources_are_rejected() {
+        let mut resources = Resources::default();
+        resources.add(Score(1.0));
+
+        let mut missing = (|_: &Vec<QueryEntity>, _: ResMut<Score>, _: Res<Gravity>| Ok(())).into_system();
+        let error = missing.run(&vec![], &mut resources).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(CustomErrors::ResourceInSystemDoesNotExist)));
+
+        let mut repeated = (|_: &Vec<QueryEntity>, _: Res<Score>, _: Res<Score>| Ok(())).into_system();
+        let error = repeated.run(&vec![], &mut resources).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(CustomErrors::ConflictingResourceAccess)));
+
+        // The resources are left untouched
+        assert_eq!(resources.get_ref::<Score>().unwrap().0, 1.0);
+    }
+
+    struct Gravity(f32);
+    struct Score(f32);
+}
@@ -0,0 +1,233 @@
+use std::{any::Any, cmp::Reverse, collections::BinaryHeap, sync::Arc};
+use eyre::Result;
+
+use crate::prelude::*;
+use crate::resources::Resources;
+
+/// A function that checks the resources and returns whether a system should run.
+#[cfg(not(feature = "parallel"))]
+pub type RunCondition = Arc<dyn Fn(&Resources) -> bool>;
+
+/// A function that checks the resources and returns whether a system should run.
+#[cfg(feature = "parallel")]
+pub type RunCondition = Arc<dyn Fn(&Resources) -> bool + Send + Sync>;
+
+/// The stages a frame is split into. The systems of a stage run after all the systems of the
+/// previous stages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    /// Runs before the main logic, for example to read the input.
+    PreUpdate,
+    /// Runs at a fixed rate instead of once per frame, for example to step the physics. Its
+    /// systems are skipped by `Systems::run_all`, and `Systems::run_frame` runs them after the
+    /// `PreUpdate` stage as many times as the elapsed time requires.
+    FixedUpdate,
+    /// The main logic of the game. Systems run in this stage unless told otherwise.
+    #[default]
+    Update,
+    /// Runs after the main logic, for example to propagate transforms or resolve collisions.
+    PostUpdate,
+    /// Draws the frame.
+    Render,
+}
+
+impl Stage {
+    /// Every stage in the order they run.
+    pub const ALL: [Stage; 5] = [Stage::PreUpdate, Stage::FixedUpdate, Stage::Update, Stage::PostUpdate, Stage::Render];
+}
+
+/// Checks if the resource of type `T` exists.
+///
+/// It can be used as a run condition so that a system only runs when the resource exists.
+///
+/// # Example
+///
+/// ```
+/// use axle_ecs::{entities::query_entity::QueryEntity, resources::Resources, World};
+/// use axle_ecs::systems::schedule::resource_exists;
+///
+/// let mut world = World::new();
+/// world
+///     .create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()))
+///     .run_if(resource_exists::<u32>).unwrap();
+/// ```
+pub fn resource_exists<T: Any>(resources: &Resources) -> bool {
+    resources.get_ref::<T>().is_some()
+}
+
+/// When a system runs: its stage, its ordering constraints and its run conditions.
+#[derive(Clone)]
+pub(crate) struct SystemSchedule {
+    /// The stage the system runs in.
+    pub stage: Stage,
+    /// The name other systems use to order themselves relative to this one.
+    pub name: Option<&'static str>,
+    /// The names of the systems this one must run before.
+    pub before: Vec<&'static str>,
+    /// The names of the systems this one must run after.
+    pub after: Vec<&'static str>,
+    /// The conditions that must be fulfilled for the system to run.
+    pub conditions: Vec<RunCondition>,
+    /// The order in which the system was created, used to order the systems that have no
+    /// constraints between them.
+    pub sequence: u64,
+}
+
+impl SystemSchedule {
+    /// Creates the schedule of a system that runs in the `Update` stage without constraints.
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - The order in which the system was created.
+    ///
+    /// # Returns
+    ///
+    /// A new `SystemSchedule` struct.
+    pub fn new(sequence: u64) -> Self {
+        Self {
+            stage: Stage::default(),
+            name: None,
+            before: vec![],
+            after: vec![],
+            conditions: vec![],
+            sequence,
+        }
+    }
+
+    /// Checks if every run condition of the system is fulfilled.
+    pub fn should_run(&self, resources: &Resources) -> bool {
+        self.conditions.iter().all(|condition| condition(resources))
+    }
+
+    /// Checks if there is an ordering constraint between this system and the other one.
+    pub fn is_ordered_with(&self, other: &SystemSchedule) -> bool {
+        self.runs_before(other) || other.runs_before(self)
+    }
+
+    /// Checks if this system must run before the other one.
+    fn runs_before(&self, other: &SystemSchedule) -> bool {
+        other.name.is_some_and(|name| self.before.contains(&name))
+            || self.name.is_some_and(|name| other.after.contains(&name))
+    }
+}
+
+/// Sorts the systems so that every stage runs after the previous ones and every ordering
+/// constraint is respected. The systems without constraints between them keep the order they
+/// were created in.
+///
+/// The constraints only apply between systems of the same stage.
+///
+/// # Arguments
+///
+/// * `systems` - The id and schedule of every system.
+///
+/// # Returns
+///
+/// A result that contains the ids of the systems in the order they must run if succeeds or an error if it fails.
+///
+/// # Errors
+///
+/// If the ordering constraints form a cycle, an error is returned.
+pub(crate) fn execution_order(systems: &[(usize, &SystemSchedule)]) -> Result<Vec<usize>> {
+    let mut order = Vec::with_capacity(systems.len());
+
+    for stage in Stage::ALL {
+        let stage_systems: Vec<&(usize, &SystemSchedule)> = systems
+            .iter()
+            .filter(|(_, schedule)| schedule.stage == stage)
+            .collect();
+
+        // Build the graph of constraints
+        let mut successors = vec![vec![]; stage_systems.len()];
+        let mut predecessors_count = vec![0; stage_systems.len()];
+
+        for (first, (_, first_schedule)) in stage_systems.iter().enumerate() {
+            for (second, (_, second_schedule)) in stage_systems.iter().enumerate() {
+                if first != second && first_schedule.runs_before(second_schedule) {
+                    successors[first].push(second);
+                    predecessors_count[second] += 1;
+                }
+            }
+        }
+
+        // Topological sort, picking the oldest system among the ones ready to run
+        let mut ready: BinaryHeap<Reverse<(u64, usize)>> = predecessors_count
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count == 0)
+            .map(|(system, _)| Reverse((stage_systems[system].1.sequence, system)))
+            .collect();
+        let mut sorted = 0;
+
+        while let Some(Reverse((_, system))) = ready.pop() {
+            order.push(stage_systems[system].0);
+            sorted += 1;
+
+            for successor in &successors[system] {
+                predecessors_count[*successor] -= 1;
+
+                if predecessors_count[*successor] == 0 {
+                    ready.push(Reverse((stage_systems[*successor].1.sequence, *successor)));
+                }
+            }
+        }
+
+        if sorted < stage_systems.len() {
+            return Err(CustomErrors::SystemOrderCycle.into());
+        }
+    }
+
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages_run_in_order() -> Result<()> {
+        let mut render = SystemSchedule::new(0);
+        render.stage = Stage::Render;
+        let update = SystemSchedule::new(1);
+        let mut pre_update = SystemSchedule::new(2);
+        pre_update.stage = Stage::PreUpdate;
+
+        let order = execution_order(&[(0, &render), (1, &update), (2, &pre_update)])?;
+        assert_eq!(order, vec![2, 1, 0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn constraints_are_respected() -> Result<()> {
+        let mut physics = SystemSchedule::new(0);
+        physics.name = Some("physics");
+        physics.after.push("input");
+        let mut input = SystemSchedule::new(1);
+        input.name = Some("input");
+        let mut movement = SystemSchedule::new(2);
+        movement.before.push("physics");
+        let unconstrained = SystemSchedule::new(3);
+
+        let order = execution_order(&[(0, &physics), (1, &input), (2, &movement), (3, &unconstrained)])?;
+        assert_eq!(order, vec![1, 2, 0, 3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cycles_are_detected() {
+        let mut first = SystemSchedule::new(0);
+        first.name = Some("first");
+        first.after.push("second");
+        let mut second = SystemSchedule::new(1);
+        second.name = Some("second");
+        second.after.push("first");
+
+        assert!(execution_order(&[(0, &first), (1, &second)]).is_err());
+
+        // Constraints between different stages are ignored
+        second.stage = Stage::PostUpdate;
+        assert!(execution_order(&[(0, &first), (1, &second)]).is_ok());
+    }
+}
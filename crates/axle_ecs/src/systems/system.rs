@@ -0,0 +1,104 @@
+use eyre::Result;
+
+use crate::prelude::*;
+use crate::resources::{NonSendResources, Resources};
+use super::access::ResourceAccess;
+
+/// A system that can be run by the world.
+///
+/// It is implemented for every `FnMut(&Vec<QueryEntity>, &mut Resources) -> Result<()>`, so
+/// functions and closures, even the ones that capture and mutate their own state, can be used as
+/// systems. Structs can implement it too to keep their state in their fields.
+///
+/// Functions that take `Res`, `ResMut`, `NonSend` and `NonSendMut` parameters instead of the whole
+/// resources are turned into systems too, see `IntoSystem`.
+///
+/// # Example
+///
+/// ```
+/// use eyre::Result;
+/// use axle_ecs::{entities::query_entity::QueryEntity, resources::Resources, systems::system::System, World};
+///
+/// struct Spawner {
+///     cooldown: u32,
+/// }
+///
+/// impl System for Spawner {
+///     fn run(&mut self, _: &Vec<QueryEntity>, _: &mut Resources) -> Result<()> {
+///         self.cooldown = self.cooldown.saturating_sub(1);
+///         Ok(())
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.create_system(Spawner { cooldown: 10 });
+///
+/// let mut frames = 0;
+/// world.create_system(move |_: &Vec<QueryEntity>, _: &mut Resources| {
+///     frames += 1;
+///     Ok(())
+/// });
+///
+/// world.run_all_systems().unwrap();
+/// ```
+pub trait System {
+    /// Runs the system.
+    ///
+    /// # Arguments
+    ///
+    /// * `entities` - The entities that match the components and filters of the system.
+    /// * `resources` - The resources of the world.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    // Same signature as the system functions, which take the entities as a `&Vec`
+    #[allow(clippy::ptr_arg)]
+    fn run(&mut self, entities: &Vec<QueryEntity>, resources: &mut Resources) -> Result<()>;
+
+    /// Gets the resources the system declares through the parameters of its function.
+    ///
+    /// # Returns
+    ///
+    /// The declared resources, or `None` if the system takes every resource of the world instead.
+    fn declared_resources(&self) -> Option<Vec<ResourceAccess>> {
+        None
+    }
+
+    /// Runs the system with access to the non-send resources too. It is only called on the thread
+    /// that runs the world, for the systems that need it.
+    ///
+    /// # Arguments
+    ///
+    /// * `entities` - The entities that match the components and filters of the system.
+    /// * `resources` - The resources of the world.
+    /// * `non_send_resources` - The non-send resources of the world.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    #[allow(clippy::ptr_arg)]
+    fn run_with_non_send(
+        &mut self,
+        entities: &Vec<QueryEntity>,
+        resources: &mut Resources,
+        _non_send_resources: &mut NonSendResources,
+    ) -> Result<()> {
+        self.run(entities, resources)
+    }
+
+    /// Checks if the system borrows non-send resources, so it must always run alone on the thread
+    /// that runs the world.
+    fn needs_main_thread(&self) -> bool {
+        false
+    }
+}
+
+impl<F> System for F
+where
+    F: FnMut(&Vec<QueryEntity>, &mut Resources) -> Result<()>,
+{
+    fn run(&mut self, entities: &Vec<QueryEntity>, resources: &mut Resources) -> Result<()> {
+        self(entities, resources)
+    }
+}
@@ -1,25 +1,80 @@
 use eyre::Result;
-use std::any::Any;
+use std::{any::{Any, TypeId}, collections::HashMap};
 
-use crate::entities::{query::Query, Entities};
+use crate::entities::{filter::QueryFilter, query::Query, typed_query::{QueryData, TypedQuery}, Entities};
 use crate::prelude::*;
-use crate::resources::Resources;
-use crate::systems::Systems;
+use crate::resources::{NonSendResources, Resources};
+use crate::registry::TypeRegistry;
+#[cfg(feature = "serde")]
+use crate::scene::prefab::Prefab;
+use crate::sync::MaybeSendSync;
+use crate::systems::{param::IntoSystem, schedule::Stage, Systems};
 
 /// The central hub of the ECS that stores all entities, systems, and resources.
-#[derive(Default)]
 pub struct World {
     /// The resources of the world. Resources are shared data that can be accessed by all systems.
     pub resources: Resources,
+    /// The resources that must stay on the thread that created them, like the handles of the
+    /// window. Systems access them through the `NonSend` and `NonSendMut` parameters.
+    pub non_send_resources: NonSendResources,
     /// The entities of the world. Entities are objects that can have components attached to them.
     pub entities: Entities,
     /// The systems of the world. Systems are functions that can access the entities and resources.
     pub systems: Systems,
+    /// The component and resource types that can be saved in scenes, reflected or inspected.
+    pub registry: TypeRegistry,
+    /// The prefabs that can be spawned, keyed by their name.
+    #[cfg(feature = "serde")]
+    pub(crate) prefabs: HashMap<String, Prefab>,
+    /// The functions that update the events added with `add_event`, keyed by the type id of the events.
+    event_updaters: HashMap<TypeId, fn(&mut Resources)>,
+    /// The functions that update the states added with `add_state`, in the order the states were
+    /// added, so that their transitions are always applied in the same order.
+    pub(crate) state_updaters: Vec<StateUpdater>,
+}
+
+/// The functions that update a state added with `add_state`.
+#[derive(Clone, Copy)]
+pub(crate) struct StateUpdater {
+    /// The type id of the states.
+    pub(crate) type_id: TypeId,
+    /// Applies the queued transition of the state.
+    pub(crate) transition: fn(&mut Resources),
+    /// Deletes the entities scoped to the state that was left in the current run of the systems.
+    pub(crate) despawn_scoped: fn(&Resources, &mut Entities) -> Result<()>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        // The commands buffer is always available so that systems can queue structural changes
+        let entities = Entities::default();
+        let mut resources = Resources::default();
+        resources.add(Commands::new(&entities));
+
+        // The hierarchy components are always shown when inspecting the entities
+        let mut registry = TypeRegistry::default();
+        registry.register_debug::<Parent>();
+        registry.register_debug::<Children>();
+
+        Self {
+            resources,
+            non_send_resources: NonSendResources::default(),
+            entities,
+            systems: Systems::default(),
+            registry,
+            #[cfg(feature = "serde")]
+            prefabs: HashMap::new(),
+            event_updaters: HashMap::new(),
+            state_updaters: vec![],
+        }
+    }
 }
 
 impl World {
     /// Create a new, empty `World`.
     /// This funtion is the equivalent of calling `World::default()`
+    ///
+    /// The only resource of the new world is the `Commands` buffer used by the systems.
     pub fn new() -> Self {
         Self::default()
     }
@@ -40,7 +95,7 @@ impl World {
     /// let mut world = World::new();
     /// world.add_resource(10_i32);
     /// ```
-    pub fn add_resource(&mut self, resource_data: impl Any) {
+    pub fn add_resource(&mut self, resource_data: impl Any + MaybeSendSync) {
         self.resources.add(resource_data);
     }
 
@@ -116,10 +171,62 @@ impl World {
         self.resources.remove::<T>();
     }
 
+    /// Add a resource that must stay on the current thread, like the handles of a platform library.
+    ///
+    /// Unlike regular resources, it doesn't need to be `Send + Sync`, even with the `parallel`
+    /// feature, but the systems that access it with `NonSend` or `NonSendMut` always run alone, on
+    /// the thread that runs the world.
+    ///
+    /// # Arguments
+    ///
+    /// * `resource_data` - The data you want to save as a resource.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    /// use axle_ecs::World;
+    ///
+    /// let mut world = World::new();
+    /// world.add_non_send_resource(Rc::new(10_i32));
+    ///
+    /// assert!(world.get_resource::<Rc<i32>>().is_none());
+    /// assert_eq!(**world.get_non_send_resource::<Rc<i32>>().unwrap(), 10);
+    /// ```
+    pub fn add_non_send_resource(&mut self, resource_data: impl Any) {
+        self.non_send_resources.add(resource_data);
+    }
+
+    /// Query for a non-send resource and get an immutable reference to it.
+    ///
+    /// # Returns
+    ///
+    /// An option containing a reference to the resource.
+    pub fn get_non_send_resource<T: Any>(&self) -> Option<&T> {
+        self.non_send_resources.get_ref::<T>()
+    }
+
+    /// Query for a non-send resource and get a mutable reference to it.
+    ///
+    /// # Returns
+    ///
+    /// An option containing a mutable reference to the resource.
+    pub fn get_non_send_resource_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.non_send_resources.get_mut::<T>()
+    }
+
+    /// This will remove the non-send resource from the world.
+    /// If the resource doesn't exist, it won't happen anything.
+    pub fn delete_non_send_resource<T: Any>(&mut self) {
+        self.non_send_resources.remove::<T>();
+    }
+
     /// This will register a component type to the world.
     /// Which will make it available for its use in entities.
     ///
-    /// If you don't register a component, you won't be able to use it in entities.
+    /// Components are registered automatically the first time they are added to an entity, so
+    /// this is only needed to set up the storage in advance. Registering a component twice does
+    /// nothing.
     ///
     /// # Example
     ///
@@ -127,15 +234,18 @@ impl World {
     /// use axle_ecs::World;
     ///
     /// let mut world = World::new();
-    /// world.register_component::<i32>(); // Now you can use the i32 component in entities
+    /// world.register_component::<i32>(); // The storage of i32 components is ready
     /// ```
-    pub fn register_component<T: Any + 'static>(&mut self) {
+    pub fn register_component<T: Any + MaybeSendSync>(&mut self) {
         self.entities.register_component::<T>();
     }
 
     /// Create a new entity and return a mutable reference to the `Entities` struct
     /// so that you can add components to it.
     ///
+    /// Calling `entity` at the end of the chain returns the `Entity` handle of the created entity,
+    /// which is what the rest of the functions of the world use to address it.
+    ///
     /// # Returns
     ///
     /// A mutable reference to the Entities struct.
@@ -144,19 +254,119 @@ impl World {
     ///
     /// ```
     /// use axle_ecs::World;
+    /// use axle_ecs::entities::entity::Entity;
     ///
     /// let mut world = World::new();
     /// world.register_component::<u32>();
     ///
-    /// world
+    /// let entity: Entity = world
     ///     .create_entity()
-    ///     .with_component(100_u32).unwrap();
+    ///     .with_component(100_u32).unwrap()
+    ///     .entity();
     /// ```
     pub fn create_entity(&mut self) -> &mut Entities {
         self.entities.create_entity()
     }
 
-    /// Query the entities in the world.
+    /// Query the entities in the world and get their components already downcasted.
+    ///
+    /// The query is described as a type: `&T` reads a component, `&mut T` writes it, `Option<&T>`
+    /// reads it if the entity has it, and `Entity` gets the handle of the entity. Tuples of them
+    /// query several components at once.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains a `TypedQuery` to iterate over the matching entities if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If a component is accessed mutably more than once, or both mutably and immutably, an error
+    /// is returned. Querying a component that was never registered yields no entities.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::World;
+    /// use axle_ecs::entities::entity::Entity;
+    ///
+    /// let mut world = World::new();
+    /// world.register_component::<u32>();
+    /// world.register_component::<f32>();
+    /// world
+    ///     .create_entity()
+    ///     .with_component(100_u32).unwrap()
+    ///     .with_component(1.0_f32).unwrap();
+    /// world
+    ///     .create_entity()
+    ///     .with_component(200_u32).unwrap();
+    ///
+    /// for (number, mut decimal) in world.query::<(&u32, &mut f32)>().unwrap().iter() {
+    ///     *decimal += *number as f32;
+    /// }
+    ///
+    /// let query = world.query::<(Entity, &u32, Option<&f32>)>().unwrap();
+    /// assert_eq!(query.count(), 2);
+    ///
+    /// for (_, number, decimal) in query.iter() {
+    ///     match decimal {
+    ///         Some(decimal) => assert_eq!(*decimal, 101.0),
+    ///         None => assert_eq!(*number, 200),
+    ///     }
+    /// }
+    ///
+    /// assert!(world.query::<(&u32, &mut u32)>().is_err());
+    /// ```
+    pub fn query<Q: QueryData>(&self) -> Result<TypedQuery<'_, Q>> {
+        TypedQuery::new(&self.entities)
+    }
+
+    /// Query the entities in the world that match a filter and get their components already
+    /// downcasted.
+    ///
+    /// The filter is described as a type: `With<T>` and `Without<T>` require the entities to have
+    /// or not have a component, `Added<T>` and `Changed<T>` require the component to have been
+    /// added or changed since the last run of the systems started, and `Or<(..)>` requires any of
+    /// its filters to match. Tuples of filters require all of them to match.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains a `TypedQuery` to iterate over the matching entities if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If a component is accessed mutably more than once, or both mutably and immutably, an error
+    /// is returned. Querying a component that was never registered yields no entities.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::World;
+    /// use axle_ecs::entities::filter::{Changed, Without};
+    ///
+    /// let mut world = World::new();
+    /// world.register_component::<u32>();
+    /// world.register_component::<f32>();
+    /// world.create_entity().with_component(100_u32).unwrap();
+    /// world
+    ///     .create_entity()
+    ///     .with_component(200_u32).unwrap()
+    ///     .with_component(1.0_f32).unwrap();
+    ///
+    /// let query = world.query_filtered::<&u32, Without<f32>>().unwrap();
+    /// assert_eq!(query.count(), 1);
+    ///
+    /// // Every component is new before the systems run for the first time
+    /// let query = world.query_filtered::<&u32, Changed<u32>>().unwrap();
+    /// assert_eq!(query.count(), 2);
+    /// ```
+    pub fn query_filtered<Q: QueryData, F: QueryFilter>(&self) -> Result<TypedQuery<'_, Q, F>> {
+        TypedQuery::new(&self.entities)
+    }
+
+    /// Build an untyped query over the entities in the world.
+    ///
+    /// The components are returned type-erased, so they have to be downcasted by hand. Most of the
+    /// time the typed `query` function is more convenient.
     ///
     /// # Returns
     ///
@@ -165,8 +375,8 @@ impl World {
     /// # Example
     ///
     /// ```
-    /// use std::{any::Any, cell::RefCell, rc::Rc};
     /// use axle_ecs::World;
+    /// use axle_ecs::entities::Component;
     /// use axle_ecs::entities::query::{QueryIndexes, QueryComponents};
     ///
     /// let mut world = World::new();
@@ -176,10 +386,10 @@ impl World {
     ///     .with_component(100_u32).unwrap();
     ///
     /// let query: (QueryIndexes, QueryComponents) = world
-    ///     .query()
+    ///     .query_builder()
     ///     .with_component::<u32>().unwrap()
     ///     .run();
-    /// let u32s: &Vec<Rc<RefCell<dyn Any>>> = &query.1[0];
+    /// let u32s: &Vec<Component> = &query.1[0];
     ///
     /// assert_eq!(u32s.len(), 1);
     ///
@@ -188,15 +398,15 @@ impl World {
     ///
     /// assert_eq!(*first_u32, 100_u32);
     /// ```
-    pub fn query(&self) -> Query {
+    pub fn query_builder(&self) -> Query<'_> {
         Query::new(&self.entities)
     }
 
-    /// Deletes the component from the entity with the given index.
+    /// Deletes the component from the entity with the given handle.
     ///
     /// # Arguments
     ///
-    /// * `index` - The index or id of the entity.
+    /// * `entity` - The handle of the entity.
     ///
     /// # Returns
     ///
@@ -211,36 +421,38 @@ impl World {
     /// let mut world = World::new();
     /// world.register_component::<u32>();
     /// world.register_component::<i32>();
-    /// world
+    /// let first_entity = world
     ///     .create_entity()
     ///     .with_component(100_u32).unwrap()
-    ///     .with_component(100_i32).unwrap();
-    /// world
+    ///     .with_component(100_i32).unwrap()
+    ///     .entity();
+    /// let second_entity = world
     ///     .create_entity()
     ///     .with_component(200_u32).unwrap()
-    ///     .with_component(200_i32).unwrap();
+    ///     .with_component(200_i32).unwrap()
+    ///     .entity();
     ///
-    /// world.delete_component_by_entity_id::<u32>(0).unwrap();
+    /// world.delete_component_by_entity_id::<u32>(first_entity).unwrap();
     ///
-    ///  let query: (QueryIndexes, QueryComponents) = world.query()
+    ///  let query: (QueryIndexes, QueryComponents) = world.query_builder()
     ///     .with_component::<u32>().unwrap()
     ///     .with_component::<i32>().unwrap()
     ///     .run();
     ///
     /// assert_eq!(query.0.len(), 1);
-    /// assert_eq!(query.0[0], 1);
+    /// assert_eq!(query.0[0], second_entity);
     ///
     /// ```
-    pub fn delete_component_by_entity_id<T: Any>(&mut self, index: usize) -> Result<()> {
-        self.entities.delete_component_by_entity_id::<T>(index)
+    pub fn delete_component_by_entity_id<T: Any>(&mut self, entity: Entity) -> Result<()> {
+        self.entities.delete_component_by_entity_id::<T>(entity)
     }
 
-    /// Adds a component to an entity by the given id.
+    /// Adds a component to an entity by the given handle.
     ///
     /// # Arguments
     ///
     /// * `data` - The component's data.
-    /// * `index` - The index or id of the entity.
+    /// * `entity` - The handle of the entity.
     ///
     /// # Returns
     ///
@@ -255,14 +467,15 @@ impl World {
     /// world.register_component::<u32>();
     /// world.register_component::<i32>();
     ///
-    /// world
+    /// let entity = world
     ///     .create_entity()
-    ///     .with_component(10_u32).unwrap();
+    ///     .with_component(10_u32).unwrap()
+    ///     .entity();
     ///
-    /// world.add_component_to_entity_by_id(10_i32, 0).unwrap();
+    /// world.add_component_to_entity_by_id(10_i32, entity).unwrap();
     ///
     /// let query = world
-    ///     .query()
+    ///     .query_builder()
     ///     .with_component::<u32>().unwrap()
     ///     .with_component::<i32>().unwrap()
     ///     .run();
@@ -270,15 +483,15 @@ impl World {
     /// assert_eq!(query.0.len(), 1);
     /// assert_eq!(query.1.len(), 2);
     /// ```
-    pub fn add_component_to_entity_by_id(&mut self, data: impl Any, index: usize) -> Result<()> {
-        self.entities.add_component_by_entity_id(data, index)
+    pub fn add_component_to_entity_by_id(&mut self, data: impl Any + MaybeSendSync, entity: Entity) -> Result<()> {
+        self.entities.add_component_by_entity_id(data, entity)
     }
 
-    /// Deletes an entity by its id.
+    /// Deletes an entity by its handle.
     ///
     /// # Arguments
     ///
-    /// * `index` - The id of the entity to delete.
+    /// * `entity` - The handle of the entity to delete.
     ///
     /// # Returns
     ///
@@ -292,31 +505,173 @@ impl World {
     /// let mut world = World::new();
     /// world.register_component::<u32>();
     ///
-    /// world
+    /// let entity = world
     ///     .create_entity()
-    ///     .with_component(100_u32).unwrap();
+    ///     .with_component(100_u32).unwrap()
+    ///     .entity();
     /// world
     ///     .create_entity()
     ///     .with_component(10_u32).unwrap();
     ///
-    /// world.delete_entity_by_id(0).unwrap();
+    /// world.delete_entity_by_id(entity).unwrap();
     ///
     /// let query = world
-    ///     .query()
+    ///     .query_builder()
     ///     .with_component::<u32>().unwrap()
     ///     .run();
     ///
     /// assert_eq!(query.0.len(), 1);
     /// ```
-    pub fn delete_entity_by_id(&mut self, index: usize) -> Result<()> {
-        self.entities.delete_entity_by_id(index)
+    pub fn delete_entity_by_id(&mut self, entity: Entity) -> Result<()> {
+        self.entities.delete_entity_by_id(entity)
+    }
+
+    /// Deletes an entity together with all its descendants.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The handle of the entity to delete.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::World;
+    ///
+    /// let mut world = World::new();
+    /// let character = world.create_entity().entity();
+    /// let weapon = world.create_entity().entity();
+    /// world.set_parent(weapon, character).unwrap();
+    ///
+    /// world.delete_entity_recursive(character).unwrap();
+    ///
+    /// assert!(!world.entities.is_alive(weapon));
+    /// ```
+    pub fn delete_entity_recursive(&mut self, entity: Entity) -> Result<()> {
+        self.entities.delete_entity_recursive(entity)
+    }
+
+    /// Makes an entity the child of another one, detaching it from its previous parent if it had one.
+    ///
+    /// The `Parent` component of the child and the `Children` component of the parent are kept in sync.
+    ///
+    /// # Arguments
+    ///
+    /// * `child` - The handle of the entity to attach.
+    /// * `parent` - The handle of the new parent.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If one of the entities doesn't exist, or if the child is the parent itself or one of its
+    /// ancestors, an error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::World;
+    ///
+    /// let mut world = World::new();
+    /// let car = world.create_entity().entity();
+    /// let wheel = world.create_entity().entity();
+    ///
+    /// world.set_parent(wheel, car).unwrap();
+    ///
+    /// assert_eq!(world.entities.get_parent(wheel), Some(car));
+    /// ```
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) -> Result<()> {
+        self.entities.set_parent(child, parent)
+    }
+
+    /// Detaches an entity from its parent, making it a root entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `child` - The handle of the entity to detach.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    pub fn remove_parent(&mut self, child: Entity) -> Result<()> {
+        self.entities.remove_parent(child)
+    }
+
+    /// Adds a hook that is called when a component is added to an entity that didn't have it.
+    ///
+    /// The hooks are called for the changes made through the world, the entities and the commands.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component.
+    /// * `hook` - The function called with the entity and its new component.
+    pub fn on_add<T: Any + MaybeSendSync>(&mut self, hook: impl Fn(Entity, &T) + MaybeSendSync + 'static) {
+        self.entities.on_add(hook);
+    }
+
+    /// Adds a hook that is called every time a component is inserted into an entity, both when it
+    /// is added and when it replaces the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component.
+    /// * `hook` - The function called with the entity and its new component.
+    pub fn on_insert<T: Any + MaybeSendSync>(&mut self, hook: impl Fn(Entity, &T) + MaybeSendSync + 'static) {
+        self.entities.on_insert(hook);
+    }
+
+    /// Adds a hook that is called right before a component is removed from an entity, including
+    /// when the entity is deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the component.
+    /// * `hook` - The function called with the entity and the component being removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use axle_ecs::World;
+    ///
+    /// struct Texture(u32);
+    ///
+    /// let freed = Arc::new(Mutex::new(vec![]));
+    /// let textures = freed.clone();
+    ///
+    /// let mut world = World::new();
+    /// world.on_remove::<Texture>(move |_, texture| textures.lock().unwrap().push(texture.0));
+    ///
+    /// let sprite = world.create_entity().with_component(Texture(7)).unwrap().entity();
+    /// world.delete_component_by_entity_id::<Texture>(sprite).unwrap();
+    ///
+    /// assert_eq!(*freed.lock().unwrap(), vec![7]);
+    /// ```
+    pub fn on_remove<T: Any + MaybeSendSync>(&mut self, hook: impl Fn(Entity, &T) + MaybeSendSync + 'static) {
+        self.entities.on_remove(hook);
+    }
+
+    /// Adds an observer that is called right before any entity is deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - The function called with the entity being deleted.
+    pub fn observe_despawn(&mut self, observer: impl Fn(Entity) + MaybeSendSync + 'static) {
+        self.entities.observe_despawn(observer);
     }
 
     /// Creates a new system and adds it to the world.
     ///
     /// # Arguments
     ///
-    /// * `system` - The system function to add.
+    /// * `system` - The system to add: a function, a closure that owns its state or a `System` struct.
+    ///   Functions can take `Res` and `ResMut` parameters instead of the whole resources, see
+    ///   `Systems::create_system`.
     ///
     /// # Returns
     ///
@@ -334,7 +689,7 @@ impl World {
     ///
     /// world.create_system(&|_: &Vec<QueryEntity>, _: &mut Resources| Ok(()));
     /// ```
-    pub fn create_system(&mut self, system: SystemFunction) -> &mut Systems {
+    pub fn create_system<M>(&mut self, system: impl IntoSystem<M>) -> &mut Systems {
         self.systems.create_system(system)
     }
 
@@ -422,12 +777,55 @@ impl World {
         self.systems.delete_system_by_id(index)
     }
 
-    /// Runs all systems and updates the entities.
+    /// Adds a channel of events of type `T` to the world, stored as the `Events<T>` resource.
+    ///
+    /// The events older than two frames are dropped at the start of every run of the systems.
+    /// Adding the same events more than once does nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `T` - The type of the events.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axle_ecs::{events::Events, World};
+    ///
+    /// let mut world = World::new();
+    /// world.add_event::<u32>();
+    ///
+    /// world.get_resource_mut::<Events<u32>>().unwrap().send(10);
+    /// ```
+    pub fn add_event<T: Any + MaybeSendSync>(&mut self) {
+        if self.resources.get_ref::<Events<T>>().is_none() {
+            self.resources.add(Events::<T>::default());
+        }
+
+        self.event_updaters.insert(TypeId::of::<T>(), |resources| {
+            if let Some(events) = resources.get_mut::<Events<T>>() {
+                events.update();
+            }
+        });
+    }
+
+    /// Runs all systems, except the ones in the `FixedUpdate` stage, and updates the entities.
+    ///
+    /// The events added with `add_event` are updated first, dropping the ones older than two frames.
+    /// Then the transitions queued in the states added with `add_state` are applied, in the order
+    /// the states were added. The entities scoped to the states that were left are deleted once
+    /// every system ran, so the `on_exit` systems still see them.
+    ///
+    /// The commands queued by a system in the `Commands` resource are applied right after the
+    /// system finishes.
     ///
     /// # Returns
     ///
     /// A result that contains nothing if succeeds or an error if it fails.
     ///
+    /// # Errors
+    ///
+    /// If a system or one of the commands it queued fails, its error is returned.
+    ///
     /// # Example
     ///
     /// ```
@@ -448,6 +846,63 @@ impl World {
     ///
     /// ```
     pub fn run_all_systems(&mut self) -> Result<()> {
-        self.systems.run_all(&self.entities, &mut self.resources)
+        self.run_frame(0)
+    }
+
+    /// Runs a whole frame, like `run_all_systems`, but also runs the `FixedUpdate` stage the given
+    /// number of times, after the `PreUpdate` stage and before the rest of the stages.
+    ///
+    /// The events and states are updated once, before any system runs, and the entities scoped to
+    /// the states that were left are deleted once, after every system ran.
+    ///
+    /// # Arguments
+    ///
+    /// * `fixed_steps` - How many times the `FixedUpdate` stage runs.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If a system or one of the commands it queued fails, its error is returned.
+    pub fn run_frame(&mut self, fixed_steps: u32) -> Result<()> {
+        for update in self.event_updaters.values() {
+            update(&mut self.resources);
+        }
+
+        for updater in &self.state_updaters {
+            (updater.transition)(&mut self.resources);
+        }
+
+        let result = self.systems.run_frame(fixed_steps, &mut self.entities, &mut self.resources, &mut self.non_send_resources);
+
+        // The states only remember the state that was left until the next frame, so the scoped
+        // entities are deleted even if a system failed
+        for updater in &self.state_updaters {
+            (updater.despawn_scoped)(&self.resources, &mut self.entities)?;
+        }
+
+        result
+    }
+
+    /// Runs the systems of a single stage, usually the `FixedUpdate` stage that `run_all_systems`
+    /// skips.
+    ///
+    /// Unlike `run_frame`, the events and states are not updated.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The stage whose systems run.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If a system or one of the commands it queued fails, its error is returned.
+    pub fn run_stage(&mut self, stage: Stage) -> Result<()> {
+        self.systems.run_stage(stage, &mut self.entities, &mut self.resources, &mut self.non_send_resources)
     }
 }
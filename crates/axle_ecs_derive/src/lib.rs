@@ -0,0 +1,199 @@
+//! Derive macros for axle_ecs.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Index, Member};
+
+/// Derives `axle_ecs::reflect::Reflect` for a struct.
+///
+/// Every field is exposed under its name, or its position for tuple structs, so its type must
+/// implement `Reflect` too. Fields marked with `#[reflect(ignore)]` are skipped.
+///
+/// The type is named after the struct, except for generic structs, which are named with
+/// `std::any::type_name` so that every instantiation, like `Foo<A>` and `Foo<B>`, gets its own name.
+#[proc_macro_derive(Reflect, attributes(reflect))]
+pub fn derive_reflect(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match reflect_impl(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Generates the implementation of `Reflect` for the given struct.
+fn reflect_impl(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, "Reflect can only be derived for structs"));
+    };
+
+    let mut names = vec![];
+    let mut members = vec![];
+
+    for (position, field) in data.fields.iter().enumerate() {
+        if is_ignored(field)? {
+            continue;
+        }
+
+        match &field.ident {
+            Some(ident) => {
+                names.push(ident.to_string());
+                members.push(Member::Named(ident.clone()));
+            }
+            None => {
+                names.push(position.to_string());
+                members.push(Member::Unnamed(Index::from(position)));
+            }
+        }
+    }
+
+    // Every type parameter must be reflectable so that the fields using it can be exposed
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::axle_ecs::reflect::Reflect));
+    }
+
+    let ident = &input.ident;
+    let is_generic = input.generics.type_params().next().is_some() || input.generics.const_params().next().is_some();
+    let type_name = if is_generic {
+        quote! { ::std::any::type_name::<Self>() }
+    } else {
+        let name = ident.to_string();
+        quote! { #name }
+    };
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::axle_ecs::reflect::Reflect for #ident #type_generics #where_clause {
+            fn static_type_name() -> &'static str
+            where
+                Self: Sized,
+            {
+                #type_name
+            }
+
+            fn type_name(&self) -> &'static str {
+                #type_name
+            }
+
+            fn field_names(&self) -> &'static [&'static str] {
+                &[#(#names),*]
+            }
+
+            fn field(&self, name: &str) -> ::std::option::Option<&dyn ::axle_ecs::reflect::Reflect> {
+                match name {
+                    #(#names => ::std::option::Option::Some(&self.#members),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn field_mut(&mut self, name: &str) -> ::std::option::Option<&mut dyn ::axle_ecs::reflect::Reflect> {
+                match name {
+                    #(#names => ::std::option::Option::Some(&mut self.#members),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn as_any(&self) -> &dyn ::std::any::Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn ::std::any::Any {
+                self
+            }
+        }
+    })
+}
+
+/// Derives `axle_ecs::bundle::Bundle` for a struct.
+///
+/// Every field is added to the entity as a component, except the fields marked with `#[bundle]`,
+/// which must be bundles themselves and have their components added instead.
+#[proc_macro_derive(Bundle, attributes(bundle))]
+pub fn derive_bundle(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match bundle_impl(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Generates the implementation of `Bundle` for the given struct.
+fn bundle_impl(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, "Bundle can only be derived for structs"));
+    };
+
+    let mut additions = vec![];
+
+    for (position, field) in data.fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(position)),
+        };
+
+        if is_nested_bundle(field)? {
+            additions.push(quote! {
+                ::axle_ecs::bundle::Bundle::add_to_entity(self.#member, entities, entity)?;
+            });
+        } else {
+            additions.push(quote! {
+                entities.add_component_by_entity_id(self.#member, entity)?;
+            });
+        }
+    }
+
+    // Every type parameter must be storable as a component
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::std::any::Any));
+        param.bounds.push(parse_quote!(::axle_ecs::sync::MaybeSendSync));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::axle_ecs::bundle::Bundle for #ident #type_generics #where_clause {
+            fn add_to_entity(
+                self,
+                entities: &mut ::axle_ecs::entities::Entities,
+                entity: ::axle_ecs::entities::entity::Entity,
+            ) -> ::axle_ecs::eyre::Result<()> {
+                #(#additions)*
+
+                ::std::result::Result::Ok(())
+            }
+        }
+    })
+}
+
+/// Checks if a field is marked with `#[bundle]`.
+fn is_nested_bundle(field: &syn::Field) -> syn::Result<bool> {
+    let mut nested = false;
+
+    for attribute in field.attrs.iter().filter(|attribute| attribute.path().is_ident("bundle")) {
+        attribute.meta.require_path_only()?;
+        nested = true;
+    }
+
+    Ok(nested)
+}
+
+/// Checks if a field is marked with `#[reflect(ignore)]`.
+fn is_ignored(field: &syn::Field) -> syn::Result<bool> {
+    let mut ignored = false;
+
+    for attribute in field.attrs.iter().filter(|attribute| attribute.path().is_ident("reflect")) {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("ignore") {
+                ignored = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown reflect attribute, expected `ignore`"))
+            }
+        })?;
+    }
+
+    Ok(ignored)
+}
@@ -6,6 +6,7 @@ use crate::math::lerp;
 use crate::vector::Vector2;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rot2 {
     pub cos: f32,
     pub sin: f32,
@@ -2,6 +2,7 @@ use crate::rotation::Rot2;
 use crate::vector::Vector2;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transform {
     pub position: Vector2,
     pub rotation: Rot2,
@@ -149,3 +150,109 @@ impl Transform {
         )
     }
 }
+
+/// The world-space transform of an entity, computed from its `Transform` and the transforms of
+/// its ancestors.
+///
+/// For an entity without a parent it is the same as its `Transform`. For a child, its `Transform`
+/// is relative to its parent, and its `GlobalTransform` is the parent's `GlobalTransform` applied
+/// to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlobalTransform {
+    pub position: Vector2,
+    pub rotation: Rot2,
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl From<&Transform> for GlobalTransform {
+    fn from(transform: &Transform) -> Self {
+        Self {
+            position: transform.position,
+            rotation: transform.rotation,
+        }
+    }
+}
+
+impl GlobalTransform {
+    pub const IDENTITY: Self = Self {
+        position: Vector2::ZERO,
+        rotation: Rot2::IDENTITY,
+    };
+
+    /// Applies this transform to a transform relative to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `local` - The transform relative to this one, for example the transform of a child.
+    ///
+    /// # Returns
+    ///
+    /// The world-space transform of `local`.
+    pub fn mul_transform(&self, local: &Transform) -> Self {
+        Self {
+            position: self.transform_point(&local.position),
+            rotation: self.rotation * local.rotation,
+        }
+    }
+
+    /// Transforms a point from the local space of this transform to world space, taking the
+    /// rotation into account.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The point in local space.
+    ///
+    /// # Returns
+    ///
+    /// A `Vector2` representing the point in world space.
+    pub fn transform_point(&self, point: &Vector2) -> Vector2 {
+        self.position + self.rotation * *point
+    }
+
+    /// Converts the global transform into a `Transform` with the same position and rotation.
+    pub fn compute_transform(&self) -> Transform {
+        Transform {
+            position: self.position,
+            rotation: self.rotation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn test_global_transform_of_a_child() {
+        let parent = GlobalTransform {
+            position: Vector2::new(10.0, 0.0),
+            rotation: Rot2::FRAC_PI_2,
+        };
+        let child = Transform {
+            position: Vector2::new(2.0, 0.0),
+            rotation: Rot2::FRAC_PI_2,
+        };
+
+        let global = parent.mul_transform(&child);
+
+        assert_approx_eq!(global.position.x, 10.0, 1e-5);
+        assert_approx_eq!(global.position.y, 2.0, 1e-5);
+        assert_approx_eq!(global.rotation.as_radians(), std::f32::consts::PI, 1e-5);
+    }
+
+    #[test]
+    fn test_global_transform_of_a_root() {
+        let transform = Transform::from_xy(3.0, 4.0);
+        let global = GlobalTransform::IDENTITY.mul_transform(&transform);
+
+        assert_eq!(global, GlobalTransform::from(&transform));
+        assert_eq!(global.compute_transform().position, transform.position);
+    }
+}
@@ -7,6 +7,7 @@ use crate::custom_errors::CustomErrors;
 ///
 /// This struct represents a 2-dimensional vector with `x` and `y` coordinates.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector2 {
     /// The x-coordinate of the vector.
     pub x: f32,
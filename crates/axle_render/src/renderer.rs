@@ -1,8 +1,9 @@
 use axle_ecs::entities::query_entity::QueryEntity;
-use axle_ecs::resources::Resources;
+use axle_ecs::systems::{param::NonSendMut, schedule::Stage};
 use axle_ecs::World;
 use axle_math::transform::Transform;
 use eyre::Result;
+use sdl2::render::WindowCanvas;
 
 use crate::config::RenderConfig;
 use crate::custom_errors::CustomErrors;
@@ -25,14 +26,15 @@ pub fn new(world: &mut World) -> Result<()> {
         .unwrap();
     let canvas = window.into_canvas().build().unwrap();
 
-    // Resgiter resources
-    world.add_resource(sdl_context);
-    world.add_resource(video_subsystem);
-    world.add_resource(canvas);
+    // Resgiter resources. The SDL handles must stay on the main thread
+    world.add_non_send_resource(sdl_context);
+    world.add_non_send_resource(video_subsystem);
+    world.add_non_send_resource(canvas);
 
     // Register render function in the ECS
     world
-        .create_system(&render)
+        .create_system(render)
+        .in_stage(Stage::Render)?
         .with_component::<Transform>()?
         .with_component::<Box<dyn Renderable>>()?;
 
@@ -40,10 +42,14 @@ pub fn new(world: &mut World) -> Result<()> {
 }
 
 // TODO:: Finish the render function
-fn render(entities: &Vec<QueryEntity>, resources: &mut Resources) -> Result<()> {
+fn render(entities: &Vec<QueryEntity>, mut canvas: NonSendMut<WindowCanvas>) -> Result<()> {
+    canvas.clear();
+
     for entities in entities {
         let transform = entities.get_component::<Transform>()?;
     }
 
+    canvas.present();
+
     Ok(())
 }
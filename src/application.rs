@@ -1,30 +1,111 @@
 use axle_ecs::World;
+use eyre::Result;
 use std::time::{Duration, Instant};
 
+use crate::config::GlobalConfig;
+use crate::engine::time::FixedTime;
+use crate::hierarchy;
+use crate::physics::{self, integration::PhysicsSettings};
+
 pub struct App {
     pub world: World,
+    /// Delay between frames based on the target FPS.
+    frame_delay: Duration,
+    /// The moment the last frame started, used to measure the time between frames.
+    last_frame: Instant,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl App {
     pub fn new() -> Self {
+        Self::build(PhysicsSettings::default(), FixedTime::default(), Duration::from_secs_f32(1.0 / 60.0))
+    }
+
+    /// Creates an app whose physics, fixed timestep and frame rate follow the given configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The global configuration of the game.
+    ///
+    /// # Returns
+    ///
+    /// A new `App`.
+    pub fn with_config(config: &GlobalConfig) -> Self {
+        Self::build(
+            PhysicsSettings::from(config),
+            FixedTime::new(config.fixed_time_step, config.max_fixed_steps),
+            config.frame_delay,
+        )
+    }
+
+    fn build(physics_settings: PhysicsSettings, fixed_time: FixedTime, frame_delay: Duration) -> Self {
+        let mut world = World::new();
+
+        // Registering the built-in systems on a new world can't fail
+        world.add_resource(fixed_time);
+        hierarchy::new(&mut world).expect("Could not register the transform propagation");
+        physics::new(&mut world, physics_settings).expect("Could not register the physics step");
+
         Self {
-            world: World::new(),
+            world,
+            frame_delay,
+            last_frame: Instant::now(),
         }
     }
 
     pub fn start(&self) {}
 
-    pub fn update(&mut self) {
+    /// Runs a frame: the `PreUpdate` stage, then the `FixedUpdate` stage once per fixed step
+    /// completed since the last frame, and then the rest of the stages.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    ///
+    /// # Errors
+    ///
+    /// If a system fails, its error is returned.
+    pub fn update(&mut self) -> Result<()> {
         let frame_start = Instant::now();
+        let elapsed = frame_start - self.last_frame;
+        self.last_frame = frame_start;
+
+        self.step(elapsed)?;
 
         self.wait(frame_start);
+
+        Ok(())
+    }
+
+    /// Advances the game by the given elapsed time, without waiting for the next frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - The time since the last frame.
+    ///
+    /// # Returns
+    ///
+    /// A result that contains nothing if succeeds or an error if it fails.
+    pub fn step(&mut self, elapsed: Duration) -> Result<()> {
+        let steps = self
+            .world
+            .get_resource_mut::<FixedTime>()
+            .map(|fixed_time| fixed_time.accumulate(elapsed))
+            .unwrap_or(0);
+
+        self.world.run_frame(steps)
     }
 
     pub fn wait(&self, frame_start: Instant) {
         // Control frame time
         let frame_time = frame_start.elapsed();
-        if frame_time < Duration::from_secs_f32(1.0 / 60.0) {
-            std::thread::sleep(Duration::from_secs_f32(1.0 / 60.0) - frame_time);
+        if frame_time < self.frame_delay {
+            std::thread::sleep(self.frame_delay - frame_time);
         }
     }
 }
@@ -21,7 +21,13 @@ pub struct GlobalConfig {
     /// Target frames per second of the game.
     pub fps: i32,
     /// Delay between frames based on the target FPS.
-    pub frame_delay: Duration
+    pub frame_delay: Duration,
+    /// Time every fixed step, like the physics step, advances the simulation by.
+    /// It is independent of the FPS and defaults to 1/60 of a second.
+    pub fixed_time_step: Duration,
+    /// Most fixed steps run in a single frame, so that slow frames don't make the game fall
+    /// further and further behind.
+    pub max_fixed_steps: u32
 }
 
 impl GlobalConfig {
@@ -43,7 +49,9 @@ impl GlobalConfig {
             title,
             gravity,
             fps,
-            frame_delay: Duration::from_secs_f32(1.0 / fps as f32)
+            frame_delay: Duration::from_secs_f32(1.0 / fps as f32),
+            fixed_time_step: Duration::from_secs_f32(1.0 / 60.0),
+            max_fixed_steps: 5
         }
     }
 }
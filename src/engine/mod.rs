@@ -1,4 +1,5 @@
 pub mod input;
+pub mod time;
 
 //sdl2 key events
 pub use sdl2::keyboard::Keycode;
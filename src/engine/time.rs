@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+/// The clock of the fixed-timestep stage, stored as a resource.
+///
+/// Every frame, the elapsed time is added to an accumulator, and the `FixedUpdate` stage runs once
+/// for every whole step the accumulator holds. What is left is less than a step, and `alpha` tells
+/// how far the frame is between the last two steps, so that rendering can blend the previous and
+/// current transforms.
+///
+/// To avoid the spiral of death, where slow steps make every frame need even more steps, at most
+/// `max_steps` run per frame and the whole steps that didn't fit are dropped. The fraction of a
+/// step left over is kept, so `alpha` stays consistent with the time that actually passed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedTime {
+    /// The time every step advances the simulation by.
+    step: Duration,
+    /// The most steps that run in a single frame.
+    max_steps: u32,
+    /// The elapsed time that wasn't consumed by a step yet.
+    accumulator: Duration,
+}
+
+impl Default for FixedTime {
+    fn default() -> Self {
+        Self::new(Duration::from_secs_f32(1.0 / 60.0), 5)
+    }
+}
+
+impl FixedTime {
+    /// Creates a new fixed-timestep clock.
+    ///
+    /// # Arguments
+    ///
+    /// * `step` - The time every step advances the simulation by. It can't be zero.
+    /// * `max_steps` - The most steps that run in a single frame. At least one step runs.
+    ///
+    /// # Returns
+    ///
+    /// A new `FixedTime` struct.
+    pub fn new(step: Duration, max_steps: u32) -> Self {
+        Self {
+            step: step.max(Duration::from_nanos(1)),
+            max_steps: max_steps.max(1),
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    /// Gets the time every step advances the simulation by.
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Gets the most steps that run in a single frame.
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+    /// Adds the elapsed time of a frame and consumes the steps it completes.
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - The time since the last frame.
+    ///
+    /// # Returns
+    ///
+    /// How many times the fixed-timestep stage has to run this frame.
+    pub fn accumulate(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step && steps < self.max_steps {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        // The steps that didn't fit in this frame would only make the next ones fall further behind
+        if self.accumulator >= self.step {
+            let remainder = self.accumulator.as_nanos() % self.step.as_nanos();
+            self.accumulator = Duration::from_nanos(remainder as u64);
+        }
+
+        steps
+    }
+
+    /// Gets how far the current frame is between the last step and the next one, from 0 to 1.
+    ///
+    /// # Returns
+    ///
+    /// The interpolation factor between the previous and current transforms.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn steps_are_run_for_the_accumulated_time() {
+        let mut time = FixedTime::new(Duration::from_millis(10), 5);
+
+        assert_eq!(time.accumulate(Duration::from_millis(4)), 0);
+        assert_approx_eq!(time.alpha(), 0.4, 1e-5);
+
+        assert_eq!(time.accumulate(Duration::from_millis(21)), 2);
+        assert_approx_eq!(time.alpha(), 0.5, 1e-5);
+    }
+
+    #[test]
+    fn long_frames_are_capped() {
+        let mut time = FixedTime::new(Duration::from_millis(10), 3);
+
+        assert_eq!(time.accumulate(Duration::from_millis(1005)), 3);
+        assert_approx_eq!(time.alpha(), 0.5, 1e-5);
+
+        assert_eq!(time.accumulate(Duration::from_millis(10)), 1);
+        assert_approx_eq!(time.alpha(), 0.5, 1e-5);
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use eyre::Result;
+
+use axle_ecs::prelude::*;
+use axle_ecs::resources::Resources;
+use axle_ecs::systems::schedule::Stage;
+use axle_math::transform::{GlobalTransform, Transform};
+
+/// Registers the transform components, making them serializable, and the systems that compute
+/// the `GlobalTransform` of every entity from its `Transform` and the ones of its ancestors.
+///
+/// The systems run in the `PostUpdate` stage, after the game logic moved the entities. The
+/// entities that have a `Transform` but no `GlobalTransform` are given one before propagating.
+pub fn new(world: &mut World) -> Result<()> {
+    // The global transforms are saved too so that the loaded entities are propagated again
+    world.register_serializable_component::<Transform>("Transform");
+    world.register_serializable_component::<GlobalTransform>("GlobalTransform");
+    world.registry.register_debug::<Transform>();
+    world.registry.register_debug::<GlobalTransform>();
+
+    world
+        .create_system(insert_global_transforms)
+        .in_stage(Stage::PostUpdate)?
+        .with_name("global_transform_insertion")?
+        .with_access::<&Transform>()?
+        .with_filter::<Without<GlobalTransform>>()?;
+
+    world
+        .create_system(propagate_transforms)
+        .in_stage(Stage::PostUpdate)?
+        .with_name("transform_propagation")?
+        .after("global_transform_insertion")?
+        .with_access::<(&Transform, &mut GlobalTransform, Option<&Parent>, Option<&Children>)>()?;
+
+    Ok(())
+}
+
+/// Gives a `GlobalTransform` to every entity that has a `Transform` but no `GlobalTransform`, so
+/// that it is propagated, and its children aren't treated as root entities.
+pub fn insert_global_transforms(entities: &Vec<QueryEntity>, mut commands: ResMut<Commands>) -> Result<()> {
+    for entity in entities {
+        let global = GlobalTransform::from(&*entity.get_component::<Transform>()?);
+        commands.add_component_to_entity_by_id(global, entity.id);
+    }
+
+    Ok(())
+}
+
+/// Computes the `GlobalTransform` of every entity that has both a `Transform` and a
+/// `GlobalTransform`, walking down the hierarchy from the root entities.
+///
+/// The `Transform` of a child is relative to its parent. An entity whose parent has no
+/// transform is treated as a root entity.
+pub fn propagate_transforms(entities: &Vec<QueryEntity>, _resources: &mut Resources) -> Result<()> {
+    let by_id: HashMap<Entity, &QueryEntity> = entities.iter().map(|entity| (entity.id, entity)).collect();
+
+    for entity in entities {
+        let is_root = entity
+            .get_component::<Parent>()
+            .map(|parent| !by_id.contains_key(&parent.get()))
+            .unwrap_or(true);
+
+        if !is_root {
+            continue;
+        }
+
+        let global = GlobalTransform::from(&*entity.get_component::<Transform>()?);
+        *entity.get_component_mut::<GlobalTransform>()? = global;
+
+        propagate_to_children(entity, &global, &by_id)?;
+    }
+
+    Ok(())
+}
+
+/// Computes the `GlobalTransform` of the descendants of an entity.
+fn propagate_to_children(
+    entity: &QueryEntity,
+    parent_global: &GlobalTransform,
+    by_id: &HashMap<Entity, &QueryEntity>,
+) -> Result<()> {
+    let children = match entity.get_component::<Children>() {
+        Ok(children) => children.to_vec(),
+        Err(_) => return Ok(()),
+    };
+
+    for child in children {
+        // The children without transforms are handled as root entities
+        let Some(child) = by_id.get(&child) else {
+            continue;
+        };
+
+        let global = parent_global.mul_transform(&*child.get_component::<Transform>()?);
+        *child.get_component_mut::<GlobalTransform>()? = global;
+
+        propagate_to_children(child, &global, by_id)?;
+    }
+
+    Ok(())
+}
@@ -2,6 +2,7 @@
 pub mod application;
 pub mod config;
 pub mod engine;
+pub mod hierarchy;
 pub mod physics;
 pub mod utils;
 pub mod view;
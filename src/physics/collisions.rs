@@ -1,6 +1,177 @@
+use axle_math::transform::Transform;
 use axle_math::vector::Vector2;
 use core::f32;
 
+use crate::physics::shape::Shape;
+
+/// Calculates the intersection between two shapes, whatever their kind, if they intersect.
+///
+/// # Arguments
+///
+/// * `shape_a` - The first shape.
+/// * `transform_a` - The transform of the first shape.
+/// * `shape_b` - The second shape.
+/// * `transform_b` - The transform of the second shape.
+///
+/// # Returns
+///
+/// If the shapes intersect, returns the collision normal, pointing from the first shape to the
+/// second one, and depth as a tuple. Otherwise, returns None.
+pub fn intersect_shapes(
+    shape_a: &Shape,
+    transform_a: &Transform,
+    shape_b: &Shape,
+    transform_b: &Transform,
+) -> Option<(Vector2, f32)> {
+    match (shape_a, shape_b) {
+        (Shape::Circle { radius: radius_a }, Shape::Circle { radius: radius_b }) => {
+            intersect_circles(transform_a.position, *radius_a, transform_b.position, *radius_b)
+        }
+        (Shape::Circle { radius }, Shape::Rectangle { .. }) => {
+            let vertices = shape_b.compute_transformed_vertices(transform_b)?;
+            intersect_circle_polygon(transform_a.position, *radius, &vertices)
+        }
+        (Shape::Rectangle { .. }, Shape::Circle { radius }) => {
+            let vertices = shape_a.compute_transformed_vertices(transform_a)?;
+            intersect_circle_polygon(transform_b.position, *radius, &vertices)
+                .map(|(normal, depth)| (normal * -1.0, depth))
+        }
+        (Shape::Rectangle { .. }, Shape::Rectangle { .. }) => {
+            let vertices_a = shape_a.compute_transformed_vertices(transform_a)?;
+            let vertices_b = shape_b.compute_transformed_vertices(transform_b)?;
+            intersect_polygons(&vertices_a, &vertices_b)
+        }
+    }
+}
+
+/// The distance under which two contact candidates are considered the same.
+const CONTACT_TOLERANCE: f32 = 0.0005;
+
+/// Where and how two shapes touch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactManifold {
+    /// The collision normal, pointing from the first shape to the second one.
+    pub normal: Vector2,
+    /// The penetration depth of the collision.
+    pub depth: f32,
+    /// The contact points in world space. Only the first `contact_count` are valid.
+    pub contacts: [Vector2; 2],
+    /// The number of contact points, 1 or 2. Two points are found when edges of both shapes lie
+    /// against each other.
+    pub contact_count: usize,
+}
+
+impl ContactManifold {
+    /// Returns the valid contact points.
+    pub fn get_contacts(&self) -> &[Vector2] {
+        &self.contacts[..self.contact_count]
+    }
+}
+
+/// Calculates the contact manifold between two shapes, whatever their kind, if they intersect.
+///
+/// # Arguments
+///
+/// * `shape_a` - The first shape.
+/// * `transform_a` - The transform of the first shape.
+/// * `shape_b` - The second shape.
+/// * `transform_b` - The transform of the second shape.
+///
+/// # Returns
+///
+/// If the shapes intersect, returns their contact manifold. Otherwise, returns None.
+pub fn find_contact_manifold(
+    shape_a: &Shape,
+    transform_a: &Transform,
+    shape_b: &Shape,
+    transform_b: &Transform,
+) -> Option<ContactManifold> {
+    // The vertices are transformed once, for both the intersection and the contact points
+    let (normal, depth, contacts, contact_count) = match (shape_a, shape_b) {
+        (Shape::Circle { radius: radius_a }, Shape::Circle { radius: radius_b }) => {
+            let (normal, depth) = intersect_circles(transform_a.position, *radius_a, transform_b.position, *radius_b)?;
+            (normal, depth, [transform_a.position + normal * *radius_a; 2], 1)
+        }
+        (Shape::Circle { radius }, Shape::Rectangle { .. }) => {
+            let vertices = shape_b.compute_transformed_vertices(transform_b)?;
+            let (normal, depth) = intersect_circle_polygon(transform_a.position, *radius, &vertices)?;
+            (normal, depth, [closest_point_on_polygon(transform_a.position, &vertices).0; 2], 1)
+        }
+        (Shape::Rectangle { .. }, Shape::Circle { radius }) => {
+            let vertices = shape_a.compute_transformed_vertices(transform_a)?;
+            let (normal, depth) = intersect_circle_polygon(transform_b.position, *radius, &vertices)?;
+            (normal * -1.0, depth, [closest_point_on_polygon(transform_b.position, &vertices).0; 2], 1)
+        }
+        (Shape::Rectangle { .. }, Shape::Rectangle { .. }) => {
+            let vertices_a = shape_a.compute_transformed_vertices(transform_a)?;
+            let vertices_b = shape_b.compute_transformed_vertices(transform_b)?;
+            let (normal, depth) = intersect_polygons(&vertices_a, &vertices_b)?;
+            let (contacts, contact_count) = polygon_contacts(&vertices_a, &vertices_b);
+            (normal, depth, contacts, contact_count)
+        }
+    };
+
+    Some(ContactManifold { normal, depth, contacts, contact_count })
+}
+
+/// Finds the contact points of two intersecting polygons: the vertices of either polygon that are
+/// the closest to the edges of the other one.
+///
+/// # Returns
+///
+/// The contact points and how many of them are valid.
+fn polygon_contacts(vertices_a: &[Vector2], vertices_b: &[Vector2]) -> ([Vector2; 2], usize) {
+    let mut contacts = [Vector2::ZERO; 2];
+    let mut contact_count = 0;
+    let mut min_distance = f32::MAX;
+
+    for (vertices, edges) in [(vertices_a, vertices_b), (vertices_b, vertices_a)] {
+        for vertex in vertices {
+            let (point, distance) = closest_point_on_polygon(*vertex, edges);
+
+            // A second vertex as close as the first one means that two edges lie against each other
+            if (distance - min_distance).abs() < CONTACT_TOLERANCE {
+                if Vector2::distance(&point, &contacts[0]) >= CONTACT_TOLERANCE {
+                    contacts[1] = point;
+                    contact_count = 2;
+                }
+            } else if distance < min_distance {
+                min_distance = distance;
+                contacts[0] = point;
+                contact_count = 1;
+            }
+        }
+    }
+
+    (contacts, contact_count)
+}
+
+/// Finds the point of the edges of a polygon that is the closest to the given point.
+///
+/// # Returns
+///
+/// The closest point and its distance to the given point.
+fn closest_point_on_polygon(point: Vector2, vertices: &[Vector2]) -> (Vector2, f32) {
+    (0..vertices.len())
+        .map(|i| closest_point_on_segment(point, vertices[i], vertices[(i + 1) % vertices.len()]))
+        .map(|closest| (closest, Vector2::distance(&point, &closest)))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap_or((point, 0.0))
+}
+
+/// Finds the point of the segment from `start` to `end` that is the closest to the given point.
+fn closest_point_on_segment(point: Vector2, start: Vector2, end: Vector2) -> Vector2 {
+    let segment = end - start;
+    let length_squared = segment.sqr_magnitude();
+
+    if length_squared == 0.0 {
+        return start;
+    }
+
+    let t = Vector2::dot(&(point - start), &segment) / length_squared;
+    start + segment * t.clamp(0.0, 1.0)
+}
+
 /// Calculates the intersection point between two circles if they intersect.
 ///
 /// # Arguments
@@ -30,8 +201,12 @@ pub fn intersect_circles(
         return None;
     }
 
-    // Calculate the normal vector of the collision, which points from one circle to the other
-    let normal = (pos_b - pos_a).normalized();
+    // Calculate the normal vector of the collision, which points from one circle to the other.
+    // Circles with the same center are pushed apart vertically
+    let mut normal = pos_b - pos_a;
+    if normal.try_normalize().is_err() {
+        normal = Vector2::UP;
+    }
 
     // Calculate the depth of the collision, which is the distance between the circles at the point of collision
     let depth = radii - distance;
@@ -40,61 +215,276 @@ pub fn intersect_circles(
     Some((normal, depth))
 }
 
-// vertices have to be transformed (rotated and in world position)
-pub fn intersect_polygons(vertices_a: &[Vector2], vertices_b: &[Vector2]) -> bool {
-    fn project_vertices(vertices: &[Vector2], axis: &Vector2, min: &mut f32, max: &mut f32) {
-        *min = f32::MAX;
-        *max = f32::MIN;
+/// Calculates the minimum translation between two convex polygons if they intersect, using the
+/// separating axis theorem.
+///
+/// The vertices have to be transformed, that is rotated and in world position, and in order
+/// around the polygon.
+///
+/// # Arguments
+///
+/// * `vertices_a` - The vertices of the first polygon.
+/// * `vertices_b` - The vertices of the second polygon.
+///
+/// # Returns
+///
+/// If the polygons intersect, returns the collision normal, pointing from the first polygon to the
+/// second one, and depth as a tuple. Otherwise, returns None.
+pub fn intersect_polygons(vertices_a: &[Vector2], vertices_b: &[Vector2]) -> Option<(Vector2, f32)> {
+    // The separating axis, if any, is perpendicular to one of the edges of either polygon
+    let axes = edge_normals(vertices_a).chain(edge_normals(vertices_b));
 
-        for vertex in vertices {
-            let projection = Vector2::scalar_projection(vertex, axis);
-            if projection < *min {
-                *min = projection;
-            }
-            if projection > *max {
-                *max = projection;
-            }
-        }
-    }
+    let (normal, depth) = least_penetration(
+        axes,
+        |axis| project_vertices(vertices_a, axis),
+        |axis| project_vertices(vertices_b, axis),
+    )?;
 
-    for i in 0..vertices_a.len() {
-        let va = vertices_a[i];
-        let vb = vertices_a[(i + 1) % vertices_a.len()];
-        let edge = vb - va;
-        let axis = edge.perpendicular();
+    // Make the normal point from the first polygon to the second one
+    Some((orient(normal, polygon_center(vertices_b) - polygon_center(vertices_a)), depth))
+}
 
-        let mut min_a = 0.0;
-        let mut max_a = 0.0;
-        project_vertices(vertices_a, &axis, &mut min_a, &mut max_a);
+/// Calculates the minimum translation between a circle and a convex polygon if they intersect,
+/// using the separating axis theorem.
+///
+/// # Arguments
+///
+/// * `center` - The center position of the circle.
+/// * `radius` - The radius of the circle.
+/// * `vertices` - The transformed vertices of the polygon, in order around it.
+///
+/// # Returns
+///
+/// If they intersect, returns the collision normal, pointing from the circle to the polygon, and
+/// depth as a tuple. Otherwise, returns None.
+pub fn intersect_circle_polygon(center: Vector2, radius: f32, vertices: &[Vector2]) -> Option<(Vector2, f32)> {
+    // Besides the edges, the circle can only be separated along the axis to the closest vertex
+    let closest_vertex = vertices
+        .iter()
+        .min_by(|a, b| Vector2::distance(a, &center).total_cmp(&Vector2::distance(b, &center)))?;
+    let axes = edge_normals(vertices).chain([*closest_vertex - center]);
+
+    let (normal, depth) = least_penetration(
+        axes,
+        |axis| project_circle(center, radius, axis),
+        |axis| project_vertices(vertices, axis),
+    )?;
+
+    // Make the normal point from the circle to the polygon
+    Some((orient(normal, polygon_center(vertices) - center), depth))
+}
+
+/// Finds the axis along which two shapes overlap the least.
+///
+/// # Arguments
+///
+/// * `axes` - The candidate axes. They don't need to be normalized, and the ones with no length
+///   are skipped.
+/// * `project_a` - Projects the first shape onto a normalized axis, returning its minimum and maximum.
+/// * `project_b` - Projects the second shape onto a normalized axis, returning its minimum and maximum.
+///
+/// # Returns
+///
+/// The normalized axis and the overlap along it, or None if the shapes are separated along one
+/// of the axes.
+fn least_penetration(
+    axes: impl Iterator<Item = Vector2>,
+    project_a: impl Fn(&Vector2) -> (f32, f32),
+    project_b: impl Fn(&Vector2) -> (f32, f32),
+) -> Option<(Vector2, f32)> {
+    let mut normal = None;
+    let mut depth = f32::MAX;
+
+    for mut axis in axes {
+        if axis.try_normalize().is_err() {
+            continue;
+        }
 
-        let mut min_b = 0.0;
-        let mut max_b = 0.0;
-        project_vertices(vertices_b, &axis, &mut min_b, &mut max_b);
+        let (min_a, max_a) = project_a(&axis);
+        let (min_b, max_b) = project_b(&axis);
 
         if min_a >= max_b || min_b >= max_a {
-            return false; // No collision if projections don't overlap on this axis
+            return None; // No collision if projections don't overlap on this axis
+        }
+
+        let axis_depth = f32::min(max_b - min_a, max_a - min_b);
+        if axis_depth < depth {
+            depth = axis_depth;
+            normal = Some(axis);
         }
     }
 
-    for i in 0..vertices_b.len() {
-        let va = vertices_b[i];
-        let vb = vertices_b[(i + 1) % vertices_b.len()];
-        let edge = vb - va;
-        let axis = edge.perpendicular();
+    normal.map(|normal| (normal, depth))
+}
 
-        let mut min_a = 0.0;
-        let mut max_a = 0.0;
-        project_vertices(vertices_a, &axis, &mut min_a, &mut max_a);
+/// Gets the vectors perpendicular to the edges of a polygon, without normalizing them.
+fn edge_normals(vertices: &[Vector2]) -> impl Iterator<Item = Vector2> + '_ {
+    (0..vertices.len()).map(|i| (vertices[(i + 1) % vertices.len()] - vertices[i]).perpendicular())
+}
 
-        let mut min_b = 0.0;
-        let mut max_b = 0.0;
-        project_vertices(vertices_b, &axis, &mut min_b, &mut max_b);
+/// Flips a normal if needed so that it points in the same direction as the given one.
+fn orient(normal: Vector2, direction: Vector2) -> Vector2 {
+    if Vector2::dot(&direction, &normal) < 0.0 {
+        normal * -1.0
+    } else {
+        normal
+    }
+}
 
-        if min_a >= max_b || min_b >= max_a {
-            return false; // No collision if projections don't overlap on this axis
-        }
+/// Projects the vertices of a polygon onto an axis.
+///
+/// # Returns
+///
+/// The minimum and maximum of the projections.
+fn project_vertices(vertices: &[Vector2], axis: &Vector2) -> (f32, f32) {
+    let mut min = f32::MAX;
+    let mut max = f32::MIN;
+
+    for vertex in vertices {
+        let projection = Vector2::scalar_projection(vertex, axis);
+        min = min.min(projection);
+        max = max.max(projection);
     }
 
-    true
+    (min, max)
+}
+
+/// Projects a circle onto a normalized axis.
+///
+/// # Returns
+///
+/// The minimum and maximum of the projection.
+fn project_circle(center: Vector2, radius: f32, axis: &Vector2) -> (f32, f32) {
+    let projection = Vector2::dot(&center, axis);
+
+    (projection - radius, projection + radius)
 }
 
+/// Calculates the arithmetic mean of the vertices of a polygon.
+fn polygon_center(vertices: &[Vector2]) -> Vector2 {
+    let sum = vertices.iter().fold(Vector2::ZERO, |sum, vertex| sum + *vertex);
+
+    sum / vertices.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use axle_math::rotation::Rot2;
+
+    #[test]
+    fn circles_return_the_normal_and_depth() {
+        let (normal, depth) = intersect_circles(Vector2::ZERO, 1.0, Vector2::new(1.5, 0.0), 1.0).unwrap();
+        assert_approx_eq!(normal.x, 1.0, 1e-5);
+        assert_approx_eq!(depth, 0.5, 1e-5);
+
+        assert!(intersect_circles(Vector2::ZERO, 1.0, Vector2::new(2.0, 0.0), 1.0).is_none());
+
+        // Circles with the same center are still pushed apart
+        assert_eq!(intersect_circles(Vector2::ZERO, 1.0, Vector2::ZERO, 1.0), Some((Vector2::UP, 2.0)));
+    }
+
+    #[test]
+    fn polygons_return_the_minimum_translation() {
+        let square = square_at(0.0, 0.0);
+
+        let (normal, depth) = intersect_polygons(&square, &square_at(1.5, 0.2)).unwrap();
+        assert_approx_eq!(normal.x, 1.0, 1e-5);
+        assert_approx_eq!(normal.y, 0.0, 1e-5);
+        assert_approx_eq!(depth, 0.5, 1e-5);
+
+        // The normal always points from the first polygon to the second one
+        let (normal, depth) = intersect_polygons(&square, &square_at(-0.2, -1.7)).unwrap();
+        assert_approx_eq!(normal.y, -1.0, 1e-5);
+        assert_approx_eq!(depth, 0.3, 1e-5);
+
+        assert!(intersect_polygons(&square, &square_at(2.0, 0.0)).is_none());
+        assert!(intersect_polygons(&square, &square_at(2.5, 2.5)).is_none());
+    }
+
+    #[test]
+    fn rotated_polygons_are_separated_by_their_own_edges() {
+        let diamond = Shape::create_rectangle(2.0, 2.0).compute_transformed_vertices(&Transform {
+            position: Vector2::new(2.3, 0.0),
+            rotation: Rot2::FRAC_PI_4,
+        });
+
+        // The corner of the diamond is at x = 2.3 - √2, inside the square
+        let (normal, depth) = intersect_polygons(&square_at(0.0, 0.0), &diamond.unwrap()).unwrap();
+        assert_approx_eq!(normal.x, 1.0, 1e-5);
+        assert_approx_eq!(depth, 1.0 - (2.3 - std::f32::consts::SQRT_2), 1e-5);
+    }
+
+    #[test]
+    fn circles_collide_with_polygons() {
+        let square = square_at(0.0, 0.0);
+
+        // Against an edge
+        let (normal, depth) = intersect_circle_polygon(Vector2::new(0.0, 1.5), 1.0, &square).unwrap();
+        assert_approx_eq!(normal.y, -1.0, 1e-5);
+        assert_approx_eq!(depth, 0.5, 1e-5);
+
+        // Against a corner, along the axis to the closest vertex
+        let (normal, depth) = intersect_circle_polygon(Vector2::new(1.5, 1.5), 1.0, &square).unwrap();
+        assert_approx_eq!(normal.x, -std::f32::consts::FRAC_1_SQRT_2, 1e-5);
+        assert_approx_eq!(normal.y, -std::f32::consts::FRAC_1_SQRT_2, 1e-5);
+        assert_approx_eq!(depth, 1.0 - std::f32::consts::FRAC_1_SQRT_2, 1e-5);
+
+        // Close to the corner, but outside the circle
+        assert!(intersect_circle_polygon(Vector2::new(1.8, 1.8), 1.0, &square).is_none());
+
+        // With the center on a vertex
+        assert!(intersect_circle_polygon(Vector2::new(1.0, 1.0), 0.5, &square).is_some());
+    }
+
+    #[test]
+    fn shapes_of_any_kind_collide() {
+        let circle = Shape::Circle { radius: 1.0 };
+        let rectangle = Shape::create_rectangle(2.0, 2.0);
+        let left = Transform::from_xy(0.0, 0.0);
+        let right = Transform::from_xy(1.5, 0.0);
+
+        for (shape_a, shape_b) in [(&circle, &circle), (&circle, &rectangle), (&rectangle, &circle), (&rectangle, &rectangle)] {
+            let (normal, depth) = intersect_shapes(shape_a, &left, shape_b, &right).unwrap();
+            assert_approx_eq!(normal.x, 1.0, 1e-5);
+            assert_approx_eq!(depth, 0.5, 1e-5);
+        }
+    }
+
+    #[test]
+    fn manifolds_have_one_or_two_contacts() {
+        let square = Shape::create_rectangle(2.0, 2.0);
+        let circle = Shape::Circle { radius: 1.0 };
+        let origin = Transform::IDENTITY;
+
+        // Stacked squares touch along an edge
+        let manifold = find_contact_manifold(&square, &origin, &square, &Transform::from_xy(0.5, 1.8)).unwrap();
+        let mut contacts = manifold.get_contacts().to_vec();
+        contacts.sort_by(|a, b| a.x.total_cmp(&b.x));
+        assert_eq!(contacts.len(), 2);
+        assert_approx_eq!(contacts[0].x, -0.5, 1e-5);
+        assert_approx_eq!(contacts[0].y, 1.0, 1e-5);
+        assert_approx_eq!(contacts[1].x, 1.0, 1e-5);
+        assert_approx_eq!(contacts[1].y, 0.8, 1e-5);
+
+        // A corner touches a single point, on the edge it went through
+        let corner = Transform { position: Vector2::new(2.3, 0.0), rotation: Rot2::FRAC_PI_4 };
+        let manifold = find_contact_manifold(&square, &origin, &square, &corner).unwrap();
+        assert_eq!(manifold.get_contacts().len(), 1);
+        assert_approx_eq!(manifold.get_contacts()[0].x, 1.0, 1e-5);
+        assert_approx_eq!(manifold.get_contacts()[0].y, 0.0, 1e-5);
+
+        // Circles touch on their surface, in both orders
+        let manifold = find_contact_manifold(&circle, &origin, &circle, &Transform::from_xy(0.0, 1.5)).unwrap();
+        assert_eq!(manifold.get_contacts(), &[Vector2::new(0.0, 1.0)]);
+
+        let manifold = find_contact_manifold(&square, &origin, &circle, &Transform::from_xy(1.5, 0.5)).unwrap();
+        assert_eq!(manifold.get_contacts(), &[Vector2::new(1.0, 0.5)]);
+        assert_approx_eq!(manifold.normal.x, 1.0, 1e-5);
+    }
+
+    fn square_at(x: f32, y: f32) -> [Vector2; 4] {
+        Shape::create_rectangle(2.0, 2.0).compute_transformed_vertices(&Transform::from_xy(x, y)).unwrap()
+    }
+}
@@ -0,0 +1,201 @@
+use eyre::Result;
+
+use axle_ecs::prelude::*;
+use axle_ecs::systems::param::Res;
+use axle_math::transform::Transform;
+use axle_math::vector::Vector2;
+
+use crate::config::GlobalConfig;
+use crate::engine::time::FixedTime;
+use crate::physics::rigid_body::RigidBody;
+
+/// The settings of the physics step, stored as a resource.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsSettings {
+    /// The acceleration applied to every non-static rigid body.
+    pub gravity: Vector2,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            gravity: Vector2::DOWN * 9.81,
+        }
+    }
+}
+
+impl From<&GlobalConfig> for PhysicsSettings {
+    /// Takes the gravity of the configuration as a downwards acceleration.
+    fn from(config: &GlobalConfig) -> Self {
+        Self {
+            gravity: Vector2::DOWN * config.gravity,
+        }
+    }
+}
+
+/// The transform an entity had before the last physics step.
+///
+/// The physics step runs at a fixed rate, so the frames are usually drawn between two steps.
+/// Adding this component to a rigid body lets the rendering blend both transforms with the
+/// `alpha` of the `FixedTime` resource, instead of showing the body stutter.
+#[derive(Debug, Clone)]
+pub struct PreviousTransform(pub Transform);
+
+impl PreviousTransform {
+    /// Blends the previous transform with the current one.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The transform after the last physics step.
+    /// * `alpha` - How far the frame is between the previous and current transforms, from 0 to 1.
+    ///
+    /// # Returns
+    ///
+    /// The transform to draw the entity with.
+    pub fn interpolate(&self, current: &Transform, alpha: f32) -> Transform {
+        Transform {
+            position: Vector2::lerp(&self.0.position, &current.position, alpha),
+            rotation: self.0.rotation.nlerp(current.rotation, alpha),
+        }
+    }
+}
+
+/// Advances a rigid body and its transform by a time step using semi-implicit Euler: the
+/// velocities are updated first, and the new velocities move the transform.
+///
+/// The forces accumulated in the body are applied and then cleared. Static bodies are not moved.
+///
+/// # Arguments
+///
+/// * `body` - The rigid body.
+/// * `transform` - The transform of the rigid body.
+/// * `gravity` - The acceleration of gravity.
+/// * `time_step` - The time, in seconds, to advance the body by.
+pub fn integrate(body: &mut RigidBody, transform: &mut Transform, gravity: Vector2, time_step: f32) {
+    if body.is_static {
+        body.force = Vector2::ZERO;
+        return;
+    }
+
+    // A body without mass isn't affected by forces, only by gravity
+    let mut acceleration = gravity;
+    if body.mass > 0.0 {
+        acceleration = acceleration + body.force / body.mass;
+    }
+
+    body.linear_velocity = body.linear_velocity + acceleration * time_step;
+    body.force = Vector2::ZERO;
+
+    transform.translate(&(body.linear_velocity * time_step));
+    transform.rotate(body.angular_velocity * time_step);
+}
+
+/// Integrates every entity that has both a `Transform` and a `RigidBody` by the step of the
+/// `FixedTime` resource, saving the transform it had before in its `PreviousTransform`, if any.
+pub fn integrate_bodies(entities: &Vec<QueryEntity>, settings: Res<PhysicsSettings>, time: Res<FixedTime>) -> Result<()> {
+    let time_step = time.step().as_secs_f32();
+
+    for entity in entities {
+        let (mut transform, mut body, previous) =
+            entity.get_components::<(&mut Transform, &mut RigidBody, Option<&mut PreviousTransform>)>()?;
+
+        if let Some(mut previous) = previous {
+            previous.0 = transform.clone();
+        }
+
+        // Static bodies don't move, so their transforms aren't written and don't match `Changed`
+        if body.is_static {
+            body.force = Vector2::ZERO;
+            continue;
+        }
+
+        integrate(&mut body, &mut transform, settings.gravity, time_step);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use axle_ecs::systems::schedule::Stage;
+    use axle_math::rotation::Rot2;
+
+    #[test]
+    fn free_fall_follows_the_analytic_result() {
+        let gravity = Vector2::new(0.0, -10.0);
+        let time_step = 1.0 / 60.0;
+        let steps = 120;
+
+        let mut body = RigidBody::new(2.0, 0.0, false);
+        body.linear_velocity = Vector2::new(3.0, 5.0);
+        let mut transform = Transform::IDENTITY;
+
+        for _ in 0..steps {
+            integrate(&mut body, &mut transform, gravity, time_step);
+        }
+
+        // Semi-implicit Euler lands on x(t) = v0·t + g·t²/2 plus a drift of g·t·dt/2
+        let time = steps as f32 * time_step;
+        assert_approx_eq!(body.linear_velocity.y, 5.0 - 10.0 * time, 1e-4);
+        assert_approx_eq!(transform.position.x, 3.0 * time, 1e-4);
+        assert_approx_eq!(transform.position.y, 5.0 * time - 5.0 * time * time - 5.0 * time * time_step, 1e-3);
+    }
+
+    #[test]
+    fn forces_and_rotation_are_applied_once() {
+        let mut body = RigidBody::new(4.0, 0.0, false);
+        body.angular_velocity = std::f32::consts::PI;
+        body.add_force(Vector2::new(8.0, 0.0));
+        let mut transform = Transform::IDENTITY;
+
+        integrate(&mut body, &mut transform, Vector2::ZERO, 0.5);
+        integrate(&mut body, &mut transform, Vector2::ZERO, 0.5);
+
+        assert_eq!(body.linear_velocity, Vector2::new(1.0, 0.0));
+        assert_eq!(transform.position, Vector2::new(1.0, 0.0));
+        assert_approx_eq!(transform.rotation.as_radians().abs(), std::f32::consts::PI, 1e-5);
+    }
+
+    #[test]
+    fn bodies_only_move_in_the_fixed_steps() -> Result<()> {
+        let mut world = World::new();
+        crate::physics::new(&mut world, PhysicsSettings::default())?;
+
+        let ground = world
+            .create_entity()
+            .with_component(Transform::from_rotation(Rot2::FRAC_PI_4))?
+            .with_component(RigidBody::new(0.0, 0.0, true))?
+            .entity();
+        let ball = world
+            .create_entity()
+            .with_component(Transform::from_xy(0.0, 10.0))?
+            .with_component(RigidBody::new(1.0, 0.0, false))?
+            .with_component(PreviousTransform(Transform::IDENTITY))?
+            .entity();
+
+        world.run_all_systems()?;
+        assert_eq!(world.query::<&Transform>()?.get(ball).unwrap().position, Vector2::new(0.0, 10.0));
+
+        world.run_stage(Stage::FixedUpdate)?;
+
+        let query = world.query::<(&Transform, &PreviousTransform)>()?;
+        let (current, previous) = query.get(ball).unwrap();
+        assert!(current.position.y < 10.0);
+        assert_eq!(previous.0.position, Vector2::new(0.0, 10.0));
+        assert_approx_eq!(previous.interpolate(&current, 0.5).position.y, (current.position.y + 10.0) / 2.0, 1e-5);
+        drop((current, previous, query));
+
+        assert_eq!(world.query::<&Transform>()?.get(ground).unwrap().position, Vector2::ZERO);
+
+        // Only the transforms that were written are reported as changed
+        world.run_stage(Stage::FixedUpdate)?;
+
+        let query = world.query_filtered::<Entity, Changed<Transform>>()?;
+        assert!(query.get(ball).is_some());
+        assert!(query.get(ground).is_none());
+
+        Ok(())
+    }
+}
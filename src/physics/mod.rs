@@ -1,3 +1,47 @@
 pub mod rigid_body;
 pub mod shape;
-pub mod collisions;
\ No newline at end of file
+pub mod collisions;
+pub mod integration;
+pub mod solver;
+
+use eyre::Result;
+
+use axle_ecs::World;
+use axle_ecs::systems::schedule::Stage;
+use axle_math::transform::Transform;
+
+use crate::engine::time::FixedTime;
+use crate::physics::integration::{integrate_bodies, PhysicsSettings, PreviousTransform};
+use crate::physics::rigid_body::RigidBody;
+use crate::physics::shape::Shape;
+use crate::physics::solver::resolve_collisions;
+
+/// Adds the physics settings as a resource and registers the systems that move every entity with a
+/// `Transform` and a `RigidBody`, and resolve the collisions between the ones that also have a
+/// `Shape`.
+///
+/// The systems run in the `FixedUpdate` stage, once per step of the `FixedTime` resource, so the
+/// simulation doesn't depend on the frame rate. A default `FixedTime` is added if the world doesn't
+/// have one yet.
+pub fn new(world: &mut World, settings: PhysicsSettings) -> Result<()> {
+    world.add_resource(settings);
+
+    if world.get_resource::<FixedTime>().is_none() {
+        world.add_resource(FixedTime::default());
+    }
+
+    world
+        .create_system(integrate_bodies)
+        .in_stage(Stage::FixedUpdate)?
+        .with_name("physics_step")?
+        .with_access::<(&mut Transform, &mut RigidBody, Option<&mut PreviousTransform>)>()?;
+
+    world
+        .create_system(resolve_collisions)
+        .in_stage(Stage::FixedUpdate)?
+        .with_name("collision_resolution")?
+        .after("physics_step")?
+        .with_access::<(&mut Transform, &mut RigidBody, &Shape)>()?;
+
+    Ok(())
+}
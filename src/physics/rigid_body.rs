@@ -3,11 +3,15 @@ use crate::physics::shape::Shape;
 
 /// A rigid body is an object that does not deform when it moves.
 /// It is the simplest type of physics object in the engine.
+#[derive(Debug, Clone)]
 pub struct RigidBody {
     /// The linear velocity of the rigid body.
     pub linear_velocity: Vector2,
     /// The angular velocity of the rigid body.
     pub angular_velocity: f32,
+    /// The force accumulated since the last physics step. It is applied and cleared by the
+    /// next step.
+    pub force: Vector2,
 
     /// The mass of the rigid body.
     pub mass: f32,
@@ -39,9 +43,54 @@ impl RigidBody {
         Self {
             linear_velocity: Vector2::new(0.0, 0.0), // initialize linear velocity to (0, 0)
             angular_velocity: 0_f32, // initialize angular velocity to 0
+            force: Vector2::ZERO,
             mass,
             restitution: restitution.clamp(0.0, 1.0), // clamp restitution between 0 and 1
             is_static
         }
     }
+
+    /// Returns the inverse of the mass of the rigid body. Static bodies and bodies without mass
+    /// behave as if their mass was infinite, so their inverse mass is 0.
+    ///
+    /// # Returns
+    ///
+    /// The inverse mass of the rigid body.
+    pub fn inverse_mass(&self) -> f32 {
+        if self.is_static || self.mass <= 0.0 {
+            0.0
+        } else {
+            1.0 / self.mass
+        }
+    }
+
+    /// Returns the inverse of the moment of inertia of the rigid body with the given shape. Like
+    /// with `inverse_mass`, static bodies and bodies without mass can't be rotated by collisions,
+    /// so their inverse moment of inertia is 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `shape` - The shape of the rigid body.
+    ///
+    /// # Returns
+    ///
+    /// The inverse moment of inertia of the rigid body.
+    pub fn inverse_inertia(&self, shape: &Shape) -> f32 {
+        let inertia = shape.get_moment_of_inertia(self.mass);
+
+        if self.is_static || inertia <= 0.0 {
+            0.0
+        } else {
+            1.0 / inertia
+        }
+    }
+
+    /// Adds a force to the rigid body, applied in the next physics step.
+    ///
+    /// # Arguments
+    ///
+    /// * `force` - The force to add.
+    pub fn add_force(&mut self, force: Vector2) {
+        self.force = self.force + force;
+    }
 }
\ No newline at end of file
@@ -79,6 +79,25 @@ impl Shape {
         }
     }
 
+    /// Calculates the vertices of the shape, if it is a rectangle, rotated and moved by a transform.
+    /// Unlike `update_transform_vertices`, it doesn't use nor update the cached vertices.
+    ///
+    /// # Arguments
+    ///
+    /// * `transform` - The transform of the shape.
+    ///
+    /// # Returns
+    ///
+    /// The vertices in world space, or `None` if it is not a rectangle.
+    pub fn compute_transformed_vertices(&self, transform: &Transform) -> Option<[Vector2; 4]> {
+        match self {
+            Shape::Rectangle { vertices, .. } => {
+                Some(vertices.map(|v| transform.transform_point(&transform.transform_vector(&v))))
+            }
+            _ => None,
+        }
+    }
+
     pub fn get_transform_vertices(&self) -> Option<[Vector2; 4]> {
         match self {
             Shape::Rectangle {
@@ -100,6 +119,21 @@ impl Shape {
         }
     }
 
+    /// Returns the moment of inertia of the shape around its center, for a uniform density.
+    ///
+    /// # Arguments
+    ///
+    /// * `mass` - The mass of the body with the shape.
+    ///
+    /// # Returns
+    /// The moment of inertia of the shape.
+    pub fn get_moment_of_inertia(&self, mass: f32) -> f32 {
+        match self {
+            Shape::Rectangle { width, height, .. } => mass * (width * width + height * height) / 12.0,
+            Shape::Circle { radius } => mass * radius * radius / 2.0,
+        }
+    }
+
     /// Returns the radius of the shape, if it is a circle.
     ///
     /// # Returns
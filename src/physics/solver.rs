@@ -0,0 +1,236 @@
+use eyre::Result;
+
+use axle_ecs::prelude::*;
+use axle_math::transform::Transform;
+use axle_math::vector::Vector2;
+
+use crate::physics::collisions::{find_contact_manifold, ContactManifold};
+use crate::physics::rigid_body::RigidBody;
+use crate::physics::shape::Shape;
+
+/// Resolves the collision between two rigid bodies with impulses.
+///
+/// At every contact point where the bodies are moving towards each other, an impulse is applied to
+/// both along the normal, using the lowest restitution of the two. An impulse away from the center
+/// of a body also changes its angular velocity, depending on its moment of inertia. Then the bodies
+/// are moved apart along the normal until they no longer penetrate, each one by a share of the
+/// depth inversely proportional to its mass. Static bodies have an infinite mass and moment of
+/// inertia, so they are neither moved nor pushed.
+///
+/// # Arguments
+///
+/// * `body_a` - The first rigid body.
+/// * `transform_a` - The transform of the first rigid body.
+/// * `inverse_inertia_a` - The inverse moment of inertia of the first rigid body.
+/// * `body_b` - The second rigid body.
+/// * `transform_b` - The transform of the second rigid body.
+/// * `inverse_inertia_b` - The inverse moment of inertia of the second rigid body.
+/// * `manifold` - The contact manifold, with the normal pointing from the first body to the second one.
+pub fn resolve_collision(
+    body_a: &mut RigidBody,
+    transform_a: &mut Transform,
+    inverse_inertia_a: f32,
+    body_b: &mut RigidBody,
+    transform_b: &mut Transform,
+    inverse_inertia_b: f32,
+    manifold: &ContactManifold,
+) {
+    let normal = manifold.normal;
+    let inverse_mass_a = body_a.inverse_mass();
+    let inverse_mass_b = body_b.inverse_mass();
+    let inverse_mass_sum = inverse_mass_a + inverse_mass_b;
+
+    // Two bodies with infinite mass can't push each other
+    if inverse_mass_sum == 0.0 {
+        return;
+    }
+
+    let restitution = f32::min(body_a.restitution, body_b.restitution);
+
+    // The impulses are computed from the velocities and positions before any of them is applied
+    let approaching: Vec<(Vector2, Vector2, f32)> = manifold
+        .get_contacts()
+        .iter()
+        .filter_map(|contact| {
+            let arm_a = *contact - transform_a.position;
+            let arm_b = *contact - transform_b.position;
+
+            let velocity_a = body_a.linear_velocity + arm_a.perpendicular() * body_a.angular_velocity;
+            let velocity_b = body_b.linear_velocity + arm_b.perpendicular() * body_b.angular_velocity;
+            let normal_velocity = Vector2::dot(&(velocity_b - velocity_a), &normal);
+
+            // The bodies are already moving apart at this point
+            (normal_velocity <= 0.0).then_some((arm_a, arm_b, normal_velocity))
+        })
+        .collect();
+
+    // The impulse is shared between the contact points that are approaching
+    let shares = approaching.len() as f32;
+
+    for (arm_a, arm_b, normal_velocity) in approaching {
+        let arm_a_normal = Vector2::cross(&arm_a, &normal);
+        let arm_b_normal = Vector2::cross(&arm_b, &normal);
+        let denominator = inverse_mass_sum
+            + arm_a_normal * arm_a_normal * inverse_inertia_a
+            + arm_b_normal * arm_b_normal * inverse_inertia_b;
+
+        let impulse = normal * (-(1.0 + restitution) * normal_velocity / denominator / shares);
+
+        body_a.linear_velocity = body_a.linear_velocity - impulse * inverse_mass_a;
+        body_a.angular_velocity -= Vector2::cross(&arm_a, &impulse) * inverse_inertia_a;
+        body_b.linear_velocity = body_b.linear_velocity + impulse * inverse_mass_b;
+        body_b.angular_velocity += Vector2::cross(&arm_b, &impulse) * inverse_inertia_b;
+    }
+
+    // Separate the bodies once the contact points were used
+    let correction = normal * (manifold.depth / inverse_mass_sum);
+    transform_a.translate(&(correction * -inverse_mass_a));
+    transform_b.translate(&(correction * inverse_mass_b));
+}
+
+/// Detects and resolves the collisions between every pair of entities that have a `Transform`, a
+/// `RigidBody` and a `Shape`.
+pub fn resolve_collisions(entities: &Vec<QueryEntity>) -> Result<()> {
+    for (index, entity_a) in entities.iter().enumerate() {
+        for entity_b in &entities[index + 1..] {
+            let (mut transform_a, mut body_a, shape_a) = entity_a.get_components::<(&mut Transform, &mut RigidBody, &Shape)>()?;
+            let (mut transform_b, mut body_b, shape_b) = entity_b.get_components::<(&mut Transform, &mut RigidBody, &Shape)>()?;
+
+            if body_a.is_static && body_b.is_static {
+                continue;
+            }
+
+            if let Some(manifold) = find_contact_manifold(&shape_a, &transform_a, &shape_b, &transform_b) {
+                let inverse_inertia_a = body_a.inverse_inertia(&shape_a);
+                let inverse_inertia_b = body_b.inverse_inertia(&shape_b);
+
+                resolve_collision(
+                    &mut body_a,
+                    &mut transform_a,
+                    inverse_inertia_a,
+                    &mut body_b,
+                    &mut transform_b,
+                    inverse_inertia_b,
+                    &manifold,
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+    use axle_ecs::systems::schedule::Stage;
+    use crate::physics::integration::PhysicsSettings;
+
+    #[test]
+    fn elastic_collisions_exchange_velocities() {
+        let mut body_a = RigidBody::new(1.0, 1.0, false);
+        body_a.linear_velocity = Vector2::new(2.0, 0.0);
+        let mut body_b = RigidBody::new(1.0, 1.0, false);
+        let mut transform_a = Transform::from_xy(0.0, 0.0);
+        let mut transform_b = Transform::from_xy(1.8, 0.0);
+
+        let circle = Shape::Circle { radius: 1.0 };
+        let manifold = find_contact_manifold(&circle, &transform_a, &circle, &transform_b).unwrap();
+        let inverse_inertia = body_a.inverse_inertia(&circle);
+        resolve_collision(&mut body_a, &mut transform_a, inverse_inertia, &mut body_b, &mut transform_b, inverse_inertia, &manifold);
+
+        assert_approx_eq!(body_a.linear_velocity.x, 0.0, 1e-5);
+        assert_approx_eq!(body_b.linear_velocity.x, 2.0, 1e-5);
+        assert_approx_eq!(transform_a.position.x, -0.1, 1e-5);
+        assert_approx_eq!(transform_b.position.x, 1.9, 1e-5);
+    }
+
+    #[test]
+    fn static_bodies_have_infinite_mass() {
+        let mut ball = RigidBody::new(3.0, 0.5, false);
+        ball.linear_velocity = Vector2::new(0.0, -4.0);
+        let mut ground = RigidBody::new(1.0, 1.0, true);
+        let mut ball_transform = Transform::from_xy(0.0, 0.8);
+        let mut ground_transform = Transform::IDENTITY;
+
+        // The normal points from the ball to the ground
+        let manifold = ContactManifold {
+            normal: Vector2::DOWN,
+            depth: 0.2,
+            contacts: [Vector2::ZERO; 2],
+            contact_count: 1,
+        };
+        let inverse_inertia = ball.inverse_inertia(&Shape::Circle { radius: 1.0 });
+        resolve_collision(&mut ball, &mut ball_transform, inverse_inertia, &mut ground, &mut ground_transform, 0.0, &manifold);
+
+        assert_approx_eq!(ball.linear_velocity.y, 2.0, 1e-5);
+        assert_eq!(ball.angular_velocity, 0.0);
+        assert_approx_eq!(ball_transform.position.y, 1.0, 1e-5);
+        assert_eq!(ground.linear_velocity, Vector2::ZERO);
+        assert_eq!(ground_transform.position, Vector2::ZERO);
+    }
+
+    #[test]
+    fn off_center_impulses_rotate_the_bodies() {
+        let ball_shape = Shape::Circle { radius: 1.0 };
+        let box_shape = Shape::create_rectangle(2.0, 2.0);
+        let mut ball = RigidBody::new(1.0, 1.0, false);
+        ball.linear_velocity = Vector2::new(3.0, 0.0);
+        let mut crate_box = RigidBody::new(1.0, 1.0, false);
+        let mut ball_transform = Transform::from_xy(-1.9, 0.8);
+        let mut box_transform = Transform::IDENTITY;
+
+        let manifold = find_contact_manifold(&ball_shape, &ball_transform, &box_shape, &box_transform).unwrap();
+        assert_eq!(manifold.get_contacts().len(), 1);
+        assert_approx_eq!(manifold.get_contacts()[0].y, 0.8, 1e-5);
+
+        let ball_inertia = ball.inverse_inertia(&ball_shape);
+        let box_inertia = crate_box.inverse_inertia(&box_shape);
+        resolve_collision(&mut ball, &mut ball_transform, ball_inertia, &mut crate_box, &mut box_transform, box_inertia, &manifold);
+
+        // Hit above its center, the box spins clockwise and takes less of the ball's speed
+        assert!(crate_box.angular_velocity < 0.0);
+        assert!(crate_box.linear_velocity.x > 0.0 && crate_box.linear_velocity.x < 3.0);
+        assert!(ball.linear_velocity.x > 0.0);
+        assert_approx_eq!(ball.angular_velocity, 0.0, 1e-5);
+    }
+
+    #[test]
+    fn bodies_rest_on_the_ground() -> Result<()> {
+        let mut world = World::new();
+        crate::physics::new(&mut world, PhysicsSettings::default())?;
+
+        world
+            .create_entity()
+            .with_component(Transform::IDENTITY)?
+            .with_component(RigidBody::new(1.0, 0.0, true))?
+            .with_component(Shape::create_rectangle(20.0, 2.0))?;
+        let ball = world
+            .create_entity()
+            .with_component(Transform::from_xy(-3.0, 3.0))?
+            .with_component(RigidBody::new(1.0, 0.0, false))?
+            .with_component(Shape::Circle { radius: 1.0 })?
+            .entity();
+        let crate_box = world
+            .create_entity()
+            .with_component(Transform::from_xy(3.0, 3.0))?
+            .with_component(RigidBody::new(1.0, 0.0, false))?
+            .with_component(Shape::create_rectangle(2.0, 2.0))?
+            .entity();
+
+        for _ in 0..180 {
+            world.run_stage(Stage::FixedUpdate)?;
+        }
+
+        // The ground's top is at 1, and both bodies are 1 unit from their center to their bottom
+        let query = world.query::<&Transform>()?;
+        assert_approx_eq!(query.get(ball).unwrap().position.y, 2.0, 0.05);
+        assert_approx_eq!(query.get(crate_box).unwrap().position.y, 2.0, 0.05);
+
+        // The box lands flat, on two contact points, so it doesn't tip over
+        assert_approx_eq!(query.get(crate_box).unwrap().rotation.as_radians(), 0.0, 0.01);
+
+        Ok(())
+    }
+}
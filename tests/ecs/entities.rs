@@ -1,6 +1,6 @@
-use std::{any::Any, cell::RefCell, rc::Rc};
+use std::{any::Any, cell::RefCell};
 
-use axle_ecs::World;
+use axle_ecs::{entities::entity::Entity, World};
 use eyre::Result;
 
 #[test]
@@ -38,13 +38,13 @@ fn query_for_entities() -> Result<()> {
         .with_component(Size(12.0))?;
 
     let query = world
-        .query()
+        .query_builder()
         .with_component::<Location>()?
         .with_component::<Size>()?
         .run();
 
-    let locations: &Vec<Rc<RefCell<dyn Any>>> = &query.1[0];
-    let sizes: &Vec<Rc<RefCell<dyn Any>>> = &query.1[1];
+    let locations: &Vec<&RefCell<dyn Any>> = &query.1[0];
+    let sizes: &Vec<&RefCell<dyn Any>> = &query.1[1];
 
     assert_eq!(locations.len(), sizes.len());
     assert_eq!(locations.len(), 2);
@@ -70,6 +70,45 @@ fn query_for_entities() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn typed_query_for_entities() -> Result<()> {
+    let mut world = World::new();
+    world.register_component::<Location>();
+    world.register_component::<Size>();
+
+    let first_entity = world
+        .create_entity()
+        .with_component(Location(42.0, 24.0))?
+        .with_component(Size(10.0))?
+        .entity();
+
+    world.create_entity().with_component(Size(11.0))?;
+
+    world.create_entity().with_component(Location(43.0, 25.0))?;
+
+    for (location, mut size) in world.query::<(&Location, &mut Size)>()?.iter() {
+        size.0 += location.0;
+    }
+
+    let query = world.query::<(Entity, &Size, Option<&Location>)>()?;
+    assert_eq!(query.count(), 2);
+
+    for (entity, size, location) in query.iter() {
+        if entity == first_entity {
+            assert_eq!(size.0, 52.0);
+            assert!(location.is_some());
+        }
+        else {
+            assert_eq!(size.0, 11.0);
+            assert!(location.is_none());
+        }
+    }
+
+    assert!(world.query::<(&Size, &mut Size)>().is_err());
+
+    Ok(())
+}
+
 #[test]
 fn delete_component_from_entity() -> Result<()> {
     let mut world = World::new();
@@ -77,26 +116,28 @@ fn delete_component_from_entity() -> Result<()> {
     world.register_component::<Location>();
     world.register_component::<Size>();
 
-    world
+    let first_entity = world
         .create_entity()
         .with_component(Location(10.0, 11.0))?
-        .with_component(Size(10.0))?;
+        .with_component(Size(10.0))?
+        .entity();
 
-    world
+    let second_entity = world
         .create_entity()
         .with_component(Location(20.0, 21.0))?
-        .with_component(Size(20.0))?;
+        .with_component(Size(20.0))?
+        .entity();
 
-    world.delete_component_by_entity_id::<Location>(0)?;
+    world.delete_component_by_entity_id::<Location>(first_entity)?;
 
     let query = world
-        .query()
+        .query_builder()
         .with_component::<Location>()?
         .with_component::<Size>()?
         .run();
 
     assert_eq!(query.0.len(), 1);
-    assert_eq!(query.0[0], 1);
+    assert_eq!(query.0[0], second_entity);
 
     Ok(())
 }
@@ -107,12 +148,15 @@ fn add_component_to_entity() -> Result<()> {
     world.register_component::<Location>();
     world.register_component::<Size>();
 
-    world.create_entity().with_component(Location(10.0, 15.0))?;
+    let entity = world
+        .create_entity()
+        .with_component(Location(10.0, 15.0))?
+        .entity();
 
-    world.add_component_to_entity_by_id(Size(20.0), 0)?;
+    world.add_component_to_entity_by_id(Size(20.0), entity)?;
 
     let query = world
-        .query()
+        .query_builder()
         .with_component::<Location>()?
         .with_component::<Size>()?
         .run();
@@ -128,21 +172,26 @@ fn deleting_an_entity() -> Result<()> {
     let mut world = World::new();
     world.register_component::<Location>();
     world.register_component::<Size>();
-    world.create_entity().with_component(Location(10.0, 15.0))?;
+    let entity = world
+        .create_entity()
+        .with_component(Location(10.0, 15.0))?
+        .entity();
     world.create_entity().with_component(Location(20.0, 25.0))?;
 
-    world.delete_entity_by_id(0)?;
+    world.delete_entity_by_id(entity)?;
 
-    let query = world.query().with_component::<Location>()?.run();
-    assert_eq!(query.0.len(), 1);
+    {
+        let query = world.query_builder().with_component::<Location>()?.run();
+        assert_eq!(query.0.len(), 1);
 
-    let borrowed_locations = query.1[0][0].borrow();
-    let location = borrowed_locations.downcast_ref::<Location>().unwrap();
-    assert_eq!(location.0, 20.0);
+        let borrowed_locations = query.1[0][0].borrow();
+        let location = borrowed_locations.downcast_ref::<Location>().unwrap();
+        assert_eq!(location.0, 20.0);
+    }
 
     world.create_entity().with_component(Location(30.0, 35.0))?;
 
-    let query = world.query().with_component::<Location>()?.run();
+    let query = world.query_builder().with_component::<Location>()?.run();
     let borrowed_locations = query.1[0][0].borrow();
     let location = borrowed_locations.downcast_ref::<Location>().unwrap();
     assert_eq!(location.0, 30.0);
@@ -150,6 +199,34 @@ fn deleting_an_entity() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn stale_entity_handles_do_not_address_reused_slots() -> Result<()> {
+    let mut world = World::new();
+    world.register_component::<Location>();
+    world.register_component::<Size>();
+
+    let old_entity = world
+        .create_entity()
+        .with_component(Location(10.0, 15.0))?
+        .entity();
+
+    world.delete_entity_by_id(old_entity)?;
+
+    let new_entity = world
+        .create_entity()
+        .with_component(Location(20.0, 25.0))?
+        .entity();
+
+    assert!(world.add_component_to_entity_by_id(Size(5.0), old_entity).is_err());
+    assert!(world.delete_component_by_entity_id::<Location>(old_entity).is_err());
+    assert!(world.delete_entity_by_id(old_entity).is_err());
+
+    let query = world.query_builder().with_component::<Location>()?.run();
+    assert_eq!(query.0, vec![new_entity]);
+
+    Ok(())
+}
+
 struct Location(pub f32, pub f32);
 struct Size(pub f32);
 
@@ -1,6 +1,15 @@
+use std::{cell::Cell, rc::Rc, thread::{self, ThreadId}};
 use eyre::Result;
 
-use axle_ecs::{entities::query_entity::QueryEntity, resources::Resources, World};
+use axle_ecs::{
+    commands::Commands,
+    entities::{filter::{Changed, Without}, query_entity::QueryEntity},
+    events::{EventReader, Events},
+    resources::Resources,
+    systems::{executor::Executor, param::{NonSend, NonSendMut, Res, ResMut}, schedule::Stage},
+    CustomErrors,
+    World,
+};
 
 #[test]
 fn create_system() -> Result<()> {
@@ -10,7 +19,7 @@ fn create_system() -> Result<()> {
     world.register_component::<Speed>();
 
     world
-        .create_system(&update_location)
+        .create_system(update_location)
         .with_component::<Location>()?
         .with_component::<Speed>()?;
 
@@ -30,13 +39,13 @@ fn running_system() -> Result<()> {
         .with_component(Speed(10.0))?;
 
     world
-        .create_system(&update_location)
+        .create_system(update_location)
         .with_component::<Location>()?
         .with_component::<Speed>()?;
 
     world.run_all_systems()?;
 
-    let query = world.query().with_component::<Location>()?.run();
+    let query = world.query_builder().with_component::<Location>()?.run();
 
     let locations = &query.1[0];
     let wrapped_location = locations[0].borrow();
@@ -63,7 +72,7 @@ fn delete_component_from_system() -> Result<()> {
     world.create_entity().with_component(Speed(20.0))?;
 
     world
-        .create_system(&update_speed)
+        .create_system(update_speed)
         .with_component::<Location>()?
         .with_component::<Speed>()?;
 
@@ -71,7 +80,7 @@ fn delete_component_from_system() -> Result<()> {
 
     world.run_all_systems()?;
 
-    let query = world.query().with_component::<Speed>()?.run();
+    let query = world.query_builder().with_component::<Speed>()?.run();
 
     let speeds = &query.1[0];
 
@@ -100,14 +109,14 @@ fn add_component_to_system() -> Result<()> {
 
     world.create_entity().with_component(Location(10.0, 10.0))?;
 
-    world.create_system(&update_location);
+    world.create_system(update_location);
 
     world.add_component_to_system_by_id::<Location>(0)?;
     world.add_component_to_system_by_id::<Speed>(0)?;
 
     world.run_all_systems()?;
 
-    let query = world.query().with_component::<Location>()?.run();
+    let query = world.query_builder().with_component::<Location>()?.run();
 
     let locations = &query.1[0];
 
@@ -139,13 +148,13 @@ fn delete_a_system() -> Result<()> {
     world.create_entity().with_component(Speed(20.0))?;
 
     world
-        .create_system(&update_speed)
+        .create_system(update_speed)
         .with_component::<Speed>()?;
 
     world.delete_system_by_id(0)?;
     world.run_all_systems()?;
 
-    let query = world.query().with_component::<Speed>()?.run();
+    let query = world.query_builder().with_component::<Speed>()?.run();
 
     let speeds = &query.1[0];
 
@@ -182,17 +191,17 @@ fn more_systems_at_the_same_time() -> Result<()> {
         .with_component(Speed(5.0))?;
 
     world
-        .create_system(&update_speed)
+        .create_system(update_speed)
         .with_component::<Speed>()?;
 
     world
-        .create_system(&update_location)
+        .create_system(update_location)
         .with_component::<Location>()?
         .with_component::<Speed>()?;
 
     world.run_all_systems()?;
 
-    let query = world.query().with_component::<Speed>()?.run();
+    let query = world.query_builder().with_component::<Speed>()?.run();
 
     let speeds = &query.1[0];
 
@@ -208,7 +217,7 @@ fn more_systems_at_the_same_time() -> Result<()> {
     let third_speed = wrapped_third_speed.downcast_ref::<Speed>().unwrap();
     assert_eq!(third_speed.0, 15.0);
 
-    let query = world.query().with_component::<Location>()?.run();
+    let query = world.query_builder().with_component::<Location>()?.run();
 
     let locations = &query.1[0];
 
@@ -230,10 +239,295 @@ fn more_systems_at_the_same_time() -> Result<()> {
     Ok(())
 }
 
-fn update_location(entities: &Vec<QueryEntity>, resources: &mut Resources) -> Result<()> {
+#[test]
+fn filtered_systems_and_queries() -> Result<()> {
+    let mut world = World::new();
+
+    world.register_component::<Location>();
+    world.register_component::<Speed>();
+    world.register_component::<Static>();
+
+    world
+        .create_entity()
+        .with_component(Location(0.0, 0.0))?
+        .with_component(Speed(10.0))?;
+
+    world
+        .create_entity()
+        .with_component(Location(5.0, 5.0))?
+        .with_component(Speed(10.0))?
+        .with_component(Static)?;
+
+    world
+        .create_system(update_location)
+        .with_component::<Location>()?
+        .with_component::<Speed>()?
+        .with_filter::<Without<Static>>()?;
+
+    world.run_all_systems()?;
+
+    let locations: Vec<f32> = world
+        .query::<&Location>()?
+        .iter()
+        .map(|location| location.0)
+        .collect();
+    assert!(locations.contains(&10.0) && locations.contains(&5.0));
+
+    // Only the location moved by the system changed during the last frame
+    world.run_all_systems()?;
+    let query = world.query_filtered::<&Location, Changed<Location>>()?;
+    assert_eq!(query.count(), 1);
+    assert_eq!(query.iter().next().unwrap().0, 20.0);
+
+    Ok(())
+}
+
+#[test]
+fn systems_queue_structural_changes() -> Result<()> {
+    let mut world = World::new();
+
+    world.register_component::<Location>();
+    world.register_component::<Speed>();
+
+    world
+        .create_entity()
+        .with_component(Location(0.0, 0.0))?
+        .with_component(Speed(10.0))?;
+
+    world.create_entity().with_component(Location(50.0, 50.0))?;
+
+    // Spawns a bullet from every moving entity, and despawns the entities that went too far
+    world
+        .create_system(shoot_and_despawn)
+        .with_component::<Location>()?;
+
+    world
+        .create_system(update_location)
+        .with_component::<Location>()?
+        .with_component::<Speed>()?;
+
+    world.run_all_systems()?;
+
+    // The bullet spawned by the first system was already moved by the second one
+    let query = world.query::<(&Location, Option<&Speed>)>()?;
+    assert_eq!(query.count(), 2);
+    assert!(query.iter().all(|(location, _)| location.0 == 10.0));
+
+    Ok(())
+}
+
+#[test]
+fn systems_run_by_stage_and_constraints() -> Result<()> {
+    let mut world = World::new();
+    world.add_resource(Vec::<&'static str>::new());
+
+    world
+        .create_system(|_: &Vec<QueryEntity>, resources: &mut Resources| log(resources, "render"))
+        .in_stage(Stage::Render)?;
+    world
+        .create_system(|_: &Vec<QueryEntity>, resources: &mut Resources| log(resources, "physics"))
+        .with_name("physics")?
+        .after("movement")?;
+    world
+        .create_system(|_: &Vec<QueryEntity>, resources: &mut Resources| log(resources, "input"))
+        .in_stage(Stage::PreUpdate)?;
+    world
+        .create_system(|_: &Vec<QueryEntity>, resources: &mut Resources| log(resources, "movement"))
+        .with_name("movement")?;
+    world
+        .create_system(|_: &Vec<QueryEntity>, resources: &mut Resources| log(resources, "paused"))
+        .run_if(|resources: &Resources| resources.get_ref::<bool>().is_some_and(|paused| *paused))?;
+
+    world.run_all_systems()?;
+
+    let logs = world.get_resource::<Vec<&'static str>>().unwrap();
+    assert_eq!(*logs, vec!["input", "movement", "physics", "render"]);
+
+    Ok(())
+}
+
+#[test]
+fn systems_with_declared_access() -> Result<()> {
+    let mut world = World::new();
+    world.systems.set_executor(Executor::SingleThreaded);
+    world.add_resource(0_u32);
+
+    world.register_component::<Location>();
+    world.register_component::<Speed>();
+
+    world
+        .create_entity()
+        .with_component(Location(0.0, 0.0))?
+        .with_component(Speed(10.0))?;
+
+    // Both systems only read the speeds, so they run in the same batch
+    world
+        .create_system(update_location)
+        .with_access::<(&mut Location, &Speed)>()?;
+    world
+        .create_system(|entities: &Vec<QueryEntity>, resources: &mut Resources| {
+            *resources.get_mut::<u32>().unwrap() += entities.len() as u32;
+            Ok(())
+        })
+        .with_access::<&Speed>()?
+        .with_resource_mut::<u32>()?;
+
+    world.run_all_systems()?;
+    world.run_all_systems()?;
+
+    let query = world.query::<&Location>()?;
+    assert!(query.iter().all(|location| location.0 == 20.0));
+    assert_eq!(*world.get_resource::<u32>().unwrap(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn systems_communicate_through_events() -> Result<()> {
+    let mut world = World::new();
+    world.add_event::<Location>();
+    world.add_resource(Vec::<f32>::new());
+
+    // The reader runs before the writer, so it sees the events of a frame in the next one
+    let mut reader = EventReader::<Location>::default();
+    world.create_system(move |_: &Vec<QueryEntity>, resources: &mut Resources| {
+        let seen: Vec<f32> = reader
+            .read(resources.get_ref::<Events<Location>>().unwrap())
+            .map(|location| location.0)
+            .collect();
+        resources.get_mut::<Vec<f32>>().unwrap().extend(seen);
+
+        Ok(())
+    });
+
+    let mut frame = 0.0;
+    world.create_system(move |_: &Vec<QueryEntity>, resources: &mut Resources| {
+        frame += 1.0;
+        resources.get_mut::<Events<Location>>().unwrap().send(Location(frame, frame));
+
+        Ok(())
+    });
+
+    world.run_all_systems()?;
+    assert!(world.get_resource::<Vec<f32>>().unwrap().is_empty());
+
+    world.run_all_systems()?;
+    world.run_all_systems()?;
+    assert_eq!(*world.get_resource::<Vec<f32>>().unwrap(), vec![1.0, 2.0]);
+
+    // Only the events of the last two frames are kept
+    assert_eq!(world.get_resource::<Events<Location>>().unwrap().len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn systems_declare_resources_as_parameters() -> Result<()> {
+    let mut world = World::new();
+    world.add_resource(Gravity(2.0));
+    world.add_resource(Vec::<f32>::new());
+
+    world
+        .create_entity()
+        .with_component(Location(0.0, 10.0))?
+        .with_component(Speed(1.0))?;
+
+    world
+        .create_system(fall)
+        .with_component::<Location>()?
+        .with_component::<Speed>()?;
+    world.create_system(|_: &Vec<QueryEntity>, mut commands: ResMut<Commands>| {
+        commands.create_entity().with_component(Location(0.0, 0.0))?;
+        Ok(())
+    });
+
+    world.run_all_systems()?;
+
+    assert_eq!(*world.get_resource::<Vec<f32>>().unwrap(), vec![8.0]);
+    assert_eq!(world.query::<&Location>()?.count(), 2);
+
+    // The system fails without running when one of its resources is missing
+    world.delete_resource::<Gravity>();
+    let error = world.run_all_systems().unwrap_err();
+
+    assert!(matches!(error.downcast_ref(), Some(CustomErrors::ResourceInSystemDoesNotExist)));
+    assert_eq!(world.get_resource::<Vec<f32>>().unwrap().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn systems_borrow_non_send_resources() -> Result<()> {
+    let mut world = World::new();
+    world.add_non_send_resource(Rc::new(Cell::new(0_u32)));
+    world.add_non_send_resource(thread::current().id());
+    world.add_resource(Gravity(2.0));
+
+    world.create_system(|_: &Vec<QueryEntity>, frames: NonSend<Rc<Cell<u32>>>, thread: NonSend<ThreadId>| {
+        // The non-send resources never leave the thread that runs the world
+        assert_eq!(*thread, thread::current().id());
+        frames.set(frames.get() + 1);
+        Ok(())
+    });
+    world.create_system(|_: &Vec<QueryEntity>, mut frames: NonSendMut<Rc<Cell<u32>>>, gravity: Res<Gravity>| {
+        *frames = Rc::new(Cell::new(frames.get() * gravity.0 as u32));
+        Ok(())
+    });
+
+    world.run_all_systems()?;
+    world.run_all_systems()?;
+
+    assert_eq!(world.get_non_send_resource::<Rc<Cell<u32>>>().unwrap().get(), 6);
+
+    // Like the regular ones, missing non-send resources make the system fail without running
+    world.delete_non_send_resource::<ThreadId>();
+    let error = world.run_all_systems().unwrap_err();
+
+    assert!(matches!(error.downcast_ref(), Some(CustomErrors::ResourceInSystemDoesNotExist)));
+    assert_eq!(world.get_non_send_resource::<Rc<Cell<u32>>>().unwrap().get(), 6);
+
+    Ok(())
+}
+
+fn log(resources: &mut Resources, message: &'static str) -> Result<()> {
+    resources.get_mut::<Vec<&'static str>>().unwrap().push(message);
+    Ok(())
+}
+
+fn shoot_and_despawn(entities: &Vec<QueryEntity>, resources: &mut Resources) -> Result<()> {
+    let commands = resources.get_mut::<Commands>().unwrap();
+
+    for entity in entities {
+        let location = entity.get_component::<Location>()?;
+
+        if location.0 > 20.0 {
+            commands.delete_entity_by_id(entity.id);
+        }
+        else if entity.get_component::<Speed>().is_ok() {
+            commands
+                .create_entity()
+                .with_component(Location(location.0, location.1))?
+                .with_component(Speed(10.0))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn fall(entities: &Vec<QueryEntity>, gravity: Res<Gravity>, mut heights: ResMut<Vec<f32>>) -> Result<()> {
     for entity in entities {
         let mut location = entity.get_component_mut::<Location>()?;
-        let speed = entity.get_component::<Speed>()?;
+
+        location.1 -= gravity.0;
+        heights.push(location.1);
+    }
+
+    Ok(())
+}
+
+fn update_location(entities: &Vec<QueryEntity>, _resources: &mut Resources) -> Result<()> {
+    for entity in entities {
+        let (mut location, speed) = entity.get_components::<(&mut Location, &Speed)>()?;
 
         location.0 += speed.0;
         location.1 += speed.0;
@@ -242,7 +536,7 @@ fn update_location(entities: &Vec<QueryEntity>, resources: &mut Resources) -> Re
     Ok(())
 }
 
-fn update_speed(entities: &Vec<QueryEntity>, resources: &mut Resources) -> Result<()> {
+fn update_speed(entities: &Vec<QueryEntity>, _resources: &mut Resources) -> Result<()> {
     for entity in entities {
         let mut speed = entity.get_component_mut::<Speed>()?;
         speed.0 += 10.0;
@@ -253,4 +547,6 @@ fn update_speed(entities: &Vec<QueryEntity>, resources: &mut Resources) -> Resul
 
 struct Location(pub f32, pub f32);
 struct Speed(pub f32);
+struct Static;
+struct Gravity(pub f32);
 
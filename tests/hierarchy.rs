@@ -0,0 +1,122 @@
+use assert_approx_eq::assert_approx_eq;
+use eyre::Result;
+
+use axle_engine::axle_ecs::{scene::Scene, World};
+use axle_engine::axle_math::{rotation::Rot2, transform::{GlobalTransform, Transform}, vector::Vector2};
+use axle_engine::hierarchy;
+
+#[test]
+fn transforms_are_propagated_down_the_hierarchy() -> Result<()> {
+    let mut world = World::new();
+    hierarchy::new(&mut world)?;
+
+    let character = world
+        .create_entity()
+        .with_component(Transform { position: Vector2::new(10.0, 0.0), rotation: Rot2::FRAC_PI_2 })?
+        .with_component(GlobalTransform::IDENTITY)?
+        .entity();
+    let weapon = world
+        .create_entity()
+        .with_component(Transform::from_xy(2.0, 0.0))?
+        .with_component(GlobalTransform::IDENTITY)?
+        .entity();
+    let sight = world
+        .create_entity()
+        .with_component(Transform::from_xy(1.0, 0.0))?
+        .with_component(GlobalTransform::IDENTITY)?
+        .entity();
+
+    world.set_parent(weapon, character)?;
+    world.set_parent(sight, weapon)?;
+    world.run_all_systems()?;
+
+    {
+        let query = world.query::<&GlobalTransform>()?;
+        let weapon_global = query.get(weapon).unwrap();
+        assert_approx_eq!(weapon_global.position.x, 10.0, 1e-5);
+        assert_approx_eq!(weapon_global.position.y, 2.0, 1e-5);
+
+        let sight_global = query.get(sight).unwrap();
+        assert_approx_eq!(sight_global.position.x, 10.0, 1e-5);
+        assert_approx_eq!(sight_global.position.y, 3.0, 1e-5);
+    }
+
+    // Detached entities are back in world space
+    world.remove_parent(weapon)?;
+    world.run_all_systems()?;
+
+    let query = world.query::<&GlobalTransform>()?;
+    assert_eq!(query.get(weapon).unwrap().position, Vector2::new(2.0, 0.0));
+    assert_eq!(query.get(sight).unwrap().position, Vector2::new(3.0, 0.0));
+
+    Ok(())
+}
+
+#[test]
+fn entities_without_global_transforms_are_given_one() -> Result<()> {
+    let mut world = World::new();
+    hierarchy::new(&mut world)?;
+
+    let car = world.create_entity().with_component(Transform::from_xy(5.0, 0.0))?.entity();
+    let wheel = world.create_entity().with_component(Transform::from_xy(0.0, -1.0))?.entity();
+
+    world.set_parent(wheel, car)?;
+    world.run_all_systems()?;
+
+    // The wheel isn't treated as a root entity even though its parent had no global transform
+    let query = world.query::<&GlobalTransform>()?;
+    assert_eq!(query.get(car).unwrap().position, Vector2::new(5.0, 0.0));
+    assert_eq!(query.get(wheel).unwrap().position, Vector2::new(5.0, -1.0));
+
+    Ok(())
+}
+
+#[test]
+fn deleting_a_parent_deletes_its_descendants() -> Result<()> {
+    let mut world = World::new();
+
+    let car = world.create_entity().entity();
+    let wheel = world.create_entity().entity();
+    let bolt = world.create_entity().entity();
+    world.set_parent(wheel, car)?;
+    world.set_parent(bolt, wheel)?;
+
+    world.delete_entity_recursive(car)?;
+
+    assert!(!world.entities.is_alive(wheel));
+    assert!(!world.entities.is_alive(bolt));
+
+    Ok(())
+}
+
+#[test]
+fn transforms_are_kept_when_saving_a_scene() -> Result<()> {
+    let mut world = World::new();
+    hierarchy::new(&mut world)?;
+
+    let ship = world
+        .create_entity()
+        .with_component(Transform::from_xy(5.0, 5.0))?
+        .with_component(GlobalTransform::IDENTITY)?
+        .entity();
+    let turret = world
+        .create_entity()
+        .with_component(Transform::from_xy(0.0, 1.0))?
+        .with_component(GlobalTransform::IDENTITY)?
+        .entity();
+    world.set_parent(turret, ship)?;
+
+    let scene = Scene::from_json(&world.save_scene()?.to_json()?)?;
+
+    let mut loaded = World::new();
+    hierarchy::new(&mut loaded)?;
+    let created = loaded.load_scene(&scene)?;
+    loaded.run_all_systems()?;
+
+    assert_eq!(loaded.entities.get_parent(created[1]), Some(created[0]));
+
+    let query = loaded.query::<&GlobalTransform>()?;
+    assert_eq!(query.get(created[1]).unwrap().position, Vector2::new(5.0, 6.0));
+
+    Ok(())
+}